@@ -9,6 +9,7 @@
   let font-color = rgb(prefs.at("font_color", default: "#000000"))
   let accent = rgb(prefs.at("accent_color", default: "#000000"))
   let heading-scale = prefs.at("heading_scale", default: 1.0)
+  let start-side = if prefs.at("dir", default: "ltr") == "rtl" { "right" } else { "left" }
   
   // Typography
   set text(
@@ -67,8 +68,8 @@
   // Academic blockquotes with subtle gray background
   show quote: it => block(
     fill: rgb(250, 250, 250),
-    stroke: (left: 2pt + rgb(150, 150, 150)),
-    inset: (left: 16pt, rest: 10pt),
+    stroke: ((start-side): 2pt + rgb(150, 150, 150)),
+    inset: ((start-side): 16pt, rest: 10pt),
     radius: 1pt,
     width: 100%,
   )[
@@ -9,6 +9,7 @@
   let font-color = rgb(prefs.at("font_color", default: "#1f2937"))
   let accent = rgb(prefs.at("accent_color", default: "#df0808"))
   let heading-scale = prefs.at("heading_scale", default: 1.25)
+  let start-side = if prefs.at("dir", default: "ltr") == "rtl" { "right" } else { "left" }
   
   // Typography - playful mix
   set text(
@@ -28,18 +29,13 @@
   set page(
     paper: prefs.papersize,
     fill: page-bg,
-    margin: (
-      left: 2.5cm,
-      right: 2.5cm,
-      top: 3cm,
-      bottom: 3cm,
-    ),
+    margin: (x: 2.5cm, y: 3cm),
   )
   
   // Headings - First level (vibrant accent)
   show heading.where(level: 1): it => {
     set text(font: main-font, weight: 900, size: 36pt * heading-scale, fill: accent)
-    set align(left)
+    set align(start)
     block(above: 24pt, below: 16pt)[
       #it.body
       #v(4pt)
@@ -81,8 +77,8 @@
   // Blockquotes with warm colored background
   show quote: it => block(
     fill: rgb(255, 250, 240),
-    stroke: (left: 4pt + rgb(221, 171, 53)),
-    inset: (left: 22pt, rest: 14pt),
+    stroke: ((start-side): 4pt + rgb(221, 171, 53)),
+    inset: ((start-side): 22pt, rest: 14pt),
     radius: 6pt,
     width: 100%,
   )[
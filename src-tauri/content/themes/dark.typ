@@ -9,6 +9,7 @@
   let font-color = rgb(prefs.at("font_color", default: "#ffffff"))
   let accent = rgb(prefs.at("accent_color", default: "#ffffff"))
   let heading-scale = prefs.at("heading_scale", default: 1.1)
+  let start-side = if prefs.at("dir", default: "ltr") == "rtl" { "right" } else { "left" }
   
   // Typography - Light text on dark
   set text(
@@ -27,12 +28,7 @@
   
   set page(
     paper: prefs.papersize,
-    margin: (
-      left: 2.5cm,
-      right: 2.5cm,
-      top: 3cm,
-      bottom: 3cm,
-    ),
+    margin: (x: 2.5cm, y: 3cm),
     fill: page-bg,
   )
   
@@ -74,8 +70,8 @@
   // Blockquotes with darker background panel
   show quote: it => block(
     fill: rgb(35, 35, 35),
-    stroke: (left: 3pt + accent.transparentize(50%)),
-    inset: (left: 20pt, rest: 14pt),
+    stroke: ((start-side): 3pt + accent.transparentize(50%)),
+    inset: ((start-side): 20pt, rest: 14pt),
     radius: 4pt,
     width: 100%,
   )[
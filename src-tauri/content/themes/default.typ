@@ -9,6 +9,8 @@
   let font-color = rgb(prefs.at("font_color", default: "#000000"))
   let accent = rgb(prefs.at("accent_color", default: "#2d3e50"))
   let heading-scale = prefs.at("heading_scale", default: 1.0)
+  // Side where text lines start (right in right-to-left documents)
+  let start-side = if prefs.at("dir", default: "ltr") == "rtl" { "right" } else { "left" }
   
   set page(paper: prefs.papersize, fill: page-bg, margin: (x: 2.5cm, y: 3cm))
   set text(font: main-font, size: font-size, lang: "tr", fill: font-color, fallback: true)
@@ -67,11 +69,11 @@
     )
   )
 
-  // Blockquotes with subtle background and left border
+  // Blockquotes with subtle background and a border where lines start
   show quote: it => block(
     fill: luma(248),
-    stroke: (left: 3pt + accent.lighten(40%)),
-    inset: (left: 18pt, rest: 12pt),
+    stroke: ((start-side): 3pt + accent.lighten(40%)),
+    inset: ((start-side): 18pt, rest: 12pt),
     radius: 3pt,
     width: 100%,
   )[
@@ -9,6 +9,7 @@
   let font-color = rgb(prefs.at("font_color", default: "#2d2d2d"))
   let accent = rgb(prefs.at("accent_color", default: "#192337"))
   let heading-scale = prefs.at("heading_scale", default: 1.15)
+  let start-side = if prefs.at("dir", default: "ltr") == "rtl" { "right" } else { "left" }
   
   set page(
     paper: prefs.papersize,
@@ -83,8 +84,8 @@
   // Blockquotes with elegant cream background
   show quote: it => block(
     fill: rgb(252, 252, 248),
-    stroke: (left: 2pt + accent.lighten(30%)),
-    inset: (left: 24pt, rest: 14pt),
+    stroke: ((start-side): 2pt + accent.lighten(30%)),
+    inset: ((start-side): 24pt, rest: 14pt),
     radius: 2pt,
     width: 100%,
   )[
@@ -9,6 +9,7 @@
   let font-color = rgb(prefs.at("font_color", default: "#0a0a0a"))
   let accent = rgb(prefs.at("accent_color", default: "#0064b4"))
   let heading-scale = prefs.at("heading_scale", default: 1.3)
+  let start-side = if prefs.at("dir", default: "ltr") == "rtl" { "right" } else { "left" }
   
   set page(
     paper: prefs.papersize,
@@ -41,7 +42,7 @@
         width: 100%,
         inset: (x: 0pt, y: 12pt),
       )[
-        #align(left)[
+        #align(start)[
           #upper(it)
         ]
       ]
@@ -62,8 +63,8 @@
     )
     block(above: 20pt, below: 14pt)[
       #box(
-        inset: (left: 16pt, y: 6pt),
-        stroke: (left: 6pt + accent.lighten(20%)),
+        inset: ((start-side): 16pt, y: 6pt),
+        stroke: ((start-side): 6pt + accent.lighten(20%)),
       )[#it]
     ]
   }
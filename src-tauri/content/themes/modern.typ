@@ -9,6 +9,7 @@
   let font-color = rgb(prefs.at("font_color", default: "#0f172a"))
   let accent = rgb(prefs.at("accent_color", default: "#323232"))
   let heading-scale = prefs.at("heading_scale", default: 1.1)
+  let start-side = if prefs.at("dir", default: "ltr") == "rtl" { "right" } else { "left" }
   
   // Typography
   set text(
@@ -28,12 +29,7 @@
   set page(
     paper: prefs.papersize,
     fill: page-bg,
-    margin: (
-      left: 3cm,
-      right: 3cm,
-      top: 3cm,
-      bottom: 3cm,
-    ),
+    margin: (x: 3cm, y: 3cm),
   )
   
   // Headings - First level
@@ -76,8 +72,8 @@
   // Blockquotes with sleek background
   show quote: it => block(
     fill: rgb(248, 250, 252),
-    stroke: (left: 3pt + accent.lighten(50%)),
-    inset: (left: 18pt, rest: 14pt),
+    stroke: ((start-side): 3pt + accent.lighten(50%)),
+    inset: ((start-side): 18pt, rest: 14pt),
     radius: 4pt,
     width: 100%,
   )[
@@ -9,6 +9,7 @@
   let font-color = rgb(prefs.at("font_color", default: "#1a1a1a"))
   let accent = rgb(prefs.at("accent_color", default: "#2a2a2a"))
   let heading-scale = prefs.at("heading_scale", default: 1.05)
+  let start-side = if prefs.at("dir", default: "ltr") == "rtl" { "right" } else { "left" }
   
   // Typography - Informal
   set text(
@@ -27,14 +28,10 @@
   
   set page(
     paper: prefs.papersize,
-    margin: (
-      left: 3.5cm,
-      right: 3cm,
-      top: 3cm,
-      bottom: 3cm,
-    ),
+    // The wider margin is on the side lines start from
+    margin: ((start-side): 3.5cm, rest: 3cm),
     fill: page-bg,
-    background: place(top + left, rect(
+    background: place(top + if start-side == "right" { right } else { left }, rect(
       width: 100%,
       height: 100%,
       stroke: (thickness: 8pt, paint: rgb("#b8926a"), dash: "densely-dotted"),
@@ -83,13 +80,13 @@
   show quote: it => block(
     fill: rgb("#ebe3d0"),
     stroke: (
-      left: (
+      (start-side): (
         paint: rgb("#a0826d"),
         thickness: 3pt,
         dash: "dotted"
       )
     ),
-    inset: (left: 22pt, rest: 14pt),
+    inset: ((start-side): 22pt, rest: 14pt),
     radius: 0pt,
     width: 100%,
   )[
@@ -137,3 +134,16 @@
   
   doc
 }
+
+// Custom classes for fenced divs and spans: `::: sticky` pins a note,
+// `[text]{.ink}` writes in red ink.
+#let notebook_classes = (
+  "sticky": (body, attrs) => block(
+    fill: rgb("#fff3a8"),
+    stroke: (bottom: 2pt + rgb("#e0c96a")),
+    inset: 12pt,
+    width: 100%,
+    body,
+  ),
+  "ink": (body, attrs) => text(fill: rgb("#9b2226"), body),
+)
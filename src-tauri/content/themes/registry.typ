@@ -8,7 +8,7 @@
 #import "creative.typ": creative_theme
 #import "modern.typ": modern_theme
 #import "serif.typ": serif_theme
-#import "notebook.typ": notebook_theme
+#import "notebook.typ": notebook_theme, notebook_classes
 #import "dark.typ": dark_theme
 
 #let theme-map = (
@@ -29,3 +29,15 @@
 #let get-theme(id) = {
   theme-map.at(id, default: default_theme)
 }
+
+// Handlers for custom fenced div / span classes (`::: {.sticky}`,
+// `[text]{.sticky}`), keyed by theme id. A theme exports a dictionary of
+// `class: (body, attrs) => content`; `attrs` holds the key=value pairs
+// written in the attribute block.
+#let class-map = (
+  "notebook": notebook_classes,
+)
+
+#let get-theme-classes(id) = {
+  class-map.at(id, default: (:))
+}
@@ -29,12 +29,7 @@
   set page(
     paper: prefs.papersize,
     fill: page-bg,
-    margin: (
-      left: 3.5cm,
-      right: 3.5cm,
-      top: 3.5cm,
-      bottom: 3.5cm,
-    ),
+    margin: (x: 3.5cm, y: 3.5cm),
     numbering: "1",
     number-align: center,
   )
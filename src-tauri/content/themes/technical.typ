@@ -9,6 +9,7 @@
   let font-color = rgb(prefs.at("font_color", default: "#1e293b"))
   let accent = rgb(prefs.at("accent_color", default: "#006496"))
   let heading-scale = prefs.at("heading_scale", default: 1.0)
+  let start-side = if prefs.at("dir", default: "ltr") == "rtl" { "right" } else { "left" }
   
   set page(
     paper: prefs.papersize,
@@ -80,8 +81,8 @@
   // Blockquotes with technical code-style background
   show quote: it => block(
     fill: accent.lighten(92%),
-    stroke: (left: 3pt + accent),
-    inset: (left: 16pt, rest: 12pt),
+    stroke: ((start-side): 3pt + accent),
+    inset: ((start-side): 16pt, rest: 12pt),
     radius: 2pt,
     width: 100%,
   )[
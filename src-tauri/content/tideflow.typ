@@ -1,12 +1,166 @@
 // Main Tideflow Typst template (lean, preference-driven)
 #import "@preview/cmarker:0.1.6": render
-#import "themes/registry.typ": get-theme
+#import "themes/registry.typ": get-theme, get-theme-classes
 
 #let prefs = json("prefs.json")
+// Front matter of the document, written by the Rust side
+#let meta = json("meta.json")
+// Document class from the front matter (`documentclass:` or `class:`):
+// "article" (default), "report", "book", "letter" or "memo"
+#let doc-class = lower(str(meta.at("documentclass", default: meta.at("class", default: "article"))))
+#let letter-like = doc-class in ("letter", "memo")
+// Document language: the front matter (`lang: de-DE`, or `language:` and
+// `region:`) wins over the preferences (checked in Rust). Codes Typst would
+// reject fall back to English.
+#let lang-tag = meta.at("lang", default: meta.at("language", default: none))
+#let lang-parts = if type(lang-tag) == str { lang-tag.trim().replace("_", "-").split("-") } else { () }
+#let doc-lang = lower(if lang-parts.len() > 0 { lang-parts.first() } else { prefs.at("language", default: "en") })
+#let doc-lang = if doc-lang.match(regex("^[a-z]{2,3}$")) != none { doc-lang } else { "en" }
+#let region-tag = if lang-parts.len() > 1 { lang-parts.at(1) } else if lang-parts.len() > 0 {
+  meta.at("region", default: "")
+} else {
+  meta.at("region", default: prefs.at("region", default: ""))
+}
+#let doc-region = if type(region-tag) == str and upper(region-tag).match(regex("^[A-Z]{2}$")) != none { upper(region-tag) } else { none }
+
+// Generated strings per language. `{n}`, `{day}`, `{month}` and `{year}` are
+// filled in; languages without a table use English.
+#let translations = (
+  en: (
+    note: "Note", info: "Info", tip: "Tip", warning: "Warning", important: "Important", caution: "Caution",
+    abstract: "Abstract", executive-summary: "Executive Summary", contents: "Contents",
+    list-of-figures: "List of Figures", list-of-tables: "List of Tables",
+    figure: "Figure", table: "Table", section: "Section", part: "Part", part-title: "Part {n}", version: "Version {n}",
+    memorandum: "Memorandum", to: "To:", from: "From:", cc: "CC:", date: "Date:", subject: "Subject:", closing: "Sincerely,",
+    months: ("January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"),
+    date-long: "{month} {day}, {year}", date-day-month-year: "{day} {month} {year}",
+  ),
+  de: (
+    note: "Hinweis", info: "Info", tip: "Tipp", warning: "Warnung", important: "Wichtig", caution: "Vorsicht",
+    abstract: "Zusammenfassung", executive-summary: "Management Summary", contents: "Inhaltsverzeichnis",
+    list-of-figures: "Abbildungsverzeichnis", list-of-tables: "Tabellenverzeichnis",
+    figure: "Abbildung", table: "Tabelle", section: "Abschnitt", part: "Teil", part-title: "Teil {n}", version: "Version {n}",
+    memorandum: "Aktennotiz", to: "An:", from: "Von:", cc: "Kopie:", date: "Datum:", subject: "Betreff:", closing: "Mit freundlichen Grüßen",
+    months: ("Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober", "November", "Dezember"),
+    date-long: "{day}. {month} {year}", date-day-month-year: "{day}. {month} {year}",
+  ),
+  fr: (
+    note: "Remarque", info: "Info", tip: "Astuce", warning: "Avertissement", important: "Important", caution: "Attention",
+    abstract: "Résumé", executive-summary: "Synthèse", contents: "Table des matières",
+    list-of-figures: "Table des figures", list-of-tables: "Liste des tableaux",
+    figure: "Figure", table: "Tableau", section: "Section", part: "Partie", part-title: "Partie {n}", version: "Version {n}",
+    memorandum: "Note de service", to: "À :", from: "De :", cc: "Copie :", date: "Date :", subject: "Objet :", closing: "Cordialement,",
+    months: ("janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre", "novembre", "décembre"),
+    date-long: "{day} {month} {year}", date-day-month-year: "{day} {month} {year}",
+  ),
+  ja: (
+    note: "注記", info: "情報", tip: "ヒント", warning: "警告", important: "重要", caution: "注意",
+    abstract: "概要", executive-summary: "エグゼクティブサマリー", contents: "目次",
+    list-of-figures: "図目次", list-of-tables: "表目次",
+    figure: "図", table: "表", section: "節", part: "部", part-title: "第{n}部", version: "バージョン {n}",
+    memorandum: "社内連絡", to: "宛先：", from: "差出人：", cc: "写し：", date: "日付：", subject: "件名：", closing: "以上",
+    months: ("1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月"),
+    date-long: "{year}年{month}{day}日", date-day-month-year: "{year}年{month}{day}日",
+  ),
+  ko: (
+    note: "참고", info: "정보", tip: "팁", warning: "경고", important: "중요", caution: "주의",
+    abstract: "초록", executive-summary: "요약", contents: "목차",
+    list-of-figures: "그림 목차", list-of-tables: "표 목차",
+    figure: "그림", table: "표", section: "절", part: "부", part-title: "제{n}부", version: "버전 {n}",
+    memorandum: "업무 연락", to: "수신:", from: "발신:", cc: "참조:", date: "날짜:", subject: "제목:", closing: "감사합니다.",
+    months: ("1월", "2월", "3월", "4월", "5월", "6월", "7월", "8월", "9월", "10월", "11월", "12월"),
+    date-long: "{year}년 {month} {day}일", date-day-month-year: "{year}년 {month} {day}일",
+  ),
+)
+#let strings = translations.at(doc-lang, default: translations.en)
+#let tr(key, n: none) = {
+  let value = strings.at(key, default: translations.en.at(key))
+  if n != none { value.replace("{n}", str(n)) } else { value }
+}
+
+// Text direction: the front matter (`dir: rtl` or `dir: ltr`), else the
+// language, else what the Rust side detected from the letters of the text.
+// Mixed-direction paragraphs are left to Unicode bidi.
+#let dir-tag = lower(str(meta.at("dir", default: "auto")))
+#let doc-dir = if dir-tag == "rtl" { rtl } else if dir-tag == "ltr" { ltr }
+  else if doc-lang in ("ar", "ckb", "dv", "fa", "he", "ps", "sd", "ug", "ur", "yi") { rtl }
+  else if meta.at("detected-dir", default: "ltr") == "rtl" { rtl }
+  else { ltr }
+// Side where lines start, for strokes and insets (which have no `start`)
+#let start-side = if doc-dir == rtl { "right" } else { "left" }
+
 #let theme-id = if "theme_id" in prefs { prefs.theme_id } else { "default" }
 
+// Safe length parsing (supports %/px/cm/mm/in/pt/em or numeric fallback).
+// Length preferences are validated and normalized in Rust; never eval them.
+#let parse-length = it => if type(it) == str {
+  if it.ends-with("%") { float(it.slice(0, -1)) * 1% }
+  else if it.ends-with("px") { float(it.slice(0, -2)) * 0.75pt } // approx CSS px→pt at 96dpi
+  else if it.ends-with("cm") { float(it.slice(0, -2)) * 1cm }
+  else if it.ends-with("mm") { float(it.slice(0, -2)) * 1mm }
+  else if it.ends-with("in") { float(it.slice(0, -2)) * 1in }
+  else if it.ends-with("pt") { float(it.slice(0, -2)) * 1pt }
+  else if it.ends-with("em") { float(it.slice(0, -2)) * 1em }
+  else {  // bare number interpret as cm for convenience
+    float(it) * 1cm
+  }
+} else { it };
+
+// Typography preferences (checked in Rust). Unset values keep the theme's.
+#let typo = prefs.at("typography", default: (:))
+#let body-size = prefs.at("font_size", default: 11) * 1pt
+#let heading-levels = typo.at("heading_levels", default: ())
+#let heading-tracking = typo.at("heading_tracking", default: "")
+
+// Font fallback chains (checked in Rust): each role's own families, then the
+// CJK and emoji ones for characters the families before them lack.
+#let font-list(key) = {
+  let value = prefs.fonts.at(key, default: ())
+  if type(value) == str { if value.trim() == "" { () } else { (value,) } } else { value }
+}
+#let script-fonts = font-list("cjk") + font-list("emoji")
+#let font-chain(..roles) = {
+  let own = roles.pos().map(font-list).flatten()
+  if own.len() > 0 { own + script-fonts } else { () }
+}
+#let main-fonts = font-chain("main", "main_fallback")
+#let mono-fonts = font-chain("mono", "mono_fallback")
+#let heading-fonts = font-chain("headings")
+#let math-fonts = font-list("math")
+// Themes read `fonts.main` and `fonts.mono`; hand them the whole chains
+#let theme-fonts = prefs.fonts
+#let theme-fonts = if main-fonts.len() > 0 { theme-fonts + (main: main-fonts) } else { theme-fonts }
+#let theme-fonts = if mono-fonts.len() > 0 { theme-fonts + (mono: mono-fonts) } else { theme-fonts }
+#let theme-prefs = prefs + (fonts: theme-fonts, dir: if doc-dir == rtl { "rtl" } else { "ltr" })
+
+// Heading fonts, sizes, weights, letter spacing and small caps. Defined
+// before the theme so that it applies inside the theme's heading rules and
+// wins over them.
+#show heading: it => {
+  let style = heading-levels.at(it.level - 1, default: (:))
+  let size = style.at("size", default: "")
+  let weight = style.at("weight", default: "")
+  // "em" sizes are relative to the body text, not to the theme's heading size
+  let size = if size == "" { none } else if size.ends-with("em") { float(size.slice(0, -2)) * body-size } else { parse-length(size) }
+  set text(font: heading-fonts) if heading-fonts.len() > 0
+  set text(size: size) if size != none
+  set text(weight: if weight.match(regex("^\\d+$")) != none { int(weight) } else { weight }) if weight != ""
+  set text(tracking: parse-length(heading-tracking)) if heading-tracking != ""
+  if typo.at("small_caps_headings", default: false) { smallcaps(it) } else { it }
+}
+
 // Apply theme to entire document using show rule
-#show: get-theme(theme-id).with(prefs)
+#show: get-theme(theme-id).with(theme-prefs)
+
+// Language and direction (after the theme, which may set its own) and
+// localized supplements of references and captions
+#set text(lang: doc-lang, region: doc-region, dir: doc-dir)
+// Code reads left to right in any document
+#show raw: set text(dir: ltr)
+#set heading(supplement: tr("section"))
+#show figure.where(kind: image): set figure(supplement: tr("figure"))
+#show figure.where(kind: table): set figure(supplement: tr("table"))
+#show math.equation: set text(font: math-fonts) if math-fonts.len() > 0
 
 #let accent-color = rgb(45, 62, 80) // Default accent
 
@@ -28,11 +182,11 @@
 )
 
 #let admonition-titles = (
-  "note": "Note",
-  "info": "Info",
-  "tip": "Tip",
-  "warning": "Warning",
-  "important": "Important"
+  "note": tr("note"),
+  "info": tr("info"),
+  "tip": tr("tip"),
+  "warning": tr("warning"),
+  "important": tr("important")
 )
 
 #let admonition(kind: str, body) = {
@@ -55,7 +209,7 @@
 #let sanitize-str = it => if type(it) == str { it.trim() } else { "" }
 
 #let cover_enabled = {
-  if "cover_page" in prefs {
+  if "cover_page" in prefs and not letter-like {
     if type(prefs.cover_page) == bool { prefs.cover_page } else { false }
   } else { false }
 }
@@ -63,50 +217,184 @@
 #let cover_title = sanitize-str(if "cover_title" in prefs { prefs.cover_title } else { "" })
 #let cover_writer = sanitize-str(if "cover_writer" in prefs { prefs.cover_writer } else { "" })
 #let cover_image = sanitize-str(if "cover_image" in prefs { prefs.cover_image } else { "" })
+
 #let cover_image_width = if "cover_image_width" in prefs { prefs.cover_image_width } else { "60%" }
 
+#let margin_x = parse-length(prefs.margin.x)
+#let margin_y = parse-length(prefs.margin.y)
+
+// Document fields shared by the cover and the header/footer placeholders.
+// The front matter wins over the cover preferences.
+#let meta-text(value) = {
+  if type(value) == str { value }
+  else if type(value) == array { value.map(meta-text).filter(v => v != "").join(", ") }
+  else if type(value) == dictionary { meta-text(value.at("name", default: "")) }
+  else if value == none { "" }
+  else { str(value) }
+}
+
+#let doc-field(keys, pref) = {
+  let found = keys.filter(k => k in meta).map(k => meta-text(meta.at(k)).trim())
+  if found.len() > 0 { found.first() } else { sanitize-str(prefs.at(pref, default: "")) }
+}
+
+// Typst date patterns the template accepts (kept in sync with
+// `is_date_format` in utils/typst.rs). `date.display` stops the compile on
+// anything else, so other patterns show the date as YYYY-MM-DD.
+#let date-pattern = regex("^(?:[^\\[\\]]|\\[(?:year(?: padding:(?:zero|space|none)| repr:(?:full|last_two))*|month(?: padding:(?:zero|space|none)| repr:(?:numerical|long|short))*|day(?: padding:(?:zero|space|none))*|weekday(?: repr:(?:long|short|sunday|monday))*)\\])*$")
+
+// "today" or YYYY-MM-DD, shown as "iso", "long" (October 5, 2025),
+// "day-month-year" (5 October 2025), "numeric" (05/10/2025) or a Typst
+// date pattern; any other date text, or a day that does not exist, is shown
+// as written.
+#let format-date(value, format) = {
+  let parts = value.match(regex("^(\\d{4})-(\\d{1,2})-(\\d{1,2})$"))
+  let date = if value == "today" { datetime.today() }
+    else if parts != none {
+      let (year, month, day) = parts.captures.map(int)
+      let leap = calc.rem(year, 4) == 0 and (calc.rem(year, 100) != 0 or calc.rem(year, 400) == 0)
+      let month-days = (31, if leap { 29 } else { 28 }, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31)
+      if month >= 1 and month <= 12 and day >= 1 and day <= month-days.at(month - 1) { datetime(year: year, month: month, day: day) }
+      else { none }
+    }
+    else { none }
+  // Month names come from the translation tables; Typst only knows English
+  let spelled(pattern) = tr(pattern).replace("{day}", str(date.day())).replace("{month}", tr("months").at(date.month() - 1)).replace("{year}", str(date.year()))
+  if date == none { value }
+  else if format == "long" { spelled("date-long") }
+  else if format == "day-month-year" { spelled("date-day-month-year") }
+  else if format == "numeric" { date.display("[day]/[month]/[year]") }
+  else if format.contains("[") and format.match(date-pattern) != none { date.display(format) }
+  else { date.display("[year]-[month]-[day]") }
+}
+
+#let doc-title = doc-field(("title",), "cover_title")
+#let doc-subtitle = doc-field(("subtitle",), "cover_subtitle")
+#let doc-organization = doc-field(("organization", "organisation", "institute"), "cover_organization")
+#let doc-version = doc-field(("version",), "cover_version")
+#let doc-date-format = doc-field(("date-format",), "cover_date_format")
+#let doc-date = format-date(doc-field(("date",), "cover_date"), doc-date-format)
+
+// Authors as (name, affiliation) pairs: from the front matter (`author:` as
+// text, a list, or entries with `name` and `affiliation`), else the cover
+// preferences
+#let author-entry(a) = if type(a) == dictionary {
+  (name: meta-text(a.at("name", default: "")), affiliation: meta-text(a.at("affiliation", default: a.at("affiliations", default: ""))))
+} else { (name: meta-text(a), affiliation: "") }
+#let doc-authors = {
+  let value = meta.at("author", default: meta.at("authors", default: none))
+  let from-meta = if type(value) == array { value.map(author-entry) } else if value != none { (author-entry(value),) } else { () }
+  let from-prefs = prefs.at("cover_authors", default: ()).map(author-entry)
+  let authors = if from-meta.len() > 0 { from-meta }
+    else if from-prefs.len() > 0 { from-prefs }
+    else { ((name: cover_writer, affiliation: ""),) }
+  authors.filter(a => a.name.trim() != "")
+}
+#let doc-author = if doc-authors.len() > 0 { doc-authors.map(a => a.name).join(", ") } else { "" }
+
+// Cover page. Layouts: "centered", "left" and "banner" (title on a band of
+// the accent color across the top).
+#let cover-layout = sanitize-str(prefs.at("cover_layout", default: "centered"))
+#let cover-accent = rgb(prefs.at("accent_color", default: "#2d3e50"))
+#let cover-logo = sanitize-str(prefs.at("cover_logo", default: ""))
+#let cover-background = sanitize-str(prefs.at("cover_background", default: ""))
+
+#let cover-authors(side) = if doc-authors.len() > 0 {
+  let columns = calc.min(doc-authors.len(), 3)
+  grid(
+    columns: if side == center { (1fr,) * columns } else { (auto,) * columns },
+    column-gutter: 2em,
+    row-gutter: 1em,
+    align: side,
+    ..doc-authors.map(a => {
+      text(size: 14pt)[#a.name]
+      if a.affiliation != "" { linebreak(); text(size: 10pt, fill: luma(90))[#a.affiliation] }
+    }),
+  )
+}
+
+#let cover-details = {
+  let version = if doc-version != "" { tr("version", n: doc-version) } else { "" }
+  let lines = (doc-organization, version, doc-date).filter(s => s != "")
+  if lines.len() > 0 { text(size: 11pt, fill: luma(70), lines.join(linebreak())) }
+}
+
+#let cover-logo-image = if cover-logo != "" { builtin-image(cover-logo, height: 1.5cm) } else { none }
+#let cover-main-image(side) = if cover_image != "" {
+  align(side, builtin-image(cover_image, width: parse-length(cover_image_width)))
+}
+
+#let cover-centered = [
+  #set align(center)
+  #if cover-logo-image != none [#cover-logo-image]
+  #v(3cm)
+  #cover-main-image(center)
+  #v(24pt)
+  #if doc-title != "" [#text(size: 28pt, weight: 700)[#doc-title]]
+  #if doc-subtitle != "" [
+    #v(4pt)
+    #text(size: 16pt, fill: color.mix(accent-color, rgb(0, 0, 0)))[#doc-subtitle]
+  ]
+  #v(1.5cm)
+  #cover-authors(center)
+  #v(1fr)
+  #cover-details
+]
+
+#let cover-left = [
+  #set align(start)
+  #if cover-logo-image != none [#cover-logo-image]
+  #v(4cm)
+  #line(length: 3cm, stroke: 3pt + cover-accent)
+  #v(12pt)
+  #if doc-title != "" [#text(size: 32pt, weight: 700)[#doc-title]]
+  #if doc-subtitle != "" [
+    #v(4pt)
+    #text(size: 16pt, fill: luma(70))[#doc-subtitle]
+  ]
+  #v(1.5cm)
+  #cover-authors(start)
+  #v(1cm)
+  #cover-main-image(start)
+  #v(1fr)
+  #cover-details
+]
+
+#let banner-height = 9cm
+#let cover-banner = [
+  #set align(start)
+  #place(top + left, dx: -margin_x, dy: -margin_y, block(
+    width: 100% + 2 * margin_x,
+    height: banner-height,
+    fill: cover-accent,
+    inset: (x: margin_x, top: 1.5cm, bottom: 1.5cm),
+    {
+      set text(fill: white)
+      if cover-logo-image != none { cover-logo-image }
+      align(bottom + start, {
+        if doc-title != "" { text(size: 30pt, weight: 700, doc-title) }
+        if doc-subtitle != "" { linebreak(); text(size: 15pt, doc-subtitle) }
+      })
+    },
+  ))
+  #v(banner-height - margin_y + 1.5cm)
+  #cover-authors(start)
+  #v(1cm)
+  #cover-main-image(start)
+  #v(1fr)
+  #cover-details
+]
+
 #let render_cover_page = {
   // Only render the cover when enabled in preferences. Use a code-level `if` so
   // `#` directives are not placed at the top-level of a code block.
   if cover_enabled {
-    let has_title = cover_title != ""
-    let has_writer = cover_writer != ""
-    let has_image = cover_image != ""
-
-    align(center, box(width: 100%)[
-      #v(5cm)
-      #if has_image [
-        #align(center, builtin-image(cover_image, width: eval(cover_image_width)))
-        #v(24pt)
-      ]
-      #if has_title [
-        #text(size: 28pt, weight: 700)[#cover_title]
-        #v(12pt)
-      ]
-      #if has_writer [
-        #text(size: 16pt, fill: color.mix(accent-color, rgb(0, 0, 0)))[#cover_writer]
-        #v(12pt)
-      ]
-  ])
+    if cover-layout == "banner" { cover-banner }
+    else if cover-layout == "left" { cover-left }
+    else { cover-centered }
   } else { none }
 }
 
-// Safe margin parsing (supports cm/mm/in/pt or numeric fallback)
-#let parse-length = it => if type(it) == str {
-  if it.ends-with("%") { float(it.slice(0, -1)) * 1% }
-  else if it.ends-with("px") { float(it.slice(0, -2)) * 0.75pt } // approx CSS px→pt at 96dpi
-  else if it.ends-with("cm") { float(it.slice(0, -2)) * 1cm }
-  else if it.ends-with("mm") { float(it.slice(0, -2)) * 1mm }
-  else if it.ends-with("in") { float(it.slice(0, -2)) * 1in }
-  else if it.ends-with("pt") { float(it.slice(0, -2)) * 1pt }
-  else {  // bare number interpret as cm for convenience
-    float(it) * 1cm
-  }
-} else { it };
-
-#let margin_x = parse-length(prefs.margin.x)
-#let margin_y = parse-length(prefs.margin.y)
-
 // Basic page setup without numbering/header (will be set conditionally later)
 // Don't set columns here - let the theme handle it initially
 #set page(
@@ -120,20 +408,69 @@
 
 // Apply paragraph spacing
 #let para_spacing = if "paragraph_spacing" in prefs { 
-  eval(prefs.paragraph_spacing)
+  parse-length(prefs.paragraph_spacing)
 } else { 
   0.65em 
 }
 #set block(spacing: para_spacing)
 
+// Paragraph typography
+#let typo-justify = typo.at("justify", default: none)
+#set par(justify: typo-justify) if typo-justify != none
+#let typo-hyphenate = typo.at("hyphenate", default: none)
+#set text(hyphenate: typo-hyphenate) if typo-hyphenate != none
+#let typo-indent = typo.at("first_line_indent", default: "")
+#set par(first-line-indent: parse-length(typo-indent)) if typo-indent != ""
+// Widows and orphans are avoided by default; zero costs allow them
+#set text(costs: (widow: 0%, orphan: 0%)) if not typo.at("widow_orphan_control", default: true)
+#set text(ligatures: false) if not typo.at("ligatures", default: true)
+#set text(number-type: "old-style") if typo.at("oldstyle_figures", default: false)
+// Sticky blocks stay with the block after them (the theme wraps headings in one)
+#show heading: set block(sticky: typo.at("keep_with_next", default: true))
+
 // Store section numbering preference for later
-#let number_sections = if "numberSections" in prefs { prefs.numberSections } else { true }
+#let number_sections = if letter-like { false } else if "numberSections" in prefs { prefs.numberSections } else { true }
+#let toc-enabled = prefs.toc and not letter-like
+
+// Books start chapters and front matter sections on odd (right-hand) pages
+#let section-break = if doc-class == "book" { pagebreak(to: "odd") } else { pagebreak() }
+
+// Numbering patterns for headings and pages (checked in Rust). A word before
+// the heading pattern ("Chapter 1", "Part I") labels top-level headings only.
+#let heading-pattern = if "heading_numbering" in prefs { prefs.heading_numbering } else { "1.1" }
+#let numbering-depth = if "heading_numbering_depth" in prefs { calc.clamp(prefs.heading_numbering_depth, 1, 6) } else { 6 }
+#let page-numbering = if "page_numbering" in prefs { prefs.page_numbering } else { "1" }
+#let page-number-format = if "page_number_format" in prefs { prefs.page_number_format } else { "{page}" }
+#let roman-front-matter = if "roman_front_matter" in prefs { prefs.roman_front_matter } else { false }
+#let first-page-number = if "first_page_number" in prefs { calc.max(prefs.first_page_number, 1) } else { 1 }
+
+#let counting-symbol = regex("[1aAiIαΑ一壹あいアイא가ㄱ*]")
+#let heading-words = heading-pattern.split(" ")
+#let heading-label = if heading-words.len() > 1 and heading-words.last().contains(counting-symbol) {
+  heading-words.slice(0, -1).join(" ")
+} else { none }
+#let heading-base = {
+  let base = if heading-label != none { heading-words.last() } else { heading-pattern }
+  // With a single counting symbol the sub-levels would run together ("11")
+  if base.matches(counting-symbol).len() == 1 { base.replace(counting-symbol, m => m.text + ".1") } else { base }
+}
+#let heading-numbering(..nums) = {
+  let n = nums.pos()
+  if n.len() > numbering-depth { none }
+  else if heading-label != none and n.len() == 1 { heading-label + " " + numbering(heading-base, ..n) }
+  else { numbering(heading-base, ..n) }
+}
 
 // CRITICAL: Disable any automatic outline generation by Typst or cmarker
 #set outline(title: none)
 
-// Read markdown content
-#let md_content = read("content.md")
+// Read markdown content. Scroll-sync anchors arrive as `<!--tf-anchor ID -->`
+// comments (never raw Typst, so they can't be mistaken for the user's) and
+// become labels the preview can query.
+#let md_content = read("content.md").replace(
+  regex("<!--tf-anchor ([\\w-]+) -->"),
+  m => "<!--raw-typst #label(\"" + m.captures.at(0) + "\") -->",
+)
 
 // Determine if we need two-column layout for main content
 #let two_column_layout = if "two_column_layout" in prefs { prefs.two_column_layout } else { false }
@@ -141,49 +478,330 @@
 #let show_page_numbers = if "page_numbers" in prefs { prefs.page_numbers } else { false }
 #let show_header = if "header_title" in prefs { prefs.header_title } else { false }
 #let header_text = if "header_text" in prefs { prefs.header_text } else { "" }
+#let list_of_figures = if "list_of_figures" in prefs { prefs.list_of_figures } else { false }
+#let list_of_tables = if "list_of_tables" in prefs { prefs.list_of_tables } else { false }
 
 // Render cover in single-column mode
 #if cover_enabled [
-  #set page(columns: 1, numbering: none)
+  #set page(
+    columns: 1,
+    numbering: none,
+    background: if cover-background != "" { builtin-image(cover-background, width: 100%, height: 100%, fit: "cover") },
+  )
   #render_cover_page
-  #pagebreak()
+  #section-break
 ]
 
-// Render TOC with optional two-column layout
-#if prefs.toc [
-  #set page(columns: if toc_two_column { 2 } else { 1 }, numbering: none)
-  
-  #let has_custom_title = "toc_title" in prefs and prefs.toc_title.trim() != ""
-  #if has_custom_title [
-    #text(size: 16pt, weight: 600)[#prefs.toc_title]
+// Front matter pages are numbered i, ii, ... when enabled; the cover is not
+#let front-numbering = if roman-front-matter { "i" } else { none }
+#counter(page).update(1)
+
+// Table of contents options. Placement: "after_cover", "after_abstract"
+// (the front matter `abstract`) or "end" of the document.
+#let toc-depth = if "toc_depth" in prefs { calc.clamp(prefs.toc_depth, 1, 6) } else { 3 }
+#let toc-placement = if "toc_placement" in prefs { prefs.toc_placement } else { "after_cover" }
+#let toc-leaders = if "toc_leaders" in prefs { prefs.toc_leaders } else { true }
+#let toc-unnumbered = if "toc_unnumbered" in prefs { prefs.toc_unnumbered } else { true }
+#let toc-mini = if "toc_mini" in prefs { prefs.toc_mini } else { false }
+
+#set outline.entry(fill: none) if not toc-leaders
+// Headings past the numbered depth count as unnumbered too
+#show outline.entry: it => {
+  let el = it.element
+  let unnumbered = el.func() == heading and (el.numbering == none or el.level > numbering-depth)
+  if number_sections and not toc-unnumbered and unnumbered { none } else { it }
+}
+
+#let toc-body = [
+  // "auto" is the translated "Contents"
+  #let toc-title = if "toc_title" in prefs { prefs.toc_title.trim() } else { "" }
+  #let toc-title = if lower(toc-title) == "auto" { tr("contents") } else { toc-title }
+  #if toc-title != "" [
+    #text(size: 16pt, weight: 600, toc-title)
     #v(6pt)
   ]
   
   // Generate outline explicitly here without numbering
   #set heading(numbering: none)
-  #outline(title: none, depth: 3)
-  #pagebreak()
+  #outline(title: none, depth: toc-depth)
+]
+
+#let doc-abstract = if type(meta.at("abstract", default: none)) == str { meta.abstract.trim() } else { "" }
+#let abstract-block = if doc-abstract != "" {
+  align(center, block(width: 85%, {
+    text(weight: 600, tr("abstract"))
+    v(4pt)
+    set align(start)
+    set par(justify: true)
+    render(doc-abstract, smart-punctuation: false)
+  }))
+} else { none }
+// Reports put the abstract and the executive summary (front matter
+// `executive-summary`) on pages of their own before the TOC
+#let doc-summary = if type(meta.at("executive-summary", default: none)) == str { meta.at("executive-summary").trim() } else { "" }
+#let summary-block(title, body) = {
+  text(size: 16pt, weight: 600, title)
+  v(6pt)
+  set par(justify: true)
+  render(body, smart-punctuation: false)
+}
+#let abstract-in-front = abstract-block != none and (doc-class == "report" or (toc-enabled and toc-placement == "after_abstract"))
+
+#if abstract-in-front [
+  #set page(columns: 1, numbering: front-numbering)
+  #abstract-block
+  #section-break
+]
+
+#if doc-class == "report" and doc-summary != "" [
+  #set page(columns: 1, numbering: front-numbering)
+  #summary-block(tr("executive-summary"), doc-summary)
+  #section-break
+]
+
+// Render TOC with optional two-column layout
+#if toc-enabled and toc-placement != "end" [
+  #set page(columns: if toc_two_column { 2 } else { 1 }, numbering: front-numbering)
+  #toc-body
+  #section-break
+]
+
+// Lists of figures/tables follow the TOC on their own front-matter page
+#if list_of_figures or list_of_tables [
+  #set page(columns: 1, numbering: front-numbering)
+
+  #if list_of_figures [
+    #text(size: 16pt, weight: 600, tr("list-of-figures"))
+    #v(6pt)
+    #outline(title: none, target: figure.where(kind: image))
+  ]
+  #if list_of_figures and list_of_tables [
+    #v(18pt)
+  ]
+  #if list_of_tables [
+    #text(size: 16pt, weight: 600, tr("list-of-tables"))
+    #v(6pt)
+    #outline(title: none, target: figure.where(kind: table))
+  ]
+  #section-break
 ]
 
+// Header and footer of the main content. Each has left/center/right slots
+// whose placeholders are filled in on every page; without any slot text the
+// older `header_text` / `page_numbers` preferences apply.
+#let hf = if "header_footer" in prefs { prefs.header_footer } else { (:) }
+#let hf-slots(key) = {
+  let s = hf.at(key, default: (:))
+  (left: s.at("left", default: ""), center: s.at("center", default: ""), right: s.at("right", default: ""))
+}
+#let hf-empty(slots) = slots.values().all(v => v.trim() == "")
+#let header-slots = {
+  let s = hf-slots("header")
+  if hf-empty(s) and show_header and header_text != "" { (left: "", center: "", right: header_text) } else { s }
+}
+#let footer-slots = {
+  let s = hf-slots("footer")
+  if hf-empty(s) and show_page_numbers { (left: "", center: page-number-format, right: "") } else { s }
+}
+#let hf-logo = sanitize-str(hf.at("logo", default: ""))
+
+// Value of one placeholder on the current page; call in context.
+#let hf-field(name) = {
+  if name == "page" { counter(page).display() }
+  else if name == "total" { numbering(page-numbering, counter(page).final().first()) }
+  else if name == "title" { doc-title }
+  else if name == "author" { doc-author }
+  else if name == "date" { if doc-date != "" { doc-date } else { format-date("today", doc-date-format) } }
+  else if name == "chapter" {
+    // The chapter starting on this page, else the one we are in
+    let here-page = here().page()
+    let starts = query(heading.where(level: 1)).filter(h => h.location().page() == here-page)
+    let before = query(heading.where(level: 1).before(here()))
+    let h = if starts.len() > 0 { starts.first() } else if before.len() > 0 { before.last() } else { none }
+    if h != none { h.body } else { "" }
+  }
+}
+
+#let hf-fill(template) = {
+  let parts = ()
+  let last = 0
+  for m in template.matches(regex("\\{(page|total|title|chapter|date|author)\\}")) {
+    parts.push(template.slice(last, m.start))
+    parts.push(hf-field(m.captures.first()))
+    last = m.end
+  }
+  parts.push(template.slice(last))
+  parts.join()
+}
+
+// One header or footer row; `logo` goes before the left or after the right
+// slot. Right-to-left documents mirror the row: "left" is where lines start.
+#let hf-row(slots, even, logo: none) = {
+  let mirror = even and hf.at("mirror_even_pages", default: false)
+  let first = hf-fill(if mirror { slots.right } else { slots.left })
+  let last = hf-fill(if mirror { slots.left } else { slots.right })
+  if logo != none {
+    let side = hf.at("logo_position", default: "left")
+    if (side == "right") != mirror { last = [#last #h(0.5em) #logo] } else { first = [#logo #h(0.5em) #first] }
+  }
+  set text(size: 9pt, fill: gray)
+  grid(columns: (1fr, auto, 1fr), align: (start + horizon, center + horizon, end + horizon), first, hf-fill(slots.center), last)
+}
+
+#let hf-first-page() = {
+  let start = query(<tf-main-start>)
+  start.len() > 0 and start.first().location().page() == here().page()
+}
+
+// The first main page shows everything ("same"), only the footer
+// ("footer") or nothing ("none"). The header takes its logo with it and the
+// footer its page number. Call in context.
+#let hf-first-mode = hf.at("first_page", default: "same")
+#let hf-shows(part) = not hf-first-page() or hf-first-mode == "same" or (part == "footer" and hf-first-mode == "footer")
+
+#let hf-rule = line(length: 100%, stroke: 0.5pt + gray)
+
+#let main-header = if hf-empty(header-slots) and hf-logo == "" { none } else {
+  context {
+    if hf-shows("header") {
+      let logo = if hf-logo != "" { builtin-image(hf-logo, height: parse-length(hf.at("logo_height", default: "0.8cm"))) } else { none }
+      hf-row(header-slots, calc.even(here().page()), logo: logo)
+      if hf.at("rules", default: false) { v(-0.4em); hf-rule }
+    }
+  }
+}
+
+#let main-footer = if hf-empty(footer-slots) { none } else {
+  context {
+    if hf-shows("footer") {
+      if hf.at("rules", default: false) { hf-rule; v(-0.4em) }
+      hf-row(footer-slots, calc.even(here().page()))
+    }
+  }
+}
+
 // Set up page format for main content with proper column layout
+#let main-page-numbering = if main-footer != none or main-header != none { page-numbering } else { none }
 #set page(
-  numbering: if show_page_numbers { "1" } else { none },
-  header: if show_header and header_text != "" {
-    align(right, text(size: 9pt, fill: gray)[_#header_text _])
-  } else { none },
+  numbering: main-page-numbering,
+  header: main-header,
+  footer: main-footer,
   columns: if two_column_layout { 2 } else { 1 }
 )
+#metadata("main") <tf-main-start>
 
-// Reset page counter to start at 1 for main content
-#if show_page_numbers [
-  #counter(page).update(1)
-]
+// Restart page numbering for the main content
+#counter(page).update(first-page-number)
 
 // Apply section numbering for main content only
-#set heading(numbering: if number_sections { "1.1" } else { none })
+#set heading(numbering: if number_sections { heading-numbering } else { none })
 
-// Render markdown content with explicit outline suppression
-#show outline: none
+#if not abstract-in-front and abstract-block != none [
+  #abstract-block
+  #v(12pt)
+]
+
+// Mini table of contents after each chapter heading: its sections, with
+// page numbers
+#let mini-toc(chapter) = context {
+  let next = query(heading.where(level: 1).after(chapter.location(), inclusive: false))
+  let sections = heading.where(level: 2).after(chapter.location())
+  let sections = if next.len() > 0 { sections.before(next.first().location()) } else { sections }
+  let entries = query(sections).filter(e => e.outlined)
+  if entries.len() > 0 {
+    block(inset: ((start-side): 1em), below: 1.5em, {
+      set text(size: 0.9em)
+      for entry in entries {
+        let loc = entry.location()
+        let number = if entry.numbering != none { [#numbering(entry.numbering, ..counter(heading).at(loc)) #h(0.3em)] } else { [] }
+        let page-number = numbering(page-numbering, ..counter(page).at(loc))
+        link(loc, [#number#entry.body #box(width: 1fr, if toc-leaders { repeat[.] }) #page-number])
+        linebreak()
+      }
+    })
+  }
+}
+
+// Chapters (top-level headings) of reports and books start a new page; part
+// pages are headings too, but break pages themselves.
+#let part-supplement = [#tr("part")]
+#show heading.where(level: 1): it => {
+  let part = it.supplement == part-supplement
+  if doc-class == "book" and not part { pagebreak(to: "odd", weak: true) }
+  else if doc-class == "report" { pagebreak(weak: true) }
+  it
+  if toc-mini and not part { mini-toc(it) }
+}
+
+// Front, main and back matter (`\frontmatter`, `\mainmatter`,
+// `\backmatter`), applied with `#show: tf-matter.with(...)` by the Rust
+// preprocessor. Front matter is numbered i, ii, ..., the main matter restarts
+// at the first page number, and neither front nor back matter numbers its
+// headings.
+#let tf-matter(kind, body) = {
+  pagebreak(weak: true, to: if doc-class == "book" { "odd" } else { none })
+  if kind == "front" {
+    set page(numbering: if main-page-numbering != none { "i" } else { none })
+    set heading(numbering: none)
+    counter(page).update(1)
+    body
+  } else if kind == "main" {
+    set page(numbering: main-page-numbering)
+    set heading(numbering: if number_sections { heading-numbering } else { none })
+    counter(page).update(first-page-number)
+    counter(heading).update(0)
+    body
+  } else {
+    set heading(numbering: none)
+    body
+  }
+}
+
+// Letters and memos: address blocks, subject and signature from the front
+// matter (`from`, `to`, `cc`, `subject`, `closing`, `signature`).
+#let address-block(value) = {
+  let lines = if type(value) == array { value.map(meta-text) } else { meta-text(value).split("\n") }
+  lines.map(l => l.trim()).filter(l => l != "").join(linebreak())
+}
+#let letter-field(key) = if key in meta { address-block(meta.at(key)) } else { none }
+#let letter-from = if "from" in meta { letter-field("from") } else { letter-field("sender") }
+#let letter-to = if "to" in meta { letter-field("to") } else { letter-field("recipient") }
+#let letter-subject = doc-field(("subject",), "")
+
+#let letter-head = {
+  if letter-from != none { align(end, letter-from); v(1cm) }
+  if doc-date != "" { align(end, doc-date); v(0.8cm) }
+  if letter-to != none { letter-to; v(1cm) }
+  if letter-subject != "" { text(weight: 700, letter-subject); v(0.6cm) }
+}
+
+#let memo-head = {
+  text(size: 20pt, weight: 700, tracking: 0.15em, upper(tr("memorandum")))
+  v(0.6cm)
+  let from = if letter-from != none { letter-from } else { doc-author }
+  let rows = ((tr("to"), letter-to), (tr("from"), from), (tr("cc"), letter-field("cc")), (tr("date"), doc-date), (tr("subject"), letter-subject))
+  grid(
+    columns: (auto, 1fr),
+    column-gutter: 1em,
+    row-gutter: 0.7em,
+    ..rows.filter(r => r.at(1) != none and r.at(1) != "").map(r => (strong(r.at(0)), r.at(1))).flatten(),
+  )
+  v(0.3cm)
+  line(length: 100%, stroke: 0.75pt)
+  v(0.6cm)
+}
+
+#let letter-closing = {
+  let closing = doc-field(("closing",), "")
+  let signature = doc-field(("signature",), "")
+  let signature = if signature != "" { signature } else { doc-author }
+  v(1cm)
+  if closing != "" { closing } else { tr("closing") }
+  v(1.5cm)
+  signature
+}
+
+#if doc-class == "letter" { letter-head } else if doc-class == "memo" { memo-head }
 
 // Define fallback helpers at top-level so we don't need to pass them as
 // keyword arguments into #render (some cmarker versions reject unexpected
@@ -213,11 +831,221 @@
   }
 }
 
+// Cross-reference helpers targeted by the Rust preprocessor, which lowers
+// `{#fig:...}` labels and `@fig:...` references to these calls.
+#let tf-ref(target) = context {
+  let found = query(target)
+  if found.len() == 0 {
+    text(fill: red)[??]
+  } else if found.first().func() == heading and found.first().numbering == none {
+    // Unnumbered headings cannot be referenced by number; link by title.
+    link(target, found.first().body)
+  } else {
+    ref(target)
+  }
+}
+
+#let tf-missing-ref(name) = text(fill: red, weight: 600)[??#name]
+
+// Captions may hold references (`@fig:x`)
+#let tf-md(body) = render(body, smart-punctuation: false, scope: (tf-ref: tf-ref, tf-missing-ref: tf-missing-ref))
+
+#let tf-heading(level, body, numbered: true, outlined: true) = {
+  if numbered { heading(level: level, outlined: outlined, tf-md(body)) }
+  else { heading(level: level, numbering: none, outlined: outlined, tf-md(body)) }
+}
+
+// Book parts (`# Title {.part}`): a page of its own with "Part I" above the
+// title.
+#let tf-part(body) = {
+  pagebreak(weak: true, to: if doc-class == "book" { "odd" } else { none })
+  counter("tf-part").step()
+  v(1fr)
+  align(center, {
+    text(size: 14pt, tracking: 0.1em, context upper(tr("part-title", n: numbering("I", counter("tf-part").get().first()))))
+    v(12pt)
+    heading(level: 1, numbering: none, supplement: part-supplement, tf-md(body))
+  })
+  v(1fr)
+  pagebreak(weak: true)
+}
+
+// Code blocks included from files with line numbers and/or highlighted
+// lines. `start` is the file line of the first line shown, so numbers and
+// `highlight` refer to lines in the original file.
+#let tf-code(code, lang: none, numbers: false, start: 1, line-numbers: none, highlight: ()) = {
+  show raw.line: it => {
+    // `line-numbers` lists the file line of each code line when the
+    // selection skipped some (nested region markers).
+    let n = if line-numbers != none { line-numbers.at(it.number - 1) } else { it.number + start - 1 }
+    let body = if numbers {
+      box(width: 2.5em, align(right, text(fill: luma(140))[#n])) + h(0.8em) + it.body
+    } else { it.body }
+    if n in highlight {
+      box(width: 100%, fill: rgb(254, 249, 195), outset: (x: 2pt, y: 1.5pt), body)
+    } else { body }
+  }
+  raw(code, block: true, lang: lang)
+}
+
+// Stand-alone images, lowered by the Rust preprocessor. `width`, `height`
+// and `align` come from `{width=50% align=right}` attributes; without them the
+// Images preferences apply.
+#let default-image-width = if "default_image_width" in prefs { prefs.default_image_width } else { "80%" }
+#let default-image-align = if "default_image_alignment" in prefs { prefs.default_image_alignment } else { "center" }
+
+#let tf-image(path, alt: none, width: none, height: none, fit: "cover") = {
+  // A height on its own keeps the aspect ratio.
+  let w = if width != none { parse-length(width) } else if height != none { auto } else { parse-length(default-image-width) }
+  let h = if height != none { parse-length(height) } else { auto }
+  builtin-image(path, alt: alt, width: w, height: h, fit: fit)
+}
+
+#let tf-aligned(name, body) = {
+  let name = if name == none { default-image-align } else { name }
+  if name == "left" { align(left, body) }
+  else if name == "right" { align(right, body) }
+  else { align(center, body) }
+}
+
+// `wrap=left|right`: the image beside the paragraph that follows it.
+#let tf-wrap(side, img, body) = {
+  if side == "left" { grid(columns: (auto, 1fr), column-gutter: 1em, img, body) }
+  else { grid(columns: (1fr, auto), column-gutter: 1em, body, img) }
+}
+
+// Galleries (`::: {.gallery columns=2}`): lettered panels in a grid, as one
+// figure. `body` holds the `tf-subfigure` panels; anything else is dropped.
+#let tf-subfigure(img, caption: none) = figure(
+  img,
+  caption: caption,
+  kind: "tf-subfigure",
+  supplement: none,
+  numbering: "(a)",
+  outlined: false,
+)
+
+#let tf-panels(it) = {
+  if it.func() == figure { (it,) }
+  else if it.has("children") { it.children.map(tf-panels).flatten() }
+  else if it.has("body") { tf-panels(it.body) }
+  else if it.has("child") { tf-panels(it.child) }
+  else { () }
+}
+
+#let tf-gallery(columns: 2, caption: none, ..options, body) = {
+  let panels = tf-panels(body)
+  figure(
+    kind: image,
+    caption: caption,
+    ..options,
+    {
+      counter(figure.where(kind: "tf-subfigure")).update(0)
+      show figure.where(kind: "tf-subfigure"): set figure.caption(separator: [ ])
+      grid(columns: (1fr,) * columns, column-gutter: 1em, row-gutter: 1em, align: center + bottom, ..panels)
+    },
+  )
+}
+
+// Fenced divs (`::: {.class}`) and bracketed spans (`[text]{.class}`) are
+// lowered by the Rust preprocessor to these calls. Each class is applied in
+// turn, the first one outermost; a theme's own handlers (see
+// themes/registry.typ) win over the built-in ones, and unknown classes are
+// ignored.
+#let theme-classes = get-theme-classes(theme-id)
+
+// Quarto callouts (`::: {.callout-tip title="..."}`) in the admonition
+// colours, plus Quarto's `caution` kind.
+#let callout-colors = admonition-colors + ("caution": (fill: rgb(254, 226, 226), stroke: rgb(252, 165, 165)))
+#let callout-titles = admonition-titles + ("caution": tr("caution"))
+
+#let callout(kind, title, body) = {
+  let key = lower(kind)
+  let palette = callout-colors.at(key, default: callout-colors.at("note"))
+  let title = if title != none { title } else { callout-titles.at(key, default: upper(kind)) }
+  block(
+    fill: palette.fill,
+    stroke: 0.5pt + palette.stroke,
+    inset: 10pt,
+    radius: 8pt,
+    spacing: 12pt,
+    width: 100%,
+  )[
+    #text(weight: 600)[#title]
+    #v(4pt, weak: true)
+    #body
+  ]
+}
+
+#let tf-div-class(class, attrs, body) = {
+  if class in theme-classes { return (theme-classes.at(class))(body, attrs) }
+  if class.starts-with("callout-") {
+    callout(class.slice(8), attrs.at("title", default: none), body)
+  } else if class == "columns" {
+    // Columns are the `column` blocks directly inside; see below.
+    let kids = if body.has("children") { body.children } else { (body,) }
+    let cols = kids.filter(k => k.func() == block)
+    if cols.len() == 0 { body } else {
+      grid(columns: cols.map(c => c.at("width", default: 1fr)), column-gutter: 1.5em, ..cols.map(c => c.at("body", default: none)))
+    }
+  } else if class == "column" {
+    if "width" in attrs { block(width: parse-length(attrs.width), body) } else { block(body) }
+  } else if class == "landscape" {
+    page(flipped: true, body)
+  } else if class == "pagebreak" {
+    pagebreak()
+    body
+  } else if class == "center" {
+    align(center, body)
+  } else if class == "small" {
+    text(size: 0.85em, body)
+  } else if class == "hidden" {
+    none
+  } else { body }
+}
+
+#let tf-div(classes, attrs, body) = {
+  let out = body
+  for class in classes.rev() { out = tf-div-class(class, attrs, out) }
+  out
+}
+
+#let tf-span(classes, attrs, body) = {
+  let out = body
+  for class in classes.rev() {
+    out = if class in theme-classes { (theme-classes.at(class))(out, attrs) }
+      else if class == "smallcaps" { smallcaps(out) }
+      else if class == "underline" { underline(out) }
+      else if class == "mark" { highlight(out) }
+      else if class == "small" { text(size: 0.85em, out) }
+      else if class == "hidden" { none }
+      else { out }
+  }
+  out
+}
+
+// Render markdown content with explicit outline suppression
+#[
+#show outline: none
 #render(md_content,
   smart-punctuation: false,
   scope: (
     // Override link to use our safe version
     link: safe-link,
+    tf-md: tf-md,
+    tf-heading: tf-heading,
+    tf-ref: tf-ref,
+    tf-missing-ref: tf-missing-ref,
+    tf-code: tf-code,
+    tf-div: tf-div,
+    tf-span: tf-span,
+    tf-image: tf-image,
+    tf-aligned: tf-aligned,
+    tf-wrap: tf-wrap,
+    tf-subfigure: tf-subfigure,
+    tf-gallery: tf-gallery,
+    tf-part: tf-part,
+    tf-matter: tf-matter,
   ),
   // Note: cmarker 0.1.6 follows standard Markdown line break rules:
   // - Single newline = soft break (ignored in output)
@@ -231,12 +1059,18 @@
       // Read width safely; consider empty string as none
       let wraw = attrs.at("width", default: none)
       let w = if type(wraw) == str and wraw.trim() != "" { wraw } else { none }
-      let im = if w != none { builtin-image(path, width: parse-length(w)) } else { builtin-image(path) }
-      // Alignment: data-align takes precedence, then align, default to center
-      let a = attrs.at("data-align", default: attrs.at("align", default: "center"))
-      if a == "center" { align(center, im) }
-      else if a == "right" { align(right, im) }
-      else { im }
+      let im = tf-image(path, width: w)
+      // Alignment: data-align takes precedence, then align, then the preference
+      let a = attrs.at("data-align", default: attrs.at("align", default: none))
+      tf-aligned(a, im)
     })
   )
 )
+]
+
+#if doc-class == "letter" { letter-closing }
+
+#if toc-enabled and toc-placement == "end" [
+  #set page(columns: if toc_two_column { 2 } else { 1 })
+  #toc-body
+]
@@ -1,5 +1,6 @@
 /// File operation commands: CRUD operations for markdown files and directories
 use crate::error::AppError;
+use crate::preprocessor;
 use crate::utils;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -52,10 +53,9 @@ pub async fn write_markdown_file(path: &str, content: &str) -> Result<(), String
         })?;
     }
 
-    // Strip any preview-only raw-typst comments (e.g., <!--raw-typst ... -->)
-    // to avoid persisting invisible TFANCHOR tokens into user files.
-    let re = regex::Regex::new(r"(?is)<!--\s*raw-typst[\s\S]*?-->").map_err(|e| e.to_string())?;
-    let cleaned = re.replace_all(content, "").to_string();
+    // Strip preview-only scroll-sync anchors so they never reach user files.
+    // Raw Typst the user wrote (`<!--raw-typst ... -->`) is content and kept.
+    let cleaned = preprocessor::strip_anchor_markup(content);
 
     fs::write(path, cleaned).map_err(|e| {
         AppError::FileWrite {
@@ -5,26 +5,50 @@ use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 use tauri::command;
 
+/// System font families, or why they cannot be listed
+type FontList = Result<Vec<String>, String>;
+
 // Global font cache to avoid repeated filesystem scans
-static FONT_CACHE: Lazy<Arc<Mutex<Option<Vec<String>>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
+static FONT_CACHE: Lazy<Arc<Mutex<Option<FontList>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
 
 /// Get list of all system fonts (cached)
 #[command]
 pub fn get_system_fonts() -> Result<Vec<String>, String> {
-    // Check cache first
-    let mut cache = FONT_CACHE.lock().unwrap();
-    if let Some(fonts) = cache.as_ref() {
-        return Ok(fonts.clone());
+    // The picker still offers the common fonts when fc-list is unavailable
+    #[cfg(target_os = "linux")]
+    {
+        Ok(cached_system_fonts().unwrap_or_else(|_| get_fallback_fonts().into_iter().map(String::from).collect()))
     }
 
-    // Cache miss - load from system
-    let fonts = load_fonts_from_system()?;
-    *cache = Some(fonts.clone());
-    Ok(fonts)
+    #[cfg(not(target_os = "linux"))]
+    {
+        cached_system_fonts()
+    }
+}
+
+fn cached_system_fonts() -> FontList {
+    let mut cache = FONT_CACHE.lock().unwrap();
+    cache.get_or_insert_with(load_fonts_from_system).clone()
+}
+
+/// Families embedded in the Typst binary, available without being installed
+const TYPST_EMBEDDED_FONTS: [&str; 4] = ["Libertinus Serif", "New Computer Modern", "New Computer Modern Math", "DejaVu Sans Mono"];
+
+/// Whether Typst can find `family`, ignoring case. Assumes it can when the
+/// system fonts cannot be listed, and on macOS, where the list is guessed
+/// from file names ("PingFang" for "PingFang SC").
+pub fn is_font_installed(family: &str) -> bool {
+    if cfg!(target_os = "macos") || TYPST_EMBEDDED_FONTS.iter().any(|f| f.eq_ignore_ascii_case(family)) {
+        return true;
+    }
+    match cached_system_fonts() {
+        | Ok(fonts) if !fonts.is_empty() => fonts.iter().any(|f| f.eq_ignore_ascii_case(family)),
+        | _ => true,
+    }
 }
 
 /// Load fonts from system (platform-specific)
-fn load_fonts_from_system() -> Result<Vec<String>, String> {
+fn load_fonts_from_system() -> FontList {
     #[cfg(target_os = "windows")]
     {
         get_windows_fonts()
@@ -44,11 +68,36 @@ fn load_fonts_from_system() -> Result<Vec<String>, String> {
 #[cfg(target_os = "windows")]
 fn get_windows_fonts() -> Result<Vec<String>, String> {
     use std::fs;
+    use std::os::windows::process::CommandExt;
     use std::path::Path;
+    use std::process::Command;
+
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
 
     let mut fonts = HashSet::new();
 
-    // Windows fonts directory
+    // Installed fonts are registered under their full names ("Malgun Gothic
+    // Bold (TrueType)"); file names ("malgunbd.ttf") rarely give the family.
+    for key in [
+        r"HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts",
+        r"HKCU\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts",
+    ] {
+        let Ok(output) = Command::new("reg").args(["query", key]).creation_flags(CREATE_NO_WINDOW).output() else {
+            continue;
+        };
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if let Some((name, _)) = line.trim().split_once("    REG_") {
+                fonts.extend(registry_families(name));
+            }
+        }
+    }
+    if !fonts.is_empty() {
+        let mut result: Vec<String> = fonts.into_iter().collect();
+        result.sort();
+        return Ok(result);
+    }
+
+    // Without the registry, guess from the files in the fonts directory
     let windows_dir = std::env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".to_string());
     let fonts_dir = Path::new(&windows_dir).join("Fonts");
 
@@ -103,9 +152,8 @@ fn get_linux_fonts() -> Result<Vec<String>, String> {
         }
     }
 
-    // Fallback to common fonts if fc-list fails
     if fonts.is_empty() {
-        fonts.extend(get_fallback_fonts().into_iter().map(String::from));
+        return Err("fc-list did not list any fonts".to_string());
     }
 
     let mut result: Vec<String> = fonts.into_iter().collect();
@@ -147,8 +195,51 @@ fn get_macos_fonts() -> Result<Vec<String>, String> {
     Ok(result)
 }
 
+/// Style words Windows appends to a family in its font registry.
+#[cfg(any(target_os = "windows", test))]
+const STYLE_WORDS: [&str; 14] = [
+    "Regular",
+    "Bold",
+    "Italic",
+    "Oblique",
+    "Light",
+    "Semilight",
+    "SemiLight",
+    "Semibold",
+    "SemiBold",
+    "Medium",
+    "Black",
+    "Thin",
+    "ExtraLight",
+    "ExtraBold",
+];
+
+/// Families named by a font registry entry: "Cambria & Cambria Math
+/// (TrueType)" gives both, "Malgun Gothic Bold (TrueType)" gives "Malgun
+/// Gothic" and the full name (some families, like "Arial Black", end in a
+/// style word).
+#[cfg(any(target_os = "windows", test))]
+fn registry_families(entry: &str) -> Vec<String> {
+    let name = match entry.rfind(" (") {
+        | Some(i) if entry.ends_with(')') => &entry[.. i],
+        | _ => entry,
+    };
+    let mut families = Vec::new();
+    for part in name.split(" & ") {
+        let mut family = part.trim();
+        while !family.is_empty() {
+            families.push(family.to_string());
+            let Some(shorter) = STYLE_WORDS.iter().find_map(|w| family.strip_suffix(w).and_then(|f| f.strip_suffix(' '))) else {
+                break;
+            };
+            family = shorter.trim_end();
+        }
+    }
+    families
+}
+
 /// Extract font name from filename
-#[allow(dead_code)]
+#[cfg(any(target_os = "windows", target_os = "macos"))]
 fn extract_font_name(filename: &str) -> Option<String> {
     let lower = filename.to_lowercase();
 
@@ -209,3 +300,20 @@ fn get_fallback_fonts() -> Vec<&'static str> {
         "Verdana",
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_families() {
+        assert_eq!(registry_families("Malgun Gothic Bold (TrueType)"), vec!["Malgun Gothic Bold", "Malgun Gothic"]);
+        assert_eq!(registry_families("Cambria & Cambria Math (TrueType)"), vec!["Cambria", "Cambria Math"]);
+        assert_eq!(
+            registry_families("Segoe UI Semibold Italic (TrueType)"),
+            vec!["Segoe UI Semibold Italic", "Segoe UI Semibold", "Segoe UI"]
+        );
+        assert_eq!(registry_families("Arial Black (TrueType)"), vec!["Arial Black", "Arial"]);
+        assert_eq!(registry_families("Bold"), vec!["Bold"]);
+    }
+}
@@ -1,5 +1,5 @@
 /// Rendering operation commands: compile markdown/typst to PDF
-use crate::renderer::{self, RenderedDocument};
+use crate::renderer::{self, MailMergeResult, RenderedDocument};
 use std::fs;
 use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Emitter};
@@ -86,3 +86,22 @@ pub async fn render_typst(app_handle: AppHandle, content: &str, format: &str, cu
         },
     }
 }
+
+/// Render a markdown template once per row of a CSV/TSV/JSON file. Progress
+/// is reported through `mail-merge-progress` events.
+#[tauri::command]
+pub async fn export_mail_merge(
+    app_handle: AppHandle,
+    template_path: &str,
+    data_path: &str,
+    output_dir: &str,
+    file_pattern: &str,
+) -> Result<MailMergeResult, String> {
+    match renderer::export_mail_merge(&app_handle, template_path, data_path, output_dir, file_pattern).await {
+        | Ok(result) => Ok(result),
+        | Err(e) => {
+            let _ = app_handle.emit("export-error", e.to_string());
+            Err(e.to_string())
+        },
+    }
+}
@@ -32,6 +32,7 @@ pub fn run() {
             commands::import_image_from_path,
             commands::render_markdown,
             commands::export_markdown,
+            commands::export_mail_merge,
             commands::save_pdf_as,
             commands::render_typst,
             commands::typst_diagnostics,
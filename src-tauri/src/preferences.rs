@@ -42,6 +42,35 @@ pub struct Preferences {
     #[serde(default)]
     pub toc_two_column: bool,
 
+    /// Deepest heading level listed in the table of contents (1-6)
+    #[serde(default = "default_toc_depth")]
+    pub toc_depth: u8,
+
+    /// Where the table of contents goes: "after_cover", "after_abstract"
+    /// (the front matter `abstract`) or "end"
+    #[serde(default = "default_toc_placement")]
+    pub toc_placement: String,
+
+    /// Dot leaders between TOC entries and their page numbers
+    #[serde(default = "default_toc_leaders")]
+    pub toc_leaders: bool,
+
+    /// List unnumbered headings in the TOC when sections are numbered
+    #[serde(default = "default_toc_unnumbered")]
+    pub toc_unnumbered: bool,
+
+    /// List the sections of each chapter below its heading
+    #[serde(default)]
+    pub toc_mini: bool,
+
+    /// Generate a List of Figures after the table of contents
+    #[serde(default)]
+    pub list_of_figures: bool,
+
+    /// Generate a List of Tables after the table of contents
+    #[serde(default)]
+    pub list_of_tables: bool,
+
     /// Enable two-column layout for main document content
     #[serde(default)]
     pub two_column_layout: bool,
@@ -66,11 +95,74 @@ pub struct Preferences {
     #[serde(default = "default_cover_image_width")]
     pub cover_image_width: String,
 
+    /// Cover layout: "centered", "left" or "banner"
+    #[serde(default = "default_cover_layout")]
+    pub cover_layout: String,
+
+    /// Cover subtitle (the front matter `subtitle` wins)
+    #[serde(default)]
+    pub cover_subtitle: String,
+
+    /// Authors on the cover; when empty, `cover_writer` is used
+    #[serde(default)]
+    pub cover_authors: Vec<CoverAuthor>,
+
+    /// Organisation shown at the bottom of the cover
+    #[serde(default)]
+    pub cover_organization: String,
+
+    /// Document version shown at the bottom of the cover
+    #[serde(default)]
+    pub cover_version: String,
+
+    /// Cover date: "today", "YYYY-MM-DD" or any text
+    #[serde(default)]
+    pub cover_date: String,
+
+    /// Date format: "iso", "long", "day-month-year", "numeric" or a Typst
+    /// date pattern
+    #[serde(default = "default_cover_date_format")]
+    pub cover_date_format: String,
+
+    /// Logo at the top of the cover (relative to content dir)
+    #[serde(default)]
+    pub cover_logo: String,
+
+    /// Full-page background image of the cover (relative to content dir)
+    #[serde(default)]
+    pub cover_background: String,
+
     /// Enable automatic section numbering - serialized as "numberSections" for
     /// Typst
     #[serde(rename = "numberSections")]
     pub number_sections: bool,
 
+    /// Heading numbering pattern (e.g., "1.1", "1.a", "I.1", "Chapter 1")
+    #[serde(default = "default_heading_numbering")]
+    pub heading_numbering: String,
+
+    /// Deepest heading level that is numbered (1-6)
+    #[serde(default = "default_heading_numbering_depth")]
+    pub heading_numbering_depth: u8,
+
+    /// Page numbering pattern of the main content (e.g., "1", "i", "A")
+    #[serde(default = "default_page_numbering")]
+    pub page_numbering: String,
+
+    /// Page number text used by `page_numbers` (e.g., "Page {page} of
+    /// {total}")
+    #[serde(default = "default_page_number_format")]
+    pub page_number_format: String,
+
+    /// Number the table of contents and lists i, ii, ... (the main content
+    /// restarts at `first_page_number`)
+    #[serde(default)]
+    pub roman_front_matter: bool,
+
+    /// Number of the first page of the main content
+    #[serde(default = "default_first_page_number")]
+    pub first_page_number: u32,
+
     /// Default width for inserted images (e.g., "80%", "320px")
     pub default_image_width: String,
 
@@ -108,6 +200,19 @@ pub struct Preferences {
     #[serde(default = "default_paragraph_spacing")]
     pub paragraph_spacing: String,
 
+    /// Document language as an ISO 639 code (e.g., "en", "de", "ja"); sets
+    /// hyphenation, smart quotes and the generated strings
+    #[serde(default = "default_language")]
+    pub language: String,
+
+    /// Optional ISO 3166 region of the language (e.g., "GB", "CH")
+    #[serde(default)]
+    pub region: String,
+
+    /// Justification, hyphenation, OpenType features and heading styles
+    #[serde(default)]
+    pub typography: Typography,
+
     /// Enable page numbers in footer
     #[serde(default)]
     pub page_numbers: bool,
@@ -120,6 +225,11 @@ pub struct Preferences {
     #[serde(default)]
     pub header_text: String,
 
+    /// Header and footer slots; when all are empty, `page_numbers` and
+    /// `header_text` apply instead
+    #[serde(default)]
+    pub header_footer: HeaderFooter,
+
     // Preview optimization settings
     /// Debounce delay in milliseconds before re-rendering on edit
     pub render_debounce_ms: u32,
@@ -133,6 +243,11 @@ pub struct Preferences {
     /// Show confirmation dialog when closing with unsaved changes
     #[serde(default = "default_confirm_exit")]
     pub confirm_exit_on_unsaved: bool,
+
+    /// Render every document in safe mode: no raw Typst, no files outside
+    /// the document's folder (for Markdown received from outside the team)
+    #[serde(default)]
+    pub safe_mode: bool,
     /// Optional explicit path to Typst binary (used as a final fallback)
     #[serde(default)]
     pub typst_path: Option<String>,
@@ -147,15 +262,192 @@ pub struct Margins {
     pub y: String,
 }
 
-/// Font configuration
+/// Font configuration. `main` and `mono` are the first choice of their role;
+/// the lists are fallbacks in order, tried for characters the families
+/// before them lack.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Fonts {
     /// Main body text font (e.g., "New Computer Modern", "Inter")
     pub main: String,
     /// Monospace font for code blocks (e.g., "JetBrains Mono", "Fira Code")
     pub mono: String,
+    /// Fallbacks of the body text
+    #[serde(default)]
+    pub main_fallback: Vec<String>,
+    /// Fallbacks of code
+    #[serde(default)]
+    pub mono_fallback: Vec<String>,
+    /// Heading families (empty = the body text's)
+    #[serde(default)]
+    pub headings: Vec<String>,
+    /// Chinese, Japanese and Korean families, after every role's own
+    #[serde(default)]
+    pub cjk: Vec<String>,
+    /// Emoji families, last in every role
+    #[serde(default)]
+    pub emoji: Vec<String>,
+    /// Math families (empty = the theme's)
+    #[serde(default)]
+    pub math: Vec<String>,
 }
 
+/// An author on the cover page
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CoverAuthor {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub affiliation: String,
+}
+
+/// Advanced typography. Unset values (`None`, empty strings) keep the
+/// theme's own settings.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Typography {
+    /// Justify paragraphs (`None` = theme default)
+    #[serde(default)]
+    pub justify: Option<bool>,
+    /// Hyphenate words (`None` = automatic, on when justified)
+    #[serde(default)]
+    pub hyphenate: Option<bool>,
+    /// Indent of the first line of paragraphs (e.g., "1.5em")
+    #[serde(default)]
+    pub first_line_indent: String,
+    /// Avoid single lines of a paragraph at the top or bottom of a page
+    #[serde(default = "default_true")]
+    pub widow_orphan_control: bool,
+    /// Keep headings on the same page as the paragraph after them
+    #[serde(default = "default_true")]
+    pub keep_with_next: bool,
+    /// Standard ligatures ("fi", "fl")
+    #[serde(default = "default_true")]
+    pub ligatures: bool,
+    /// Old-style (lowercase) figures
+    #[serde(default)]
+    pub oldstyle_figures: bool,
+    /// Set headings in small capitals
+    #[serde(default)]
+    pub small_caps_headings: bool,
+    /// Letter spacing of headings (e.g., "0.05em")
+    #[serde(default)]
+    pub heading_tracking: String,
+    /// Size and weight of heading levels 1, 2, ...
+    #[serde(default)]
+    pub heading_levels: Vec<HeadingStyle>,
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self {
+            justify: None,
+            hyphenate: None,
+            first_line_indent: String::new(),
+            widow_orphan_control: true,
+            keep_with_next: true,
+            ligatures: true,
+            oldstyle_figures: false,
+            small_caps_headings: false,
+            heading_tracking: String::new(),
+            heading_levels: Vec::new(),
+        }
+    }
+}
+
+/// Size and weight of one heading level; empty values keep the theme's
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct HeadingStyle {
+    /// Font size (e.g., "20pt"; "em" is relative to the body text)
+    #[serde(default)]
+    pub size: String,
+    /// Font weight ("regular", "bold", ... or 100-900)
+    #[serde(default)]
+    pub weight: String,
+}
+
+/// Text of the left, center and right part of a header or footer. May
+/// contain the placeholders `{page}`, `{total}`, `{title}`, `{chapter}`,
+/// `{date}` and `{author}`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PageSlots {
+    #[serde(default)]
+    pub left: String,
+    #[serde(default)]
+    pub center: String,
+    #[serde(default)]
+    pub right: String,
+}
+
+/// Header and footer layout of the main content pages
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HeaderFooter {
+    #[serde(default)]
+    pub header: PageSlots,
+    #[serde(default)]
+    pub footer: PageSlots,
+    /// First page of the main content: "same", "footer" (footer only) or
+    /// "none"
+    #[serde(default = "default_first_page")]
+    pub first_page: String,
+    /// Swap the left and right slots on even pages (for two-sided printing)
+    #[serde(default)]
+    pub mirror_even_pages: bool,
+    /// Logo shown in the header (relative to content dir)
+    #[serde(default)]
+    pub logo: String,
+    /// Header side of the logo ("left" or "right")
+    #[serde(default = "default_logo_position")]
+    pub logo_position: String,
+    /// Logo height (e.g., "0.8cm")
+    #[serde(default = "default_logo_height")]
+    pub logo_height: String,
+    /// Draw a thin rule below the header and above the footer
+    #[serde(default)]
+    pub rules: bool,
+}
+
+impl Default for HeaderFooter {
+    fn default() -> Self {
+        Self {
+            header: PageSlots::default(),
+            footer: PageSlots::default(),
+            first_page: default_first_page(),
+            mirror_even_pages: false,
+            logo: String::new(),
+            logo_position: default_logo_position(),
+            logo_height: default_logo_height(),
+            rules: false,
+        }
+    }
+}
+
+fn default_true() -> bool { true }
+
+fn default_language() -> String { "en".to_string() }
+
+fn default_toc_depth() -> u8 { 3 }
+
+fn default_toc_placement() -> String { "after_cover".to_string() }
+
+fn default_toc_leaders() -> bool { true }
+
+fn default_toc_unnumbered() -> bool { true }
+
+fn default_heading_numbering() -> String { "1.1".to_string() }
+
+fn default_heading_numbering_depth() -> u8 { 6 }
+
+fn default_page_numbering() -> String { "1".to_string() }
+
+fn default_page_number_format() -> String { "{page}".to_string() }
+
+fn default_first_page_number() -> u32 { 1 }
+
+fn default_first_page() -> String { "same".to_string() }
+
+fn default_logo_position() -> String { "left".to_string() }
+
+fn default_logo_height() -> String { "0.8cm".to_string() }
+
 fn default_font_size() -> f32 { 11.0 }
 
 fn default_page_bg_color() -> String { "#ffffff".to_string() }
@@ -172,6 +464,10 @@ fn default_paragraph_spacing() -> String { "0.65em".to_string() }
 
 fn default_cover_image_width() -> String { "60%".to_string() }
 
+fn default_cover_layout() -> String { "centered".to_string() }
+
+fn default_cover_date_format() -> String { "long".to_string() }
+
 fn default_confirm_exit() -> bool { true }
 
 impl Default for Preferences {
@@ -187,18 +483,46 @@ impl Default for Preferences {
             toc: false, // default disabled
             toc_title: String::new(),
             toc_two_column: false,
+            toc_depth: default_toc_depth(),
+            toc_placement: default_toc_placement(),
+            toc_leaders: true,
+            toc_unnumbered: true,
+            toc_mini: false,
+            list_of_figures: false,
+            list_of_tables: false,
             two_column_layout: false,
             cover_page: false,
             cover_title: String::new(),
             cover_writer: String::new(),
             cover_image: String::new(),
             cover_image_width: "60%".to_string(),
+            cover_layout: default_cover_layout(),
+            cover_subtitle: String::new(),
+            cover_authors: Vec::new(),
+            cover_organization: String::new(),
+            cover_version: String::new(),
+            cover_date: String::new(),
+            cover_date_format: default_cover_date_format(),
+            cover_logo: String::new(),
+            cover_background: String::new(),
             number_sections: true,
+            heading_numbering: default_heading_numbering(),
+            heading_numbering_depth: default_heading_numbering_depth(),
+            page_numbering: default_page_numbering(),
+            page_number_format: default_page_number_format(),
+            roman_front_matter: false,
+            first_page_number: default_first_page_number(),
             default_image_width: "80%".to_string(),
             default_image_alignment: "center".to_string(),
             fonts: Fonts {
                 main: "New Computer Modern".to_string(),
                 mono: "Liberation Mono".to_string(),
+                main_fallback: Vec::new(),
+                mono_fallback: Vec::new(),
+                headings: Vec::new(),
+                cjk: Vec::new(),
+                emoji: Vec::new(),
+                math: Vec::new(),
             },
             font_size: 11.0,
             page_bg_color: "#ffffff".to_string(),
@@ -207,14 +531,19 @@ impl Default for Preferences {
             accent_color: "#1e40af".to_string(),
             line_height: 1.5,
             paragraph_spacing: "0.65em".to_string(),
+            language: default_language(),
+            region: String::new(),
+            typography: Typography::default(),
             page_numbers: false,
             header_title: false,
             header_text: String::new(),
+            header_footer: HeaderFooter::default(),
             // Preview optimization defaults
             render_debounce_ms: 400, // 400ms for responsive feel
             focused_preview_enabled: true,
             preserve_scroll_position: true,
             confirm_exit_on_unsaved: true,
+            safe_mode: false,
             typst_path: None,
         }
     }
@@ -0,0 +1,75 @@
+//! Page and column breaks, landscape sections, and the front, main and back
+//! matter of a book.
+//!
+//! ```markdown
+//! \newpage
+//!
+//! <!-- landscape -->
+//! | A wide table | ... |
+//! <!-- portrait -->
+//! ```
+//!
+//! Each marker sits on a line of its own. Page breaks: `\newpage`,
+//! `\pagebreak`, `\clearpage`, `<!-- pagebreak -->` and `<!-- newpage -->`.
+//! Column breaks: `\columnbreak` and `<!-- columnbreak -->` (a page break in
+//! a single-column layout). A landscape section starts at `<!-- landscape -->`
+//! or `\begin{landscape}` and runs to `<!-- portrait -->`, `\end{landscape}`
+//! or the end of the document. `\frontmatter`, `\mainmatter` and
+//! `\backmatter` (or `<!-- frontmatter -->`, ...) switch page and heading
+//! numbering for the rest of the document.
+
+use super::{PreprocessWarning, SourceLine, fenced_code_mask, line_at, raw_typst};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref PAGE_BREAK: Regex = Regex::new(r"^\s*(?:\\(?:newpage|pagebreak|clearpage)|<!--\s*(?:pagebreak|newpage)\s*-->)\s*$").unwrap();
+    static ref COLUMN_BREAK: Regex = Regex::new(r"^\s*(?:\\columnbreak|<!--\s*columnbreak\s*-->)\s*$").unwrap();
+    static ref LANDSCAPE: Regex = Regex::new(r"^\s*(?:\\begin\{landscape\}|<!--\s*landscape\s*-->)\s*$").unwrap();
+    static ref PORTRAIT: Regex = Regex::new(r"^\s*(?:\\end\{landscape\}|<!--\s*portrait\s*-->)\s*$").unwrap();
+    static ref MATTER: Regex = Regex::new(r"^\s*(?:\\(front|main|back)matter|<!--\s*(front|main|back)matter\s*-->)\s*$").unwrap();
+}
+
+/// Replace break and orientation markers with raw Typst.
+pub(super) fn lower_breaks(lines: Vec<SourceLine>, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let in_code = fenced_code_mask(&lines);
+    let mut out: Vec<SourceLine> = Vec::with_capacity(lines.len());
+    let mut landscape = false;
+    for (src, code) in lines.into_iter().zip(in_code) {
+        if code {
+            out.push(src);
+        } else if PAGE_BREAK.is_match(&src.text) {
+            out.push(line_at(raw_typst("#pagebreak()"), src.line));
+        } else if COLUMN_BREAK.is_match(&src.text) {
+            out.push(line_at(raw_typst("#colbreak()"), src.line));
+        } else if let Some(caps) = MATTER.captures(&src.text) {
+            let kind = caps.get(1).or(caps.get(2)).map_or("main", |m| m.as_str());
+            out.push(line_at(raw_typst(&format!("#show: tf-matter.with(\"{}\")", kind)), src.line));
+        } else if LANDSCAPE.is_match(&src.text) {
+            if landscape {
+                warnings.push(PreprocessWarning::at(src.line, "Already in a landscape section".to_string()));
+            } else {
+                out.push(line_at(raw_typst("#page(flipped: true)["), src.line));
+                landscape = true;
+            }
+        } else if PORTRAIT.is_match(&src.text) {
+            if landscape {
+                out.push(line_at(raw_typst("]"), src.line));
+                landscape = false;
+            } else {
+                warnings.push(PreprocessWarning::at(
+                    src.line,
+                    "Portrait marker without a landscape section before it".to_string(),
+                ));
+            }
+        } else {
+            out.push(src);
+        }
+    }
+    if landscape {
+        let line = out.last().map_or(0, |l| l.line);
+        out.push(line_at(String::new(), line));
+        out.push(line_at(raw_typst("]"), line));
+    }
+    out
+}
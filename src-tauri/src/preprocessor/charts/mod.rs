@@ -0,0 +1,456 @@
+//! Charts drawn from data files.
+//!
+//! ````markdown
+//! ```{chart caption="Revenue by quarter" #fig:revenue width=80%}
+//! type: bar
+//! src: data/revenue.csv
+//! x: quarter
+//! y: [north, south]
+//! ```
+//! ````
+//!
+//! The block body is a small YAML spec: `type` (`bar`, `line`, `scatter` or
+//! `pie`), the data (`src` file, or inline `data` rows), the `x` column and
+//! one or more `y` columns, plus optional `title`, `x-label`, `y-label`,
+//! `aspect` and `colors`. The chart is drawn to SVG here, without network
+//! access, and written to the build cache under a hash of everything that
+//! affects it, so unchanged charts are not rewritten on every render. The
+//! cache is shared by every open document, so drawings are only deleted
+//! once no render has used them for a month. Series
+//! colours are derived from the accent colour unless `colors` is given.
+//!
+//! Attributes on the opening fence work as for images: `caption`, `#fig:`
+//! labels, `placement`, `.unnumbered` / `.unlisted`, plus `width`.
+
+mod svg;
+
+use super::{Attributes, PreprocessConfig, PreprocessWarning, figures, raw_typst, safe_mode, typst_string};
+use crate::utils::data::{DataTable, load_data_file};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use serde_yaml::Value as YamlValue;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use svg::{ChartData, ChartKind, ChartStyle, Series};
+
+/// Bump when the drawing code changes so cached SVGs are regenerated.
+const RENDERER_VERSION: u32 = 1;
+/// Cached drawings no render used for this long are deleted.
+const CACHE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const CHART_WIDTH: f64 = 640.0;
+
+lazy_static! {
+    static ref OPENER: Regex = Regex::new(r"^(\s*)(`{3,}|~{3,})\s*\{([^}]*)\}\s*$").unwrap();
+    static ref LENGTH: Regex = Regex::new(r"^\d+(\.\d+)?(%|pt|mm|cm|in|em)$").unwrap();
+    static ref HEX_COLOR: Regex = Regex::new(r"^#([0-9a-fA-F]{3}|[0-9a-fA-F]{6})$").unwrap();
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ChartSpec {
+    #[serde(rename = "type")]
+    kind: String,
+    src: Option<String>,
+    data: Option<Vec<BTreeMap<String, YamlValue>>>,
+    x: String,
+    y: Columns,
+    title: Option<String>,
+    x_label: Option<String>,
+    y_label: Option<String>,
+    aspect: Option<f64>,
+    #[serde(default)]
+    colors: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Columns {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Columns {
+    fn names(&self) -> Vec<&str> {
+        match self {
+            | Columns::One(name) => vec![name.as_str()],
+            | Columns::Many(names) => names.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// A fenced ```` ```{chart} ```` block. The spec is in the body, so the
+/// caller collects lines up to the closing fence before calling `expand`.
+pub(super) struct ChartBlock {
+    indent: String,
+    attrs: Attributes,
+}
+
+impl ChartBlock {
+    pub fn parse(line: &str) -> Option<Self> {
+        let caps = OPENER.captures(line)?;
+        let attrs = Attributes::parse(&caps[3]);
+        if !attrs.has_class("chart") {
+            return None;
+        }
+        Some(ChartBlock {
+            indent: caps[1].to_string(),
+            attrs,
+        })
+    }
+
+    /// Draw the chart (or reuse the cached drawing) and produce the
+    /// replacement line, or `None` (with a problem recorded) on failure. The
+    /// drawing's path is added to `drawn`.
+    pub fn expand(&self, body: &str, config: &PreprocessConfig, base_dir: &Path, drawn: &mut Vec<PathBuf>, problems: &mut Vec<String>) -> Option<Vec<String>> {
+        let spec: ChartSpec = match serde_yaml::from_str(body) {
+            | Ok(spec) => spec,
+            | Err(e) => {
+                problems.push(format!("Invalid chart spec: {}", e));
+                return None;
+            },
+        };
+        let kind = match spec.kind.as_str() {
+            | "bar" => ChartKind::Bar,
+            | "line" => ChartKind::Line,
+            | "scatter" => ChartKind::Scatter,
+            | "pie" => ChartKind::Pie,
+            | other => {
+                problems.push(format!("Unknown chart type '{}' (expected bar, line, scatter or pie)", other));
+                return None;
+            },
+        };
+
+        if let Some(src) = &spec.src
+            && let Err(e) = safe_mode::check_readable(config, &base_dir.join(src))
+        {
+            problems.push(e);
+            return None;
+        }
+        let (table, source) = match (&spec.src, &spec.data) {
+            | (Some(src), None) => match load_data_file(&base_dir.join(src)) {
+                | Ok(table) => (table, src.as_str()),
+                | Err(e) => {
+                    problems.push(format!("Cannot load chart data from '{}': {}", src, e));
+                    return None;
+                },
+            },
+            | (None, Some(rows)) => (inline_table(rows), "inline data"),
+            | _ => {
+                problems.push("A chart needs either 'src' or 'data'".to_string());
+                return None;
+            },
+        };
+
+        let column = |name: &str, problems: &mut Vec<String>| {
+            let index = table.columns.iter().position(|c| c == name);
+            if index.is_none() {
+                problems.push(format!("Column '{}' not found in {}", name, source));
+            }
+            index
+        };
+        let x = column(&spec.x, problems)?;
+        let mut series = Vec::new();
+        for name in spec.y.names() {
+            if let Some(y) = column(name, problems) {
+                series.push(Series {
+                    name: name.to_string(),
+                    values: table.rows.iter().map(|row| parse_number(row.get(y))).collect(),
+                });
+            }
+        }
+        if series.is_empty() {
+            problems.push("A chart needs at least one 'y' column".to_string());
+            return None;
+        }
+        if kind == ChartKind::Pie && series.len() > 1 {
+            problems.push("Pie charts show only the first 'y' column".to_string());
+            series.truncate(1);
+        }
+        let x_values: Vec<Option<f64>> = table.rows.iter().map(|row| parse_number(row.get(x))).collect();
+        if kind == ChartKind::Scatter && x_values.iter().all(Option::is_none) {
+            problems.push(format!("Scatter charts need a numeric 'x' column; '{}' has no numbers", spec.x));
+            return None;
+        }
+
+        let chart = ChartData {
+            kind,
+            categories: table.rows.iter().map(|row| row.get(x).cloned().unwrap_or_default()).collect(),
+            x_values,
+            series,
+            title: spec.title,
+            x_label: spec.x_label,
+            y_label: spec.y_label,
+        };
+        let aspect = spec.aspect.filter(|a| *a >= 0.25 && *a <= 4.0).unwrap_or(1.6);
+        let style = ChartStyle {
+            width: CHART_WIDTH,
+            height: (CHART_WIDTH / aspect).round(),
+            colors: palette(&spec.colors, config, chart.series.len().max(chart.categories.len()), problems),
+            text_color: config
+                .variables
+                .get("prefs.font_color")
+                .filter(|c| HEX_COLOR.is_match(c))
+                .cloned()
+                .unwrap_or_else(|| "#000000".to_string()),
+            font_family: config.variables.get("prefs.fonts.main").cloned().unwrap_or_default(),
+        };
+
+        let mut hash = Fnv1a::new();
+        hash.field(&RENDERER_VERSION.to_string());
+        hash.field(body);
+        hash.fields(&table.columns);
+        for row in &table.rows {
+            hash.fields(row);
+        }
+        hash.fields(&style.colors);
+        hash.field(&style.text_color);
+        hash.field(&style.font_family);
+        let path = config.cache_dir.join("charts").join(format!("chart-{:016x}.svg", hash.0));
+        if path.exists() {
+            // Mark it used, so pruning leaves it alone
+            let _ = fs::File::options().append(true).open(&path).and_then(|f| f.set_modified(SystemTime::now()));
+        } else {
+            let written = path
+                .parent()
+                .map(fs::create_dir_all)
+                .transpose()
+                .and_then(|_| fs::write(&path, svg::render_svg(&chart, &style)));
+            if let Err(e) = written {
+                problems.push(format!("Cannot write chart to '{}': {}", path.display(), e));
+                return None;
+            }
+        }
+        drawn.push(path.clone());
+
+        let width = match self.attrs.get("width") {
+            | Some(w) if LENGTH.is_match(w) => w,
+            | Some(w) => {
+                problems.push(format!("Invalid chart width '{}' (expected e.g. 80% or 12cm)", w));
+                "100%"
+            },
+            | None => "100%",
+        };
+        // Forward slashes so the image path rewrite can make it root-relative.
+        let image = format!("image({}, width: {})", typst_string(&path.to_string_lossy().replace('\\', "/")), width);
+        let code = match (self.attrs.get("caption"), &self.attrs.id) {
+            | (None, None) => format!("#{}", image),
+            | (caption, id) => {
+                let mut warnings: Vec<PreprocessWarning> = Vec::new();
+                let options = figures::figure_options(&self.attrs, 0, &mut warnings);
+                problems.extend(warnings.into_iter().map(|w| w.message));
+                let caption = caption.map(|c| format!(", caption: tf-md({})", typst_string(c))).unwrap_or_default();
+                let label = id.as_ref().map(|id| format!(" <{}>", id)).unwrap_or_default();
+                format!("#figure(kind: image{}{})[#{}]{}", caption, options, image, label)
+            },
+        };
+        Some(vec![format!("{}{}", self.indent, raw_typst(&code))])
+    }
+}
+
+/// Delete cached drawings that are not in `drawn` and that no render used
+/// for `CACHE_MAX_AGE`. Other documents share the cache, so recent drawings
+/// stay even when this one no longer uses them.
+pub(super) fn prune_cache(cache_dir: &Path, drawn: &[PathBuf]) {
+    let Ok(entries) = fs::read_dir(cache_dir.join("charts")) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let is_drawing = name.to_str().is_some_and(|n| n.starts_with("chart-") && n.ends_with(".svg"));
+        if !is_drawing || drawn.iter().any(|p| p.file_name() == Some(name.as_os_str())) {
+            continue;
+        }
+        let unused_for = entry.metadata().and_then(|m| m.modified()).ok().and_then(|t| t.elapsed().ok());
+        if unused_for.is_some_and(|age| age > CACHE_MAX_AGE) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// 64-bit FNV-1a. Cache names must not change between builds of the app,
+/// which `DefaultHasher` does not promise.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self { Fnv1a(0xcbf2_9ce4_8422_2325) }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// A string followed by a byte UTF-8 never contains, so `("ab", "c")`
+    /// and `("a", "bc")` hash differently.
+    fn field(&mut self, value: &str) {
+        self.write(value.as_bytes());
+        self.write(&[0xff]);
+    }
+
+    /// A list of strings, closed by a second marker byte.
+    fn fields(&mut self, values: &[String]) {
+        for value in values {
+            self.field(value);
+        }
+        self.write(&[0xfe]);
+    }
+}
+
+fn inline_table(rows: &[BTreeMap<String, YamlValue>]) -> DataTable {
+    let mut table = DataTable::default();
+    for row in rows {
+        for key in row.keys() {
+            if !table.columns.contains(key) {
+                table.columns.push(key.clone());
+            }
+        }
+    }
+    table.rows = rows
+        .iter()
+        .map(|row| {
+            table
+                .columns
+                .iter()
+                .map(|c| match row.get(c) {
+                    | Some(YamlValue::String(s)) => s.clone(),
+                    | Some(YamlValue::Number(n)) => n.to_string(),
+                    | Some(YamlValue::Bool(b)) => b.to_string(),
+                    | _ => String::new(),
+                })
+                .collect()
+        })
+        .collect();
+    table
+}
+
+/// Numbers as written in data files; thousands separators and a trailing
+/// percent sign are tolerated.
+fn parse_number(cell: Option<&String>) -> Option<f64> {
+    let cleaned: String = cell?.trim().trim_end_matches('%').chars().filter(|c| *c != ',').collect();
+    cleaned.parse().ok().filter(|v: &f64| v.is_finite())
+}
+
+/// Explicit colours when given, otherwise `count` colours derived from the
+/// accent colour by rotating its hue.
+fn palette(explicit: &[String], config: &PreprocessConfig, count: usize, problems: &mut Vec<String>) -> Vec<String> {
+    let valid: Vec<String> = explicit
+        .iter()
+        .filter(|c| {
+            let ok = HEX_COLOR.is_match(c);
+            if !ok {
+                problems.push(format!("Invalid chart colour '{}' (expected #rrggbb)", c));
+            }
+            ok
+        })
+        .cloned()
+        .collect();
+    if !valid.is_empty() {
+        return valid;
+    }
+
+    let accent = config
+        .variables
+        .get("prefs.accent_color")
+        .and_then(|c| parse_hex(c))
+        .unwrap_or((0x1e, 0x40, 0xaf));
+    let (h, s, l) = rgb_to_hsl(accent);
+    (0 .. count.max(1))
+        .map(|i| {
+            // Golden-angle steps keep neighbouring series distinct.
+            let hue = (h + i as f64 * 137.508) % 360.0;
+            let (r, g, b) = hsl_to_rgb(hue, s.max(0.35), l.clamp(0.3, 0.6));
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        })
+        .collect()
+}
+
+fn parse_hex(color: &str) -> Option<(u8, u8, u8)> {
+    if !HEX_COLOR.is_match(color) {
+        return None;
+    }
+    let hex = &color[1 ..];
+    let hex = if hex.len() == 3 {
+        hex.chars().flat_map(|c| [c, c]).collect()
+    } else {
+        hex.to_string()
+    };
+    let channel = |i: usize| u8::from_str_radix(&hex[i .. i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn rgb_to_hsl((r, g, b): (u8, u8, u8)) -> (f64, f64, f64) {
+    let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        return (0.0, 0.0, l);
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        60.0 * (((g - b) / d).rem_euclid(6.0))
+    } else if max == g {
+        60.0 * ((b - r) / d + 2.0)
+    } else {
+        60.0 * ((r - g) / d + 4.0)
+    };
+    (h, s, l)
+}
+
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> (u8, u8, u8) {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r, g, b) = match (h / 60.0) as u32 {
+        | 0 => (c, x, 0.0),
+        | 1 => (x, c, 0.0),
+        | 2 => (0.0, c, x),
+        | 3 => (0.0, x, c),
+        | 4 => (x, 0.0, c),
+        | _ => (c, 0.0, x),
+    };
+    let to_byte = |v: f64| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    (to_byte(r), to_byte(g), to_byte(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_is_stable() {
+        let mut hash = Fnv1a::new();
+        hash.write(b"a");
+        assert_eq!(hash.0, 0xaf63_dc4c_8601_ec8c);
+
+        let (mut a, mut b) = (Fnv1a::new(), Fnv1a::new());
+        a.fields(&["ab".to_string(), "c".to_string()]);
+        b.fields(&["a".to_string(), "bc".to_string()]);
+        assert_ne!(a.0, b.0);
+    }
+
+    #[test]
+    fn test_prune_cache_deletes_only_old_drawings() {
+        let dir = crate::preprocessor::test_support::temp_folder("charts");
+        let charts = dir.join("charts");
+        fs::create_dir_all(&charts).unwrap();
+        let long_ago = SystemTime::now() - CACHE_MAX_AGE - Duration::from_secs(60);
+        for name in ["chart-drawn.svg", "chart-old.svg", "chart-other-doc.svg", "notes.txt"] {
+            let file = fs::File::create(charts.join(name)).unwrap();
+            if name != "chart-other-doc.svg" {
+                file.set_modified(long_ago).unwrap();
+            }
+        }
+        prune_cache(&dir, &[charts.join("chart-drawn.svg")]);
+        assert!(charts.join("chart-drawn.svg").exists());
+        assert!(!charts.join("chart-old.svg").exists());
+        assert!(charts.join("chart-other-doc.svg").exists());
+        assert!(charts.join("notes.txt").exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
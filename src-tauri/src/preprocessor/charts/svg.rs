@@ -0,0 +1,382 @@
+//! Minimal SVG output for bar, line, scatter and pie charts.
+//!
+//! The output is deliberately plain (no scripts, no external references) so
+//! Typst's SVG renderer handles it without surprises.
+
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ChartKind {
+    Bar,
+    Line,
+    Scatter,
+    Pie,
+}
+
+pub(super) struct Series {
+    pub name: String,
+    pub values: Vec<Option<f64>>,
+}
+
+/// Data ready to draw: one label (or x value, for scatter plots) per point
+/// and one or more series of y values.
+pub(super) struct ChartData {
+    pub kind: ChartKind,
+    pub categories: Vec<String>,
+    /// Numeric x positions, used by scatter plots
+    pub x_values: Vec<Option<f64>>,
+    pub series: Vec<Series>,
+    pub title: Option<String>,
+    pub x_label: Option<String>,
+    pub y_label: Option<String>,
+}
+
+pub(super) struct ChartStyle {
+    pub width: f64,
+    pub height: f64,
+    pub colors: Vec<String>,
+    pub text_color: String,
+    pub font_family: String,
+}
+
+const FONT_SIZE: f64 = 12.0;
+const TITLE_SIZE: f64 = 15.0;
+
+/// Plot area inside the SVG canvas.
+struct Frame {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+pub(super) fn render_svg(chart: &ChartData, style: &ChartStyle) -> String {
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}"{font} font-size="{size}" fill="{text}">"#,
+        w = style.width,
+        h = style.height,
+        font = if style.font_family.is_empty() {
+            String::new()
+        } else {
+            format!(r#" font-family="{}""#, escape(&style.font_family))
+        },
+        size = FONT_SIZE,
+        text = escape(&style.text_color),
+    );
+
+    let mut top = 12.0;
+    if let Some(title) = &chart.title {
+        let _ = write!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle" font-size="{}" font-weight="bold">{}</text>"#,
+            style.width / 2.0,
+            top + TITLE_SIZE,
+            TITLE_SIZE,
+            escape(title)
+        );
+        top += TITLE_SIZE + 12.0;
+    }
+
+    let legend = chart.kind == ChartKind::Pie || chart.series.len() > 1;
+    if chart.kind == ChartKind::Pie {
+        draw_pie(&mut svg, chart, style, top);
+    } else {
+        if legend {
+            top = draw_legend_row(&mut svg, chart, style, top);
+        }
+        draw_axes_chart(&mut svg, chart, style, top);
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+fn draw_legend_row(svg: &mut String, chart: &ChartData, style: &ChartStyle, top: f64) -> f64 {
+    let mut x = 16.0;
+    let y = top + 4.0;
+    for (i, series) in chart.series.iter().enumerate() {
+        let _ = write!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="10" height="10" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+            color(style, i),
+            x + 14.0,
+            y + 9.5,
+            escape(&series.name)
+        );
+        x += 14.0 + text_width(&series.name) + 18.0;
+    }
+    top + 24.0
+}
+
+fn draw_axes_chart(svg: &mut String, chart: &ChartData, style: &ChartStyle, top: f64) {
+    let values = chart.series.iter().flat_map(|s| s.values.iter().flatten().copied());
+    let (y_min, y_max) = bounds(values, chart.kind == ChartKind::Bar);
+    let y_ticks = nice_ticks(y_min, y_max);
+    let y_lo = *y_ticks.first().unwrap_or(&y_min);
+    let y_hi = *y_ticks.last().unwrap_or(&y_max);
+
+    let tick_width = y_ticks.iter().map(|t| text_width(&format_tick(*t, &y_ticks))).fold(0.0, f64::max);
+    let left = 12.0 + tick_width + if chart.y_label.is_some() { 20.0 } else { 0.0 };
+    let bottom = 28.0 + if chart.x_label.is_some() { 20.0 } else { 0.0 };
+    let frame = Frame {
+        left,
+        top,
+        width: style.width - left - 16.0,
+        height: (style.height - top - bottom).max(40.0),
+    };
+    let y_pos = |v: f64| frame.top + frame.height - (v - y_lo) / (y_hi - y_lo) * frame.height;
+
+    // Horizontal grid lines with y tick labels
+    for tick in &y_ticks {
+        let y = y_pos(*tick);
+        let _ = write!(
+            svg,
+            r##"<line x1="{}" y1="{y:.2}" x2="{}" y2="{y:.2}" stroke="#000000" stroke-opacity="0.12"/><text x="{}" y="{:.2}" text-anchor="end">{}</text>"##,
+            frame.left,
+            frame.left + frame.width,
+            frame.left - 6.0,
+            y + 4.0,
+            format_tick(*tick, &y_ticks)
+        );
+    }
+
+    match chart.kind {
+        | ChartKind::Scatter => {
+            let (x_min, x_max) = bounds(chart.x_values.iter().flatten().copied(), false);
+            let x_ticks = nice_ticks(x_min, x_max);
+            let x_lo = *x_ticks.first().unwrap_or(&x_min);
+            let x_hi = *x_ticks.last().unwrap_or(&x_max);
+            let x_pos = |v: f64| frame.left + (v - x_lo) / (x_hi - x_lo) * frame.width;
+            for tick in &x_ticks {
+                let _ = write!(
+                    svg,
+                    r#"<text x="{:.2}" y="{:.2}" text-anchor="middle">{}</text>"#,
+                    x_pos(*tick),
+                    frame.top + frame.height + 18.0,
+                    format_tick(*tick, &x_ticks)
+                );
+            }
+            for (i, series) in chart.series.iter().enumerate() {
+                for (x, y) in chart.x_values.iter().zip(&series.values) {
+                    if let (Some(x), Some(y)) = (x, y) {
+                        let _ = write!(
+                            svg,
+                            r#"<circle cx="{:.2}" cy="{:.2}" r="4" fill="{}" fill-opacity="0.85"/>"#,
+                            x_pos(*x),
+                            y_pos(*y),
+                            color(style, i)
+                        );
+                    }
+                }
+            }
+        },
+        | ChartKind::Bar | ChartKind::Line => {
+            let n = chart.categories.len().max(1);
+            let band = frame.width / n as f64;
+            let center = |i: usize| frame.left + band * (i as f64 + 0.5);
+            draw_category_labels(svg, &chart.categories, &frame, band, center);
+
+            if chart.kind == ChartKind::Bar {
+                let group = band * 0.8;
+                let bar = group / chart.series.len().max(1) as f64;
+                let base = y_pos(0.0_f64.clamp(y_lo, y_hi));
+                for (s, series) in chart.series.iter().enumerate() {
+                    for (i, value) in series.values.iter().enumerate() {
+                        let Some(value) = value else {
+                            continue;
+                        };
+                        let x = center(i) - group / 2.0 + bar * s as f64;
+                        let y = y_pos(*value);
+                        let _ = write!(
+                            svg,
+                            r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}"/>"#,
+                            x + bar * 0.05,
+                            y.min(base),
+                            bar * 0.9,
+                            (base - y).abs(),
+                            color(style, s)
+                        );
+                    }
+                }
+            } else {
+                for (s, series) in chart.series.iter().enumerate() {
+                    // Missing values split the line rather than dropping to
+                    // zero.
+                    let mut segment: Vec<String> = Vec::new();
+                    let mut segments: Vec<Vec<String>> = Vec::new();
+                    for (i, value) in series.values.iter().enumerate() {
+                        match value {
+                            | Some(v) => {
+                                segment.push(format!("{:.2},{:.2}", center(i), y_pos(*v)));
+                                let _ = write!(svg, r#"<circle cx="{:.2}" cy="{:.2}" r="3" fill="{}"/>"#, center(i), y_pos(*v), color(style, s));
+                            },
+                            | None => segments.push(std::mem::take(&mut segment)),
+                        }
+                    }
+                    segments.push(segment);
+                    for points in segments.iter().filter(|p| p.len() > 1) {
+                        let _ = write!(
+                            svg,
+                            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2" stroke-linejoin="round"/>"#,
+                            points.join(" "),
+                            color(style, s)
+                        );
+                    }
+                }
+            }
+        },
+        | ChartKind::Pie => {},
+    }
+
+    // Axis lines
+    let _ = write!(
+        svg,
+        r#"<line x1="{l}" y1="{t}" x2="{l}" y2="{b}" stroke="{c}"/><line x1="{l}" y1="{b}" x2="{r}" y2="{b}" stroke="{c}"/>"#,
+        l = frame.left,
+        t = frame.top,
+        b = frame.top + frame.height,
+        r = frame.left + frame.width,
+        c = escape(&style.text_color),
+    );
+
+    if let Some(label) = &chart.x_label {
+        let _ = write!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}" text-anchor="middle">{}</text>"#,
+            frame.left + frame.width / 2.0,
+            frame.top + frame.height + 40.0,
+            escape(label)
+        );
+    }
+    if let Some(label) = &chart.y_label {
+        let x = 14.0;
+        let y = frame.top + frame.height / 2.0;
+        let _ = write!(
+            svg,
+            r#"<text x="{x}" y="{y:.2}" text-anchor="middle" transform="rotate(-90 {x} {y:.2})">{}</text>"#,
+            escape(label)
+        );
+    }
+}
+
+/// Category labels under the x axis, thinned out when they would overlap.
+fn draw_category_labels(svg: &mut String, categories: &[String], frame: &Frame, band: f64, center: impl Fn(usize) -> f64) {
+    let widest = categories.iter().map(|c| text_width(c)).fold(0.0, f64::max);
+    let every = ((widest + 8.0) / band).ceil().max(1.0) as usize;
+    for (i, label) in categories.iter().enumerate().filter(|(i, _)| i % every == 0) {
+        let _ = write!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}" text-anchor="middle">{}</text>"#,
+            center(i),
+            frame.top + frame.height + 18.0,
+            escape(label)
+        );
+    }
+}
+
+fn draw_pie(svg: &mut String, chart: &ChartData, style: &ChartStyle, top: f64) {
+    let Some(series) = chart.series.first() else {
+        return;
+    };
+    let slices: Vec<(usize, f64)> = series
+        .values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| v.filter(|v| *v > 0.0).map(|v| (i, v)))
+        .collect();
+    let total: f64 = slices.iter().map(|(_, v)| v).sum();
+    if total <= 0.0 {
+        return;
+    }
+
+    let radius = ((style.height - top - 16.0) / 2.0).min(style.width * 0.3).max(20.0);
+    let cx = 16.0 + radius;
+    let cy = top + radius;
+    let mut angle = -std::f64::consts::FRAC_PI_2;
+    for (n, (i, value)) in slices.iter().enumerate() {
+        let sweep = value / total * std::f64::consts::TAU;
+        let fill = color(style, n);
+        if slices.len() == 1 {
+            let _ = write!(svg, r#"<circle cx="{cx:.2}" cy="{cy:.2}" r="{radius:.2}" fill="{fill}"/>"#);
+        } else {
+            let (x1, y1) = (cx + radius * angle.cos(), cy + radius * angle.sin());
+            let (x2, y2) = (cx + radius * (angle + sweep).cos(), cy + radius * (angle + sweep).sin());
+            let large = if sweep > std::f64::consts::PI { 1 } else { 0 };
+            let _ = write!(
+                svg,
+                r##"<path d="M{cx:.2},{cy:.2} L{x1:.2},{y1:.2} A{radius:.2},{radius:.2} 0 {large} 1 {x2:.2},{y2:.2} Z" fill="{fill}" stroke="#ffffff" stroke-width="1"/>"##
+            );
+        }
+        angle += sweep;
+
+        // Legend entry to the right of the pie
+        let lx = cx + radius + 24.0;
+        let ly = top + 8.0 + n as f64 * 20.0;
+        let name = chart.categories.get(*i).map(String::as_str).unwrap_or("");
+        let _ = write!(
+            svg,
+            r#"<rect x="{lx:.2}" y="{ly:.2}" width="10" height="10" fill="{fill}"/><text x="{:.2}" y="{:.2}">{} ({:.0}%)</text>"#,
+            lx + 14.0,
+            ly + 9.5,
+            escape(name),
+            value / total * 100.0
+        );
+    }
+}
+
+fn color(style: &ChartStyle, index: usize) -> String {
+    if style.colors.is_empty() {
+        return "#1e40af".to_string();
+    }
+    escape(&style.colors[index % style.colors.len()])
+}
+
+/// Value range to plot; bar charts always include zero.
+fn bounds(values: impl Iterator<Item = f64>, include_zero: bool) -> (f64, f64) {
+    let (mut lo, mut hi) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if !lo.is_finite() || !hi.is_finite() {
+        return (0.0, 1.0);
+    }
+    if include_zero {
+        lo = lo.min(0.0);
+        hi = hi.max(0.0);
+    }
+    if (hi - lo).abs() < f64::EPSILON {
+        hi = lo + 1.0;
+    }
+    (lo, hi)
+}
+
+/// Round tick values (1, 2, 5 × 10^n steps) covering `lo..=hi`.
+fn nice_ticks(lo: f64, hi: f64) -> Vec<f64> {
+    let raw_step = (hi - lo) / 5.0;
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|s| *s >= raw_step)
+        .unwrap_or(10.0 * magnitude);
+    let start = (lo / step).floor() * step;
+    let mut ticks = Vec::new();
+    let mut tick = start;
+    while tick < hi + step * 0.999 && ticks.len() < 50 {
+        ticks.push(tick);
+        tick += step;
+    }
+    ticks
+}
+
+/// Tick label with just enough decimals for the tick step.
+fn format_tick(value: f64, ticks: &[f64]) -> String {
+    let step = if ticks.len() > 1 { (ticks[1] - ticks[0]).abs() } else { 1.0 };
+    let decimals = if step >= 1.0 { 0 } else { (-step.log10().floor()) as usize };
+    let text = format!("{:.*}", decimals, value);
+    if text == "-0" { "0".to_string() } else { text }
+}
+
+/// Rough width of a label in the chart font; good enough for layout.
+fn text_width(text: &str) -> f64 { text.chars().map(|c| if c.is_ascii() { 0.6 } else { 1.0 }).sum::<f64>() * FONT_SIZE }
+
+fn escape(text: &str) -> String { text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;") }
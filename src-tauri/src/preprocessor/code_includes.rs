@@ -0,0 +1,285 @@
+//! Code blocks filled from source files.
+//!
+//! A fenced code block whose attribute block names a file is replaced by that
+//! file's contents when the document is preprocessed, so examples never go
+//! stale:
+//!
+//! ````markdown
+//! ```rust {include="src/lib.rs" lines="10-42" numbers=true highlight="12,20-22"}
+//! ```
+//!
+//! ```{include="server.py" region="setup"}
+//! ```
+//! ````
+//!
+//! - `lines="A-B"` selects a range (`"A-"` and `"-B"` are open ended).
+//! - `region="name"` selects the lines between `region: name` (or `#region
+//!   name`, `ANCHOR: name`) and the next `endregion` / `ANCHOR_END` marker
+//!   line. Markers count only at the start of a line (after indentation);
+//!   marker lines themselves, including those of nested regions, are dropped.
+//! - `numbers=true` (or the `.numberLines` class) prints line numbers, which
+//!   follow the file so `lines="10-42"` starts counting at 10.
+//! - `highlight="..."` marks lines by their number in the file.
+//!
+//! The language is taken from the info string, a `.lang` class, or the file
+//! extension. Whatever the block contained before is discarded.
+
+use super::{Attributes, PreprocessConfig, raw_typst, safe_mode, typst_string};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+/// Comment leader in front of a region marker (`//`, `#`, `--`, `<!--`, ...),
+/// which must open the line so trailing comments on code don't count.
+const MARKER_PREFIX: &str = r"^\s*(?://+|#|--|/\*|<!--|;+|%|')\s*#?";
+
+lazy_static! {
+    static ref OPENER: Regex = Regex::new(r"^(\s*)(`{3,}|~{3,})\s*([\w+#.-]*)\s*\{([^}]*)\}\s*$").unwrap();
+    static ref REGION_END: Regex = Regex::new(&format!(r"{}(?:endregion|ANCHOR_END)\b", MARKER_PREFIX)).unwrap();
+    static ref ANY_MARKER: Regex = Regex::new(&format!(r"{}(?:(?:end)?region\b|ANCHOR(?:_END)?:)", MARKER_PREFIX)).unwrap();
+}
+
+/// A fenced code block opener that asks for a file's contents.
+pub(super) struct CodeInclude {
+    indent: String,
+    fence: String,
+    lang: Option<String>,
+    attrs: Attributes,
+    path: String,
+}
+
+impl CodeInclude {
+    /// Recognise ```` ```lang {include="..."} ```` openers.
+    pub fn parse(line: &str) -> Option<Self> {
+        let caps = OPENER.captures(line)?;
+        let attrs = Attributes::parse(&caps[4]);
+        let path = attrs.get("include")?.to_string();
+        let lang = Some(caps[3].to_string())
+            .filter(|l| !l.is_empty())
+            .or_else(|| attrs.classes.iter().find(|c| c.as_str() != "numberLines").cloned());
+        Some(CodeInclude {
+            indent: caps[1].to_string(),
+            fence: caps[2].to_string(),
+            lang,
+            attrs,
+            path,
+        })
+    }
+
+    /// The opener without its attribute block, used when the file cannot be
+    /// read so the placeholder body still renders as ordinary code.
+    pub fn plain_opener(&self) -> String { format!("{}{}{}", self.indent, self.fence, self.lang.as_deref().unwrap_or_default()) }
+
+    /// Read the referenced file and produce the replacement lines. Problems
+    /// are pushed to `problems`; `None` means nothing could be included and
+    /// the block should be left as written.
+    pub fn expand(&self, config: &PreprocessConfig, base_dir: &Path, problems: &mut Vec<String>) -> Option<Vec<String>> {
+        let path = base_dir.join(&self.path);
+        if let Err(e) = safe_mode::check_readable(config, &path) {
+            problems.push(e);
+            return None;
+        }
+        let content = match fs::read_to_string(&path) {
+            | Ok(content) => content,
+            | Err(e) => {
+                problems.push(format!("Cannot include code from '{}': {}", self.path, e));
+                return None;
+            },
+        };
+        let file_lines: Vec<&str> = content.lines().collect();
+
+        let selected = match (self.attrs.get("region"), self.attrs.get("lines")) {
+            | (Some(region), lines) => {
+                if lines.is_some() {
+                    problems.push(format!("Both region and lines given for '{}'; using region '{}'", self.path, region));
+                }
+                self.select_region(&file_lines, region, problems)?
+            },
+            | (None, Some(range)) => self.select_lines(&file_lines, range, problems)?,
+            | (None, None) => numbered(file_lines.iter().enumerate()),
+        };
+
+        // Typst's highlighter accepts file extensions as language tags.
+        let lang = self
+            .lang
+            .clone()
+            .or_else(|| Path::new(&self.path).extension().map(|e| e.to_string_lossy().to_lowercase()));
+        let numbers = self.attrs.has_class("numberLines") || matches!(self.attrs.get("numbers"), Some("true" | "yes" | "1"));
+        let highlight = self.attrs.get("highlight").map(|h| parse_line_list(h, problems)).unwrap_or_default();
+
+        if !numbers && highlight.is_empty() {
+            let longest_run = selected.iter().map(|(_, l)| longest_backtick_run(l)).max().unwrap_or(0);
+            let fence = "`".repeat((longest_run + 1).max(3));
+            let mut out = Vec::with_capacity(selected.len() + 2);
+            out.push(format!("{}{}{}", self.indent, fence, lang.unwrap_or_default()));
+            out.extend(selected.iter().map(|(_, l)| format!("{}{}", self.indent, l)));
+            out.push(format!("{}{}", self.indent, fence));
+            return Some(out);
+        }
+
+        let text = selected.iter().map(|(_, l)| l.as_str()).collect::<Vec<_>>().join("\n");
+        let mut code = format!("#tf-code({}", typst_string(&text));
+        if let Some(lang) = lang {
+            code.push_str(&format!(", lang: {}", typst_string(&lang)));
+        }
+        if numbers {
+            code.push_str(", numbers: true");
+        }
+        let first = selected.first().map_or(1, |(n, _)| *n);
+        if selected.iter().enumerate().all(|(i, (n, _))| *n == first + i) {
+            code.push_str(&format!(", start: {}", first));
+        } else {
+            // Nested region markers were dropped; number by file line.
+            let list = selected.iter().map(|(n, _)| n.to_string()).collect::<Vec<_>>().join(", ");
+            code.push_str(&format!(", line-numbers: ({},)", list));
+        }
+        if !highlight.is_empty() {
+            let list = highlight.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ");
+            code.push_str(&format!(", highlight: ({},)", list));
+        }
+        code.push(')');
+        Some(vec![format!("{}{}", self.indent, raw_typst(&code))])
+    }
+
+    fn select_lines(&self, file_lines: &[&str], range: &str, problems: &mut Vec<String>) -> Option<Vec<(usize, String)>> {
+        let Some((start, end)) = parse_range(range) else {
+            problems.push(format!("Invalid line range '{}' for '{}'", range, self.path));
+            return None;
+        };
+        let end = end.unwrap_or(file_lines.len());
+        if start > file_lines.len() || start > end {
+            problems.push(format!("Lines {} are outside '{}' ({} lines)", range, self.path, file_lines.len()));
+            return None;
+        }
+        if end > file_lines.len() {
+            problems.push(format!("Lines {} run past the end of '{}' ({} lines)", range, self.path, file_lines.len()));
+        }
+        let end = end.min(file_lines.len());
+        Some(numbered(file_lines.iter().enumerate().take(end).skip(start - 1)))
+    }
+
+    fn select_region(&self, file_lines: &[&str], region: &str, problems: &mut Vec<String>) -> Option<Vec<(usize, String)>> {
+        let start_marker = Regex::new(&format!(
+            r"{}(?:region:?|ANCHOR:)\s*{}\s*(?:-->|\*/)?\s*$",
+            MARKER_PREFIX,
+            regex::escape(region)
+        ))
+        .ok()?;
+        let Some(start) = file_lines.iter().position(|l| start_marker.is_match(l)) else {
+            problems.push(format!("Region '{}' not found in '{}'", region, self.path));
+            return None;
+        };
+        // Skip over regions nested inside the one we want.
+        let mut depth = 0;
+        let mut end = None;
+        for (i, l) in file_lines.iter().enumerate().skip(start + 1) {
+            if REGION_END.is_match(l) {
+                if depth == 0 {
+                    end = Some(i);
+                    break;
+                }
+                depth -= 1;
+            } else if ANY_MARKER.is_match(l) {
+                depth += 1;
+            }
+        }
+        let end = end.unwrap_or_else(|| {
+            problems.push(format!("Region '{}' in '{}' is never closed", region, self.path));
+            file_lines.len()
+        });
+        let inside = file_lines.iter().enumerate().take(end).skip(start + 1);
+        Some(numbered(inside.filter(|(_, l)| !ANY_MARKER.is_match(l))))
+    }
+}
+
+/// Selected lines paired with their 1-based line number in the file.
+fn numbered<'a>(lines: impl Iterator<Item = (usize, &'a &'a str)>) -> Vec<(usize, String)> { lines.map(|(i, l)| (i + 1, l.to_string())).collect() }
+
+/// `"10-42"`, `"10-"`, `"-42"` or `"10"` as a 1-based inclusive range.
+fn parse_range(range: &str) -> Option<(usize, Option<usize>)> {
+    let (start, end) = match range.split_once('-') {
+        | Some((a, b)) => (a.trim(), b.trim()),
+        | None => (range.trim(), range.trim()),
+    };
+    let start = if start.is_empty() { 1 } else { start.parse().ok()? };
+    let end = if end.is_empty() { None } else { Some(end.parse().ok()?) };
+    (start >= 1).then_some((start, end))
+}
+
+/// `"3,5-7"` → `[3, 5, 6, 7]`.
+fn parse_line_list(list: &str, problems: &mut Vec<String>) -> Vec<usize> {
+    let mut lines = Vec::new();
+    for part in list.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match parse_range(part) {
+            | Some((start, Some(end))) if start <= end => lines.extend(start ..= end),
+            | _ => problems.push(format!("Invalid highlight range '{}'", part)),
+        }
+    }
+    lines
+}
+
+fn longest_backtick_run(line: &str) -> usize { line.split(|c| c != '`').map(str::len).max().unwrap_or(0) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::test_support::{config, temp_folder};
+
+    const SOURCE: &str = "fn main() {\n    // region: setup\n    let x = 1; // region: not-a-marker\n    // region: inner\n    let y = 2;\n    // endregion\n    run(x, y);\n    // endregion\n}";
+
+    fn include(attrs: &str) -> CodeInclude { CodeInclude::parse(&format!("```rust {{include=\"main.rs\" {}}}", attrs)).unwrap() }
+
+    #[test]
+    fn test_region_keeps_file_line_numbers() {
+        let file_lines: Vec<&str> = SOURCE.lines().collect();
+        let mut problems = Vec::new();
+        let selected = include("").select_region(&file_lines, "setup", &mut problems).unwrap();
+        let numbers: Vec<usize> = selected.iter().map(|(n, _)| *n).collect();
+        assert_eq!(numbers, vec![3, 5, 7]);
+        assert_eq!(selected[0].1, "    let x = 1; // region: not-a-marker");
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn test_unclosed_and_missing_regions() {
+        let file_lines = vec!["# region: open", "print(1)"];
+        let mut problems = Vec::new();
+        assert_eq!(
+            include("").select_region(&file_lines, "open", &mut problems).unwrap(),
+            vec![(2, "print(1)".to_string())]
+        );
+        assert!(include("").select_region(&file_lines, "other", &mut problems).is_none());
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn test_line_ranges() {
+        let file_lines: Vec<&str> = SOURCE.lines().collect();
+        let mut problems = Vec::new();
+        let selected = include("").select_lines(&file_lines, "7-", &mut problems).unwrap();
+        assert_eq!(
+            selected,
+            vec![(7, "    run(x, y);".to_string()), (8, "    // endregion".to_string()), (9, "}".to_string())]
+        );
+        assert!(include("").select_lines(&file_lines, "12-14", &mut problems).is_none());
+        assert!(include("").select_lines(&file_lines, "x", &mut problems).is_none());
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn test_expand_numbers_follow_file() {
+        let dir = temp_folder("code-includes");
+        fs::write(dir.join("main.rs"), SOURCE).unwrap();
+        let config = config(&dir, false);
+        let mut problems = Vec::new();
+        let region = include("region=\"setup\" highlight=\"5\"").expand(&config, &dir, &mut problems).unwrap();
+        assert!(region[0].contains("lang: \"rust\", line-numbers: (3, 5, 7,), highlight: (5,))"));
+        let lines = include("lines=\"2-3\" numbers=true").expand(&config, &dir, &mut problems).unwrap();
+        assert!(lines[0].contains("numbers: true, start: 2)"));
+        let plain = include("lines=\"9\"").expand(&config, &dir, &mut problems).unwrap();
+        assert_eq!(plain, vec!["```rust", "}", "```"]);
+        assert!(problems.is_empty());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
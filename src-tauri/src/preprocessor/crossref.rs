@@ -0,0 +1,222 @@
+//! Pandoc-crossref style labels and references.
+//!
+//! Labels are attached with attribute blocks and lowered to Typst labels:
+//! - `# Introduction {#sec:intro}` on headings, whose attribute block may
+//!   also hold `.unnumbered` (or `-`), `.unlisted` (left out of the TOC) and
+//!   `.part` (a part page of a book)
+//! - `$$ E = m c^2 $$ {#eq:energy}` on display equations (Typst math syntax)
+//! - `![Caption](diagram.png){#fig:arch}` and `Table: Caption {#tbl:results}`
+//!   (lowered to figures by the `figures` pass; only collected here)
+//!
+//! References such as `@fig:arch` become numbered Typst references. References
+//! to labels that are never defined are reported as warnings and rendered as a
+//! visible `??label` marker instead of silently falling back to plain text.
+
+use super::{Attributes, PreprocessWarning, SourceLine, fenced_code_mask, figures, is_anchor_line, line_at, map_outside_inline_code, raw_typst, typst_string};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::collections::HashSet;
+
+const LABEL: &str = r"(?:fig|tbl|eq|sec):[\w-]+(?:[.:][\w-]+)*";
+
+lazy_static! {
+    static ref HEADING: Regex = Regex::new(r#"^(#{1,6})\s+(.*?)\s*\{((?:\s*(?:#[\w:.-]+|\.[\w-]+|-|[\w-]+=(?:"[^"]*"|\S+)))+)\s*\}\s*$"#).unwrap();
+    static ref EQUATION_INLINE: Regex = Regex::new(&format!(r"^\s*\$\$(.+)\$\$\s*\{{#({})\}}\s*$", LABEL)).unwrap();
+    static ref EQUATION_OPEN: Regex = Regex::new(r"^\s*\$\$\s*$").unwrap();
+    static ref EQUATION_CLOSE: Regex = Regex::new(&format!(r"^\s*\$\$\s*\{{#({})\}}\s*$", LABEL)).unwrap();
+    static ref REFERENCE: Regex = Regex::new(&format!(r"(^|[^\w@])@({})", LABEL)).unwrap();
+    /// Label at the end of raw Typst generated by an earlier stage (data tables, ...)
+    static ref RAW_LABEL: Regex = Regex::new(&format!(r"^\s*<!--raw-typst .*\s<({})> -->\s*$", LABEL)).unwrap();
+}
+
+/// Rewrite label definitions and `@prefix:label` references into raw Typst.
+pub(super) fn resolve_cross_references(lines: Vec<SourceLine>, safe_mode: bool, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let in_code = fenced_code_mask(&lines);
+    let labels = collect_labels(&lines, &in_code, warnings);
+    if labels.is_empty() && !lines.iter().any(|l| l.text.contains('@') || HEADING.is_match(&l.text)) {
+        return lines;
+    }
+
+    let mut out: Vec<SourceLine> = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let src = &lines[i];
+        if in_code[i] {
+            out.push(src.clone());
+            i += 1;
+            continue;
+        }
+
+        if let Some(caps) = HEADING.captures(&src.text) {
+            out.push(line_at(raw_typst(&heading(&caps)), src.line));
+            i += 1;
+            continue;
+        }
+
+        if let Some(caps) = EQUATION_INLINE.captures(&src.text)
+            && !refused_in_safe_mode(&caps[1], safe_mode, src.line, warnings)
+        {
+            out.push(line_at(raw_typst(&equation(&caps[1], &caps[2])), src.line));
+            i += 1;
+            continue;
+        }
+
+        if EQUATION_OPEN.is_match(&src.text) {
+            let close = (i + 1 .. lines.len()).take_while(|j| !lines[*j].text.trim().is_empty()).find(|j| EQUATION_CLOSE.is_match(&lines[*j].text));
+            if let Some(close) = close {
+                let body = lines[i + 1 .. close].iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+                if !refused_in_safe_mode(&body, safe_mode, src.line, warnings) {
+                    let label = EQUATION_CLOSE.captures(&lines[close].text).map(|c| c[1].to_string()).unwrap_or_default();
+                    out.push(line_at(raw_typst(&equation(&body, &label)), src.line));
+                    i = close + 1;
+                    continue;
+                }
+            }
+        }
+
+        out.push(rewrite_line_references(src, &labels, warnings));
+        i += 1;
+    }
+
+    out
+}
+
+/// First pass: find every label definition so references can be checked
+/// regardless of whether they point forwards or backwards.
+fn collect_labels(lines: &[SourceLine], in_code: &[bool], warnings: &mut Vec<PreprocessWarning>) -> HashSet<String> {
+    let mut labels: HashSet<String> = HashSet::new();
+    for (l, code) in lines.iter().zip(in_code) {
+        if *code {
+            continue;
+        }
+        let label = HEADING
+            .captures(&l.text)
+            .and_then(|c| Attributes::parse(&c[3]).id)
+            .or_else(|| EQUATION_INLINE.captures(&l.text).map(|c| c[2].to_string()))
+            .or_else(|| EQUATION_CLOSE.captures(&l.text).map(|c| c[1].to_string()))
+            .or_else(|| figures::image_label(&l.text))
+            .or_else(|| figures::table_caption(&l.text).and_then(|(_, attrs)| attrs.id))
+            .or_else(|| RAW_LABEL.captures(&l.text).map(|c| c[1].to_string()));
+        if let Some(label) = label
+            && !labels.insert(label.clone())
+        {
+            warnings.push(PreprocessWarning::at(l.line, format!("Duplicate label '{}'", label)));
+        }
+    }
+    labels
+}
+
+fn rewrite_line_references(src: &SourceLine, labels: &HashSet<String>, warnings: &mut Vec<PreprocessWarning>) -> SourceLine {
+    // Lines that already are raw Typst are left alone: a nested raw-typst
+    // comment would end the outer one early. Image and table captions are
+    // fine, they end up in Typst strings that escape `-->`.
+    if !src.text.contains('@') || is_anchor_line(&src.text) || src.text.trim_start().starts_with("<!--raw-typst") {
+        return src.clone();
+    }
+    let text = map_outside_inline_code(&src.text, |segment| {
+        REFERENCE
+            .replace_all(segment, |caps: &Captures| {
+                let label = &caps[2];
+                let code = if labels.contains(label) {
+                    format!("#tf-ref(<{}>)", label)
+                } else {
+                    warnings.push(PreprocessWarning::at(src.line, format!("Reference to undefined label '@{}'", label)));
+                    format!("#tf-missing-ref({})", typst_string(label))
+                };
+                format!("{}{}", &caps[1], raw_typst(&code))
+            })
+            .into_owned()
+    });
+    line_at(text, src.line)
+}
+
+/// A heading with an attribute block.
+fn heading(caps: &Captures) -> String {
+    let attrs = Attributes::parse(&caps[3]);
+    if attrs.has_class("part") {
+        return format!("#tf-part({})", typst_string(&caps[2]));
+    }
+    let mut code = format!("#tf-heading({}, {}", caps[1].len(), typst_string(&caps[2]));
+    if attrs.has_class("unnumbered") || attrs.has_class("-") {
+        code.push_str(", numbered: false");
+    }
+    if attrs.has_class("unlisted") {
+        code.push_str(", outlined: false");
+    }
+    code.push(')');
+    if let Some(id) = &attrs.id {
+        code.push_str(&format!(" <{}>", id));
+    }
+    code
+}
+
+/// Equations are Typst math, where `#` switches to code.
+fn refused_in_safe_mode(body: &str, safe_mode: bool, line: usize, warnings: &mut Vec<PreprocessWarning>) -> bool {
+    let refused = safe_mode && body.contains('#');
+    if refused {
+        warnings.push(PreprocessWarning::at(line, "Safe mode: equation contains '#' and was left as text".to_string()));
+    }
+    refused
+}
+
+fn equation(body: &str, label: &str) -> String { format!("#math.equation(block: true, numbering: \"(1)\", $ {} $) <{}>", body.trim(), label) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::test_support::{lines, text};
+
+    fn run(src: &str) -> (String, Vec<PreprocessWarning>) {
+        let mut warnings = Vec::new();
+        let out = resolve_cross_references(lines(src), false, &mut warnings);
+        (text(out), warnings)
+    }
+
+    #[test]
+    fn test_figure_reference() {
+        let (md, warnings) = run("![Architecture](arch.png){#fig:arch}\n\nSee @fig:arch.");
+        assert!(md.starts_with("![Architecture](arch.png){#fig:arch}"));
+        assert!(md.contains("See <!--raw-typst #tf-ref(<fig:arch>) -->."));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_dangling_reference_warns() {
+        let (md, warnings) = run("# Intro {#sec:intro}\n\nSee @tbl:missing and `@sec:intro`.");
+        assert!(md.contains("#tf-heading(1, \"Intro\") <sec:intro>"));
+        assert!(md.contains("#tf-missing-ref(\"tbl:missing\")"));
+        assert!(md.contains("`@sec:intro`"));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_references_in_captions() {
+        let src = "![Arch](a.png){#fig:a}\n\n![Like @fig:a](b.png){#fig:b}\n\n|x|\n|-|\n\nTable: See @fig:b and @tbl:gone {#tbl:c}";
+        let (md, warnings) = run(src);
+        assert!(md.contains("![Like <!--raw-typst #tf-ref(<fig:a>) -->](b.png){#fig:b}"));
+        assert!(md.contains("Table: See <!--raw-typst #tf-ref(<fig:b>) --> and <!--raw-typst #tf-missing-ref(\"tbl:gone\") --> {#tbl:c}"));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].line, Some(7));
+
+        let mut warnings = Vec::new();
+        let lowered = figures::lower_figures(lines(&md), &mut warnings);
+        assert!(lowered[2].text.contains("alt: \"Like\""));
+        assert!(lowered[2].text.contains("caption: tf-md(\"Like <!--raw-typst #tf-ref(<fig:a>) --\\u{3e}\")"));
+    }
+
+    #[test]
+    fn test_heading_classes() {
+        let (md, warnings) = run("# Preface {- .unlisted}\n\n## Setup {#sec:setup .unnumbered}\n\n# Costs {x}");
+        assert!(md.contains("#tf-heading(1, \"Preface\", numbered: false, outlined: false) -->"));
+        assert!(md.contains("#tf-heading(2, \"Setup\", numbered: false) <sec:setup>"));
+        assert!(md.contains("# Costs {x}"));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_multiline_equation() {
+        let (md, _) = run("$$\nsum_(i=1)^n i\n$$ {#eq:sum}");
+        assert_eq!(md, "<!--raw-typst #math.equation(block: true, numbering: \"(1)\", $ sum_(i=1)^n i $) <eq:sum> -->");
+    }
+}
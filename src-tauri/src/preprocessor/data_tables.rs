@@ -0,0 +1,243 @@
+//! Tables generated from CSV/TSV/JSON files.
+//!
+//! ````markdown
+//! ```{table src="data/results.csv" columns="name,score" caption="Results" #tbl:results}
+//! ```
+//! ````
+//!
+//! The block body is ignored; the file is read at preprocess time, relative
+//! to the document, so the table always matches the data.
+//!
+//! - `columns="a,b"` picks and orders columns (JSON keys are otherwise
+//!   sorted); `headers="A,B"` renames them for display.
+//! - `align="lrc"` sets one alignment letter per column. Without it numeric
+//!   columns are right-aligned and everything else left-aligned.
+//! - `format="score:.2;amount:$,.0"` formats numeric cells per column, or all
+//!   numeric columns when no `column:` prefix is given. A spec is an optional
+//!   prefix, `,` for thousands separators, `.N` for N decimals, `%` to show
+//!   fractions as percentages, and an optional suffix.
+//! - `header-repeat=false` stops the header row repeating after page breaks.
+//! - `caption`, `#tbl:` labels, `placement` and `.unnumbered` / `.unlisted`
+//!   work as for captioned pipe tables.
+
+use super::{Attributes, PreprocessConfig, PreprocessWarning, figures, raw_typst, safe_mode, typst_string};
+use crate::utils::data::{DataTable, load_data_file};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::path::Path;
+
+lazy_static! {
+    static ref OPENER: Regex = Regex::new(r"^(\s*)(`{3,}|~{3,})\s*\{([^}]*)\}\s*$").unwrap();
+    static ref NUMBER_FORMAT: Regex = Regex::new(r"^([^,.%\d]*)(,)?(?:\.(\d+))?(%)?(.*)$").unwrap();
+}
+
+/// A fenced ```` ```{table src=...} ```` block.
+pub(super) struct DataTableBlock {
+    indent: String,
+    attrs: Attributes,
+    src: String,
+}
+
+impl DataTableBlock {
+    pub fn parse(line: &str) -> Option<Self> {
+        let caps = OPENER.captures(line)?;
+        let attrs = Attributes::parse(&caps[3]);
+        if !attrs.has_class("table") {
+            return None;
+        }
+        let src = attrs.get("src")?.to_string();
+        Some(DataTableBlock {
+            indent: caps[1].to_string(),
+            attrs,
+            src,
+        })
+    }
+
+    /// Load the data and produce the replacement line, or `None` (with a
+    /// problem recorded) when there is nothing to show.
+    pub fn expand(&self, config: &PreprocessConfig, base_dir: &Path, problems: &mut Vec<String>) -> Option<Vec<String>> {
+        let path = base_dir.join(&self.src);
+        if let Err(e) = safe_mode::check_readable(config, &path) {
+            problems.push(e);
+            return None;
+        }
+        let data = match load_data_file(&path) {
+            | Ok(data) => data,
+            | Err(e) => {
+                problems.push(format!("Cannot load table data from '{}': {}", self.src, e));
+                return None;
+            },
+        };
+
+        let columns = self.select_columns(&data, problems);
+        if columns.is_empty() {
+            problems.push(format!("No columns to show from '{}'", self.src));
+            return None;
+        }
+        let headers: Vec<String> = match self.attrs.get("headers") {
+            | Some(list) => {
+                let names: Vec<String> = split_list(list);
+                if names.len() != columns.len() {
+                    problems.push(format!("{} headers given for {} columns", names.len(), columns.len()));
+                }
+                columns.iter().enumerate().map(|(i, &c)| names.get(i).cloned().unwrap_or_else(|| data.columns[c].clone())).collect()
+            },
+            | None => columns.iter().map(|&c| data.columns[c].clone()).collect(),
+        };
+
+        let formats = self.number_formats(&data, &columns);
+        let alignments = self.alignments(&data, &columns, problems);
+
+        let mut table = format!("table(columns: {}, align: ({},)", columns.len(), alignments.join(", "));
+        let repeat = !matches!(self.attrs.get("header-repeat"), Some("false" | "no" | "0"));
+        table.push_str(&format!(", table.header(repeat: {}", repeat));
+        for header in &headers {
+            table.push_str(&format!(", strong({})", typst_string(header)));
+        }
+        table.push(')');
+        for row in &data.rows {
+            for (&c, format) in columns.iter().zip(&formats) {
+                let cell = row.get(c).map(String::as_str).unwrap_or("");
+                let cell = format.as_ref().and_then(|f| f.apply(cell)).unwrap_or_else(|| cell.to_string());
+                table.push_str(&format!(", {}", typst_string(&cell)));
+            }
+        }
+        table.push(')');
+
+        let code = match (self.attrs.get("caption"), &self.attrs.id) {
+            | (None, None) => format!("#{}", table),
+            | (caption, id) => {
+                let mut warnings: Vec<PreprocessWarning> = Vec::new();
+                let options = figures::figure_options(&self.attrs, 0, &mut warnings);
+                problems.extend(warnings.into_iter().map(|w| w.message));
+                let caption = caption.map(|c| format!(", caption: tf-md({})", typst_string(c))).unwrap_or_default();
+                let label = id.as_ref().map(|id| format!(" <{}>", id)).unwrap_or_default();
+                format!("#figure(kind: table{}{})[#{}]{}", caption, options, table, label)
+            },
+        };
+        Some(vec![format!("{}{}", self.indent, raw_typst(&code))])
+    }
+
+    fn select_columns(&self, data: &DataTable, problems: &mut Vec<String>) -> Vec<usize> {
+        let Some(list) = self.attrs.get("columns") else {
+            return (0 .. data.columns.len()).collect();
+        };
+        split_list(list)
+            .into_iter()
+            .filter_map(|name| {
+                let index = data.columns.iter().position(|c| *c == name);
+                if index.is_none() {
+                    problems.push(format!("Column '{}' not found in '{}'", name, self.src));
+                }
+                index
+            })
+            .collect()
+    }
+
+    fn number_formats(&self, data: &DataTable, columns: &[usize]) -> Vec<Option<NumberFormat>> {
+        let Some(spec) = self.attrs.get("format") else {
+            return vec![None; columns.len()];
+        };
+        let mut default = None;
+        let mut per_column: Vec<(String, NumberFormat)> = Vec::new();
+        for part in spec.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once(':') {
+                | Some((column, f)) => per_column.extend(NumberFormat::parse(f).map(|f| (column.trim().to_string(), f))),
+                | None => default = NumberFormat::parse(part),
+            }
+        }
+        columns
+            .iter()
+            .map(|&c| {
+                per_column
+                    .iter()
+                    .find(|(name, _)| *name == data.columns[c])
+                    .map(|(_, f)| f.clone())
+                    .or_else(|| default.clone())
+            })
+            .collect()
+    }
+
+    fn alignments(&self, data: &DataTable, columns: &[usize], problems: &mut Vec<String>) -> Vec<&'static str> {
+        let letters: Vec<char> = self.attrs.get("align").map(|a| a.chars().collect()).unwrap_or_default();
+        columns
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| match letters.get(i) {
+                | Some('l') => "left",
+                | Some('c') => "center",
+                | Some('r') => "right",
+                | Some(other) => {
+                    problems.push(format!("Unknown alignment '{}' (expected l, c or r)", other));
+                    "left"
+                },
+                | None if is_numeric_column(data, c) => "right",
+                | None => "left",
+            })
+            .collect()
+    }
+}
+
+fn split_list(list: &str) -> Vec<String> { list.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect() }
+
+fn is_numeric_column(data: &DataTable, column: usize) -> bool {
+    let mut cells = data.rows.iter().filter_map(|r| r.get(column)).map(|c| c.trim()).filter(|c| !c.is_empty()).peekable();
+    cells.peek().is_some() && cells.all(|c| c.parse::<f64>().is_ok())
+}
+
+/// A parsed `format` spec such as `$,.2` or `.1%`.
+#[derive(Debug, Clone)]
+struct NumberFormat {
+    prefix: String,
+    thousands: bool,
+    decimals: Option<usize>,
+    percent: bool,
+    suffix: String,
+}
+
+impl NumberFormat {
+    fn parse(spec: &str) -> Option<Self> {
+        let caps = NUMBER_FORMAT.captures(spec)?;
+        Some(NumberFormat {
+            prefix: caps[1].to_string(),
+            thousands: caps.get(2).is_some(),
+            decimals: caps.get(3).and_then(|d| d.as_str().parse().ok()),
+            percent: caps.get(4).is_some(),
+            suffix: caps[5].to_string(),
+        })
+    }
+
+    /// Format a cell, or `None` when it is not a number.
+    fn apply(&self, cell: &str) -> Option<String> {
+        let trimmed = cell.trim();
+        let mut value: f64 = trimmed.parse().ok()?;
+        if self.percent {
+            value *= 100.0;
+        }
+        let digits = match self.decimals {
+            | Some(d) => format!("{:.*}", d, value),
+            | None if self.percent => value.to_string(),
+            | None => trimmed.to_string(),
+        };
+        let digits = if self.thousands { group_thousands(&digits) } else { digits };
+        // Keep the sign in front of a currency prefix: -$1.00, not $-1.00.
+        let (sign, digits) = digits.strip_prefix('-').map(|d| ("-", d)).unwrap_or(("", &digits));
+        Some(format!("{}{}{}{}{}", sign, self.prefix, digits, if self.percent { "%" } else { "" }, self.suffix))
+    }
+}
+
+fn group_thousands(number: &str) -> String {
+    let (sign, rest) = number.strip_prefix('-').map(|r| ("-", r)).unwrap_or(("", number));
+    let (int, frac) = rest.split_once('.').map(|(i, f)| (i, Some(f))).unwrap_or((rest, None));
+    let mut grouped = String::with_capacity(int.len() + int.len() / 3);
+    for (i, ch) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(ch);
+    }
+    match frac {
+        | Some(frac) => format!("{}{}.{}", sign, grouped, frac),
+        | None => format!("{}{}", sign, grouped),
+    }
+}
@@ -0,0 +1,73 @@
+//! Text direction of a document without a `dir:` in its front matter.
+//!
+//! A document reads right-to-left when most of its letters are Arabic or
+//! Hebrew. Front matter, fenced code and comments (raw Typst, included code,
+//! charts) are not counted; the template sets the direction of mixed
+//! paragraphs itself (Unicode bidi).
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+lazy_static! {
+    static ref COMMENT: Regex = Regex::new(r"(?s)<!--.*?(?:-->|\z)").unwrap();
+}
+
+/// Whether Arabic and Hebrew letters outnumber the other letters of `text`.
+pub(super) fn is_mostly_rtl(text: &str) -> bool {
+    let mut rtl = 0usize;
+    let mut ltr = 0usize;
+    let mut fence: Option<&str> = None;
+    // Keep the line breaks of comments so fences still start lines.
+    let text = COMMENT.replace_all(text, |caps: &Captures| "\n".repeat(caps[0].matches('\n').count()));
+    let mut lines = text.lines().peekable();
+    if lines.peek().is_some_and(|l| l.trim_end() == "---") {
+        lines.next();
+        for line in lines.by_ref() {
+            if matches!(line.trim_end(), "---" | "...") {
+                break;
+            }
+        }
+    }
+    for line in lines {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[.. 3]);
+            continue;
+        }
+        for c in line.chars().filter(|c| c.is_alphabetic()) {
+            if is_rtl_letter(c) {
+                rtl += 1;
+            } else {
+                ltr += 1;
+            }
+        }
+    }
+    rtl > ltr
+}
+
+/// Hebrew, Arabic (with its supplements) and their presentation forms.
+fn is_rtl_letter(c: char) -> bool { matches!(c, '\u{0590}' ..= '\u{08FF}' | '\u{FB1D}' ..= '\u{FDFF}' | '\u{FE70}' ..= '\u{FEFF}') }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mostly_rtl() {
+        assert!(is_mostly_rtl("# مقدمة\n\nهذا نص عربي with English."));
+        assert!(!is_mostly_rtl("# Introduction\n\nEnglish text with one word: שלום."));
+        assert!(!is_mostly_rtl(""));
+    }
+
+    #[test]
+    fn test_front_matter_code_and_comments_are_ignored() {
+        let text = "---\ntitle: A long English title for this document\n---\n\nשלום עולם\n\n```rust\nfn english_identifiers_everywhere() {}\n```\n\n<!--raw-typst #table(columns: (1fr, 1fr), align: (left, left),\n[ -->\nטבלה\n<!--raw-typst ]) -->";
+        assert!(is_mostly_rtl(text));
+    }
+}
@@ -0,0 +1,125 @@
+//! Pandoc fenced divs and bracketed spans.
+//!
+//! ```markdown
+//! ::: {.callout-warning title="Before you start" #prereq}
+//! Back up your data.
+//! :::
+//!
+//! Status: [draft]{.smallcaps}
+//! ```
+//!
+//! Divs open with three or more colons followed by an attribute block (or a
+//! single bare class, as Quarto writes `::: callout-note`) and close with a
+//! line of colons; they nest. Both are lowered to calls of `tf-div` /
+//! `tf-span` in the template, which map the classes to Typst: `callout-*`,
+//! `columns` / `column`, `landscape`, `pagebreak`, `center`, `small` and
+//! `hidden` for divs; `smallcaps`, `underline`, `mark`, `small` and
+//! `hidden` for spans. Other classes are passed to the theme's handlers,
+//! and otherwise ignored, as Pandoc does. Image galleries are fenced divs
+//! too, lowered to figures by `galleries` before this pass.
+
+use super::{Attributes, PreprocessWarning, SourceLine, fenced_code_mask, line_at, map_outside_inline_code, raw_typst, typst_string};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+lazy_static! {
+    static ref DIV_OPEN: Regex = Regex::new(r"^\s*:{3,}\s*(?:\{([^}]*)\}|([A-Za-z][\w-]*))\s*:*\s*$").unwrap();
+    static ref DIV_CLOSE: Regex = Regex::new(r"^\s*:{3,}\s*$").unwrap();
+    static ref SPAN: Regex = Regex::new(r"(^|[^!\]\\])\[([^\[\]]+)\]\{([^}]*)\}").unwrap();
+    static ref LENGTH: Regex = Regex::new(r"^\d+(\.\d+)?(%|pt|mm|cm|in|px)$").unwrap();
+    static ref LABEL_NAME: Regex = Regex::new(r"^[\w:.-]+$").unwrap();
+}
+
+/// Lower fenced divs and bracketed spans to raw Typst.
+pub(super) fn lower_divs(lines: Vec<SourceLine>, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let in_code = fenced_code_mask(&lines);
+    let mut out: Vec<SourceLine> = Vec::with_capacity(lines.len());
+    // Labels to attach when each open div closes
+    let mut open: Vec<(Option<String>, usize)> = Vec::new();
+    for (src, code) in lines.into_iter().zip(in_code) {
+        if code {
+            out.push(src);
+            continue;
+        }
+        if let Some(attrs) = div_opening(&src.text) {
+            let label = attrs.id.clone().filter(|id| LABEL_NAME.is_match(id));
+            out.push(line_at(raw_typst(&format!("#tf-div({})[", call_args(&attrs, src.line, warnings))), src.line));
+            open.push((label, src.line));
+            continue;
+        }
+        if is_div_closing(&src.text)
+            && let Some((label, _)) = open.pop()
+        {
+            out.push(line_at(raw_typst(&close(label)), src.line));
+            continue;
+        }
+        if src.text.contains("]{") && !src.text.trim_start().starts_with("<!--raw-typst") {
+            let text = lower_spans(&src.text, src.line, warnings);
+            out.push(line_at(text, src.line));
+            continue;
+        }
+        out.push(src);
+    }
+
+    // Pandoc closes divs left open at the end of the document.
+    while let Some((label, line)) = open.pop() {
+        warnings.push(PreprocessWarning::at(line, "Fenced div is never closed (missing ':::')".to_string()));
+        out.push(line_at(String::new(), line));
+        out.push(line_at(raw_typst(&close(label)), line));
+    }
+    out
+}
+
+/// Attributes of a line opening a div, `None` for any other line.
+pub(super) fn div_opening(text: &str) -> Option<Attributes> {
+    let caps = DIV_OPEN.captures(text)?;
+    Some(match (caps.get(1), caps.get(2)) {
+        | (Some(inner), _) => Attributes::parse(inner.as_str()),
+        | (None, Some(class)) => Attributes::parse(&format!(".{}", class.as_str())),
+        | (None, None) => Attributes::default(),
+    })
+}
+
+pub(super) fn is_div_closing(text: &str) -> bool { DIV_CLOSE.is_match(text) }
+
+fn close(label: Option<String>) -> String {
+    match label {
+        | Some(id) => format!("] <{}>", id),
+        | None => "]".to_string(),
+    }
+}
+
+fn lower_spans(line: &str, line_no: usize, warnings: &mut Vec<PreprocessWarning>) -> String {
+    map_outside_inline_code(line, |segment| {
+        SPAN.replace_all(segment, |caps: &Captures| {
+            let attrs = Attributes::parse(&caps[3]);
+            format!(
+                "{}{}{}{}",
+                &caps[1],
+                raw_typst(&format!("#tf-span({})[", call_args(&attrs, line_no, warnings))),
+                &caps[2],
+                raw_typst("]")
+            )
+        })
+        .into_owned()
+    })
+}
+
+/// `(classes), (attributes)` for `tf-div` / `tf-span`.
+fn call_args(attrs: &Attributes, line: usize, warnings: &mut Vec<PreprocessWarning>) -> String {
+    let classes: Vec<String> = attrs.classes.iter().map(|c| typst_string(c)).collect();
+    let mut pairs: Vec<String> = Vec::new();
+    for (key, value) in &attrs.pairs {
+        if key == "width" && !LENGTH.is_match(value) {
+            warnings.push(PreprocessWarning::at(line, format!("Invalid width '{}' (expected e.g. 40% or 6cm)", value)));
+            continue;
+        }
+        pairs.push(format!("{}: {}", typst_string(key), typst_string(value)));
+    }
+    let pairs = if pairs.is_empty() {
+        ":".to_string()
+    } else {
+        format!("{},", pairs.join(", "))
+    };
+    format!("({}), ({})", classes.iter().map(|c| format!("{},", c)).collect::<String>(), pairs)
+}
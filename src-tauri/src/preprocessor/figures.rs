@@ -0,0 +1,452 @@
+//! Numbered figures and table captions.
+//!
+//! A stand-alone image becomes a Typst `figure` when it has something to use
+//! as a caption (an explicit `caption="..."` attribute, the image title, or its
+//! alt text) or a `{#fig:...}` label:
+//!
+//! ```markdown
+//! ![Request flow](flow.png "Request flow through the gateway"){#fig:flow placement=top}
+//! ```
+//!
+//! Every stand-alone image, captioned or not, is sized and aligned by the
+//! template's `tf-image`: `width`, `height`, `fit=cover|contain|stretch` and
+//! `align=left|center|right` attributes win, otherwise the Images preferences
+//! apply. `wrap=left|right` (or `float=left|right`) puts the image beside the
+//! paragraph that follows it:
+//!
+//! ```markdown
+//! ![](portrait.jpg){width=30% wrap=right}
+//! ```
+//!
+//! Pipe tables get a caption from a `Table: ...` (or bare `: ...`) line placed
+//! directly before or after them, optionally followed by an attribute block.
+//!
+//! Supported figure attributes: `#fig:`/`#tbl:` labels, `caption="..."`,
+//! `placement=here|top|bottom|auto`, and the `.unnumbered` / `.unlisted`
+//! classes (no number / left out of the List of Figures or Tables).
+
+use super::{Attributes, PreprocessWarning, SourceLine, fenced_code_mask, is_anchor_line, line_at, raw_typst, typst_string};
+use crate::utils::typst::normalize_length;
+use lazy_static::lazy_static;
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+
+lazy_static! {
+    static ref IMAGE: Regex = Regex::new(r#"^\s*!\[([^\]]*)\]\(\s*(<[^>]*>|[^\s)]+)(?:\s+(?:"([^"]*)"|'([^']*)'))?\s*\)\s*(?:\{([^}]*)\})?\s*$"#).unwrap();
+    static ref TABLE_CAPTION: Regex = Regex::new(r"^\s*(Table)?:\s+(.*?)\s*(?:\{([^}]*)\})?\s*$").unwrap();
+    /// Resolved references (`@fig:x`) in a caption, left out of alt text
+    static ref COMMENT: Regex = Regex::new(r"<!--.*?-->").unwrap();
+}
+
+/// Width of a wrapped image without a `width` attribute; the default image
+/// width would leave no room for the text.
+const WRAP_WIDTH: &str = "40%";
+
+/// A stand-alone image line split into its parts.
+struct ImageLine {
+    alt: String,
+    path: String,
+    title: Option<String>,
+    attrs: Attributes,
+}
+
+fn parse_image_line(text: &str) -> Option<ImageLine> {
+    let caps = IMAGE.captures(text)?;
+    Some(ImageLine {
+        alt: caps[1].to_string(),
+        path: caps[2].trim_start_matches('<').trim_end_matches('>').to_string(),
+        title: caps.get(3).or_else(|| caps.get(4)).map(|m| m.as_str().to_string()),
+        attrs: caps.get(5).map(|m| Attributes::parse(m.as_str())).unwrap_or_default(),
+    })
+}
+
+/// Label attached to a stand-alone image, if any.
+pub(super) fn image_label(text: &str) -> Option<String> { parse_image_line(text).and_then(|img| img.attrs.id) }
+
+/// Caption text and attributes of a `Table: ...` line.
+pub(super) fn table_caption(text: &str) -> Option<(String, Attributes)> {
+    let caps = TABLE_CAPTION.captures(text)?;
+    let caption = caps[2].to_string();
+    if caption.is_empty() {
+        return None;
+    }
+    Some((caption, caps.get(3).map(|m| Attributes::parse(m.as_str())).unwrap_or_default()))
+}
+
+/// Lower stand-alone images and captioned tables to Typst.
+pub(super) fn lower_figures(lines: Vec<SourceLine>, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let in_code = fenced_code_mask(&lines);
+    let mut out: Vec<SourceLine> = Vec::with_capacity(lines.len());
+    let mut pending_caption: Option<(String, Attributes, usize)> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let src = &lines[i];
+        if in_code[i] {
+            out.push(src.clone());
+            i += 1;
+            continue;
+        }
+
+        if let Some(img) = parse_image_line(&src.text) {
+            let mut layout = image_layout(&img, src.line, warnings);
+            let paragraph = layout.wrap.and_then(|_| following_paragraph(&lines, &in_code, i + 1));
+            if layout.wrap.is_some() && paragraph.is_none() {
+                warnings.push(PreprocessWarning::at(
+                    src.line,
+                    "No paragraph after the image to wrap around it (headings, lists, quotes and tables don't wrap)".to_string(),
+                ));
+            } else if layout.wrap.is_some() && img.attrs.get("width").is_none() && img.attrs.get("height").is_none() {
+                layout.args.push_str(&format!(", width: {}", typst_string(WRAP_WIDTH)));
+            }
+            let (image, figure) = image_expr(&img, &layout, src.line, warnings);
+            match (layout.wrap, paragraph) {
+                | (Some(side), Some(end)) => {
+                    out.push(line_at(raw_typst(&format!("#tf-wrap({}, {})[", typst_string(side), image)), src.line));
+                    out.push(line_at(String::new(), src.line));
+                    out.extend(lines[i + 1 .. end].iter().cloned());
+                    out.push(line_at(String::new(), src.line));
+                    out.push(line_at(raw_typst("]"), src.line));
+                    i = end;
+                },
+                | _ => {
+                    // Figures keep Typst's centring unless asked otherwise.
+                    let code = match (figure, layout.align) {
+                        | (true, None) => format!("#{}", image),
+                        | (_, align) => format!("#tf-aligned({}, {})", align.map(typst_string).unwrap_or_else(|| "none".to_string()), image),
+                    };
+                    out.push(line_at(raw_typst(&code), src.line));
+                    i += 1;
+                },
+            }
+            continue;
+        }
+
+        if let Some((caption, attrs)) = table_caption(&src.text) {
+            let explicit = src.text.trim_start().starts_with("Table:") || attrs.id.is_some();
+            // Caption after the table: wrap the table we just emitted.
+            if let Some(table_start) = trailing_table_start(&out) {
+                let body = out.split_off(table_start);
+                wrap_table(&mut out, body, &caption, &attrs, src.line, warnings);
+                i += 1;
+                continue;
+            }
+            // Caption before the table: remember it until the table shows up.
+            if next_content_is_table(&lines[i + 1 ..]) {
+                pending_caption = Some((caption, attrs, src.line));
+                i += 1;
+                continue;
+            }
+            if explicit {
+                warnings.push(PreprocessWarning::at(src.line, format!("Table caption '{}' is not next to a table", caption)));
+            }
+        }
+
+        if is_table_line(&src.text) {
+            let end = (i .. lines.len())
+                .take_while(|j| !in_code[*j] && (is_table_line(&lines[*j].text) || is_anchor_line(&lines[*j].text)))
+                .last()
+                .unwrap_or(i);
+            let rows = lines[i ..= end].to_vec();
+            match pending_caption.take() {
+                | Some((caption, attrs, line)) => wrap_table(&mut out, rows, &caption, &attrs, line, warnings),
+                | None => out.extend(rows),
+            }
+            i = end + 1;
+            continue;
+        }
+
+        out.push(src.clone());
+        i += 1;
+    }
+    out
+}
+
+/// Size, alignment and wrapping requested by an image's attributes.
+struct ImageLayout {
+    /// `tf-image` arguments after the path
+    args: String,
+    align: Option<&'static str>,
+    wrap: Option<&'static str>,
+}
+
+fn image_layout(img: &ImageLine, line: usize, warnings: &mut Vec<PreprocessWarning>) -> ImageLayout {
+    let mut args = String::new();
+    let alt = COMMENT.replace_all(&img.alt, "");
+    if !alt.trim().is_empty() {
+        args.push_str(&format!(", alt: {}", typst_string(alt.trim())));
+    }
+    for key in ["width", "height"] {
+        let Some(value) = img.attrs.get(key) else { continue };
+        match normalize_length(value) {
+            | Some(length) => args.push_str(&format!(", {}: {}", key, typst_string(&length))),
+            | None => warnings.push(PreprocessWarning::at(line, format!("Invalid image {} '{}' (expected e.g. 50% or 6cm)", key, value))),
+        }
+    }
+    match img.attrs.get("fit") {
+        | None => {},
+        | Some(fit @ ("cover" | "contain" | "stretch")) => args.push_str(&format!(", fit: {}", typst_string(fit))),
+        | Some(other) => warnings.push(PreprocessWarning::at(line, format!("Unknown image fit '{}' (expected cover, contain or stretch)", other))),
+    }
+    let align = match img.attrs.get("align") {
+        | None => None,
+        | Some("left") => Some("left"),
+        | Some("center") => Some("center"),
+        | Some("right") => Some("right"),
+        | Some(other) => {
+            warnings.push(PreprocessWarning::at(line, format!("Unknown image alignment '{}' (expected left, center or right)", other)));
+            None
+        },
+    };
+    let wrap = match img.attrs.get("wrap").or_else(|| img.attrs.get("float")) {
+        | None | Some("none") => None,
+        | Some("left") => Some("left"),
+        | Some("right") => Some("right"),
+        | Some(other) => {
+            warnings.push(PreprocessWarning::at(line, format!("Unknown image wrap '{}' (expected left or right)", other)));
+            None
+        },
+    };
+    ImageLayout { args, align, wrap }
+}
+
+/// Typst expression for a stand-alone image: a figure when there is
+/// something to use as a caption or a label, otherwise the bare image.
+/// Returns whether it is a figure.
+fn image_expr(img: &ImageLine, layout: &ImageLayout, line: usize, warnings: &mut Vec<PreprocessWarning>) -> (String, bool) {
+    let image = format!("tf-image({}{})", typst_string(&img.path), layout.args);
+    let caption = image_caption(img);
+    if caption.is_none() && img.attrs.id.is_none() {
+        return (image, false);
+    }
+
+    let mut code = format!("figure({}", image);
+    if let Some(caption) = caption {
+        code.push_str(&format!(", caption: tf-md({})", typst_string(&caption)));
+    }
+    code.push_str(&figure_options(&img.attrs, line, warnings));
+    code.push(')');
+    if let Some(id) = &img.attrs.id {
+        code = format!("[#{} <{}>]", code, id);
+    }
+    (code, true)
+}
+
+/// Explicit `caption` attribute, else the title, else the alt text.
+fn image_caption(img: &ImageLine) -> Option<String> {
+    img.attrs
+        .get("caption")
+        .map(str::to_string)
+        .or(img.title.clone())
+        .filter(|c| !c.trim().is_empty())
+        .or_else(|| Some(img.alt.clone()).filter(|a| !a.trim().is_empty()))
+}
+
+/// A stand-alone image as one panel of a gallery: lettered, with its own
+/// caption and label, filling its column unless it has a size.
+pub(super) fn subfigure(text: &str, line: usize, warnings: &mut Vec<PreprocessWarning>) -> Option<String> {
+    let img = parse_image_line(text)?;
+    let mut layout = image_layout(&img, line, warnings);
+    if img.attrs.get("width").is_none() && img.attrs.get("height").is_none() {
+        layout.args.push_str(", width: \"100%\"");
+    }
+    let mut code = format!("#tf-subfigure(tf-image({}{})", typst_string(&img.path), layout.args);
+    if let Some(caption) = image_caption(&img) {
+        code.push_str(&format!(", caption: tf-md({})", typst_string(&caption)));
+    }
+    code.push(')');
+    if let Some(id) = &img.attrs.id {
+        code.push_str(&format!(" <{}>", id));
+    }
+    Some(code)
+}
+
+/// End (exclusive) of the paragraph after an image, which may be preceded
+/// by blank lines and anchors. `None` when the next block is anything but
+/// a single plain paragraph.
+fn following_paragraph(lines: &[SourceLine], in_code: &[bool], from: usize) -> Option<usize> {
+    let start = (from .. lines.len()).find(|j| !lines[*j].text.trim().is_empty() && !is_anchor_line(&lines[*j].text))?;
+    if in_code[start] || parse_image_line(&lines[start].text).is_some() {
+        return None;
+    }
+    let end = (start .. lines.len()).find(|j| in_code[*j] || lines[*j].text.trim().is_empty()).unwrap_or(lines.len());
+    let block = lines[start .. end].iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+    let mut depth = 0;
+    let mut paragraphs = 0;
+    for event in Parser::new(&block) {
+        match event {
+            | Event::Start(tag) => {
+                if depth == 0 && !matches!(tag, Tag::Paragraph) {
+                    return None;
+                }
+                paragraphs += usize::from(depth == 0);
+                depth += 1;
+            },
+            | Event::End(_) => depth -= 1,
+            | _ if depth == 0 => return None,
+            | _ => {},
+        }
+    }
+    (paragraphs == 1).then_some(end)
+}
+
+/// Shared `figure(...)` arguments derived from attributes.
+pub(super) fn figure_options(attrs: &Attributes, line: usize, warnings: &mut Vec<PreprocessWarning>) -> String {
+    let mut options = String::new();
+    match attrs.get("placement") {
+        | None | Some("here") | Some("none") => {},
+        | Some(p @ ("top" | "bottom" | "auto")) => options.push_str(&format!(", placement: {}", p)),
+        | Some(other) => warnings.push(PreprocessWarning::at(line, format!("Unknown figure placement '{}' (expected here, top, bottom or auto)", other))),
+    }
+    if attrs.has_class("unnumbered") {
+        options.push_str(", numbering: none");
+    }
+    if attrs.has_class("unlisted") {
+        options.push_str(", outlined: false");
+    }
+    options
+}
+
+fn wrap_table(
+    out: &mut Vec<SourceLine>,
+    rows: Vec<SourceLine>,
+    caption: &str,
+    attrs: &Attributes,
+    line: usize,
+    warnings: &mut Vec<PreprocessWarning>,
+) {
+    let options = figure_options(attrs, line, warnings);
+    let open = format!("#figure(kind: table, caption: tf-md({}){})[", typst_string(caption), options);
+    let close = match &attrs.id {
+        | Some(id) => format!("] <{}>", id),
+        | None => "]".to_string(),
+    };
+    out.push(line_at(raw_typst(&open), line));
+    out.push(line_at(String::new(), line));
+    out.extend(rows);
+    out.push(line_at(String::new(), line));
+    out.push(line_at(raw_typst(&close), line));
+}
+
+/// Index where the pipe table at the end of `out` starts, skipping the blank
+/// lines and anchors between it and a trailing caption.
+fn trailing_table_start(out: &[SourceLine]) -> Option<usize> {
+    let mut end = out.len();
+    while end > 0 && (out[end - 1].text.trim().is_empty() || is_anchor_line(&out[end - 1].text)) {
+        end -= 1;
+    }
+    if end == 0 || !is_table_line(&out[end - 1].text) {
+        return None;
+    }
+    let mut start = end - 1;
+    while start > 0 && (is_table_line(&out[start - 1].text) || is_anchor_line(&out[start - 1].text)) {
+        start -= 1;
+    }
+    while start < end && is_anchor_line(&out[start].text) {
+        start += 1;
+    }
+    Some(start)
+}
+
+fn next_content_is_table(rest: &[SourceLine]) -> bool {
+    rest.iter()
+        .find(|l| !l.text.trim().is_empty() && !is_anchor_line(&l.text))
+        .is_some_and(|l| is_table_line(&l.text))
+}
+
+fn is_table_line(text: &str) -> bool { text.trim_start().starts_with('|') }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::test_support::{lines, text};
+
+    fn run(src: &str) -> (String, Vec<PreprocessWarning>) {
+        let mut warnings = Vec::new();
+        let out = lower_figures(lines(src), &mut warnings);
+        (text(out), warnings)
+    }
+
+    #[test]
+    fn test_labelled_image_figure() {
+        let (md, warnings) = run("![Architecture](arch.png){#fig:arch}");
+        assert_eq!(
+            md,
+            "<!--raw-typst #[#figure(tf-image(\"arch.png\", alt: \"Architecture\"), caption: tf-md(\"Architecture\")) <fig:arch>] -->"
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_image_layout_attributes() {
+        let (md, warnings) = run("![Logo](logo.png){width=wide height=40% fit=contain align=right}");
+        assert_eq!(
+            md,
+            "<!--raw-typst #tf-aligned(\"right\", figure(tf-image(\"logo.png\", alt: \"Logo\", height: \"40%\", fit: \"contain\"), caption: tf-md(\"Logo\"))) -->"
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("width 'wide'"));
+
+        let (md, warnings) = run("![](a.png){fit=fill align=middle wrap=up}");
+        assert_eq!(md, "<!--raw-typst #tf-aligned(none, tf-image(\"a.png\")) -->");
+        let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].contains("fit 'fill'") && messages[1].contains("alignment 'middle'") && messages[2].contains("wrap 'up'"));
+    }
+
+    #[test]
+    fn test_wrap_around_paragraph() {
+        let (md, warnings) = run("![](p.jpg){wrap=right}\n\nText beside\nthe image.\n\nAfter.");
+        assert!(
+            md.starts_with("<!--raw-typst #tf-wrap(\"right\", tf-image(\"p.jpg\", width: \"40%\"))[ -->\n\n\nText beside\nthe image.\n\n<!--raw-typst ] -->")
+        );
+        assert!(md.ends_with("After."));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_wrap_needs_a_paragraph() {
+        for next in ["# Heading", "- item", "> quote", "Text\n# Heading", "<!--raw-typst #table() -->"] {
+            let (md, warnings) = run(&format!("![](p.jpg){{float=left width=30%}}\n\n{}", next));
+            assert!(
+                md.starts_with("<!--raw-typst #tf-aligned(none, tf-image(\"p.jpg\", width: \"30%\")) -->"),
+                "{}",
+                next
+            );
+            assert_eq!(warnings.len(), 1, "{}", next);
+        }
+    }
+
+    #[test]
+    fn test_table_caption_after_table() {
+        let (md, warnings) = run("| a | b |\n|---|---|\n| 1 | 2 |\n\nTable: Results {#tbl:results}");
+        assert!(md.starts_with("<!--raw-typst #figure(kind: table, caption: tf-md(\"Results\"))[ -->\n\n| a | b |"));
+        assert!(md.ends_with("| 1 | 2 |\n\n\n<!--raw-typst ] <tbl:results> -->"));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_table_caption_before_table() {
+        let (md, warnings) = run(": Scores {#tbl:scores placement=top .unlisted}\n\n| a |\n|---|\n\nAfter.");
+        assert!(md.starts_with("\n<!--raw-typst #figure(kind: table, caption: tf-md(\"Scores\"), placement: top, outlined: false)[ -->\n\n| a |"));
+        assert!(md.ends_with("|---|\n\n<!--raw-typst ] <tbl:scores> -->\n\nAfter."));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_stray_table_captions() {
+        // A bare `:` line may be a definition list; only `Table:` or a label warns.
+        let (md, warnings) = run("Table: Lonely\n\n: Term text\n\n: Tagged {#tbl:x}");
+        assert_eq!(md, "Table: Lonely\n\n: Term text\n\n: Tagged {#tbl:x}");
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].line, Some(0));
+        assert_eq!(warnings[1].line, Some(4));
+    }
+
+    #[test]
+    fn test_unknown_table_placement_warns() {
+        let (md, warnings) = run("| a |\n|---|\nTable: Odd {placement=middle}");
+        assert!(md.starts_with("<!--raw-typst #figure(kind: table, caption: tf-md(\"Odd\"))[ -->"));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("middle"));
+    }
+}
@@ -0,0 +1,107 @@
+//! Image galleries: several images laid out in a grid as one figure.
+//!
+//! ```markdown
+//! ::: {.gallery #fig:login columns=2}
+//! ![Before](login-old.png)
+//!
+//! ![After](login-new.png){#fig:login-new}
+//!
+//! The login screen before and after the redesign.
+//! :::
+//! ```
+//!
+//! A div with the `gallery` class, or Quarto's `layout-ncol` attribute,
+//! becomes a numbered figure whose panels are lettered (a), (b), ... and
+//! captioned like stand-alone images. The shared caption is the text after
+//! the images (or a `caption="..."` attribute). `columns` (or `layout-ncol`)
+//! defaults to one row; the figure attributes of `figures` apply.
+
+use super::{Attributes, PreprocessWarning, SourceLine, divs, fenced_code_mask, figures, is_anchor_line, line_at, raw_typst, typst_string};
+
+/// Lower gallery divs; other divs are left to `divs`.
+pub(super) fn lower_galleries(lines: Vec<SourceLine>, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let in_code = fenced_code_mask(&lines);
+    let mut out: Vec<SourceLine> = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let src = &lines[i];
+        let attrs = if in_code[i] { None } else { divs::div_opening(&src.text).filter(is_gallery) };
+        let Some(attrs) = attrs else {
+            out.push(src.clone());
+            i += 1;
+            continue;
+        };
+        let Some(close) =
+            (i + 1 .. lines.len()).find(|j| !in_code[*j] && (divs::is_div_closing(&lines[*j].text) || divs::div_opening(&lines[*j].text).is_some()))
+        else {
+            warnings.push(PreprocessWarning::at(src.line, "Gallery is never closed (missing ':::')".to_string()));
+            out.push(src.clone());
+            i += 1;
+            continue;
+        };
+        if !divs::is_div_closing(&lines[close].text) {
+            warnings.push(PreprocessWarning::at(lines[close].line, "Galleries cannot contain other divs".to_string()));
+            out.push(src.clone());
+            i += 1;
+            continue;
+        }
+        out.extend(lower_gallery(&attrs, &lines[i ..= close], warnings));
+        i = close + 1;
+    }
+    out
+}
+
+fn is_gallery(attrs: &Attributes) -> bool { attrs.has_class("gallery") || attrs.get("layout-ncol").is_some() }
+
+/// `lines` runs from the opening to the closing fence.
+fn lower_gallery(attrs: &Attributes, lines: &[SourceLine], warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let open = &lines[0];
+    let close = &lines[lines.len() - 1];
+    let mut panels = Vec::new();
+    let mut caption: Vec<&str> = Vec::new();
+    for src in &lines[1 .. lines.len() - 1] {
+        if src.text.trim().is_empty() || is_anchor_line(&src.text) {
+            continue;
+        }
+        match figures::subfigure(&src.text, src.line, warnings) {
+            | Some(code) => panels.push(line_at(raw_typst(&code), src.line)),
+            | None => caption.push(src.text.trim()),
+        }
+    }
+    if panels.is_empty() {
+        warnings.push(PreprocessWarning::at(open.line, "Gallery has no images".to_string()));
+        return lines.to_vec();
+    }
+
+    let columns = match attrs.get("columns").or_else(|| attrs.get("layout-ncol")) {
+        | None => panels.len(),
+        | Some(value) => match value.parse::<usize>() {
+            | Ok(n) if n > 0 => n,
+            | _ => {
+                warnings.push(PreprocessWarning::at(
+                    open.line,
+                    format!("Invalid gallery columns '{}' (expected a whole number)", value),
+                ));
+                panels.len()
+            },
+        },
+    };
+    let caption = attrs.get("caption").map(str::to_string).unwrap_or_else(|| caption.join(" "));
+    let mut call = format!("#tf-gallery(columns: {}", columns);
+    if !caption.is_empty() {
+        call.push_str(&format!(", caption: tf-md({})", typst_string(&caption)));
+    }
+    call.push_str(&figures::figure_options(attrs, open.line, warnings));
+    call.push_str(")[");
+    let end = match &attrs.id {
+        | Some(id) => format!("] <{}>", id),
+        | None => "]".to_string(),
+    };
+
+    // Keep the scroll-sync anchors of the lines folded into the figure.
+    let mut out: Vec<SourceLine> = lines.iter().filter(|l| is_anchor_line(&l.text)).cloned().collect();
+    out.push(line_at(raw_typst(&call), open.line));
+    out.extend(panels);
+    out.push(line_at(raw_typst(&end), close.line));
+    out
+}
@@ -0,0 +1,556 @@
+//! Pandoc grid tables and multiline tables.
+//!
+//! pulldown-cmark and cmarker only know GFM pipe tables, whose cells hold a
+//! single line of inline text. The two Pandoc formats below allow lists,
+//! several paragraphs and code in a cell, so they are lowered to a Typst
+//! `table` here, with each cell's Markdown left between raw Typst brackets
+//! for cmarker to render:
+//!
+//! ```text
+//! +-----------+-----------------+        -------------------------------
+//! | Fruit     | Advantages      |         Fruit       Advantages
+//! +===========+=================+        ----------- -------------------
+//! | Bananas   | - wrapper       |         Bananas     Built-in wrapper,
+//! |           | - bright colour |                     bright colour
+//! +-----------+-----------------+        -------------------------------
+//! ```
+//!
+//! Column widths follow the widths drawn in the source, counting East Asian
+//! wide characters as two columns like a monospace editor does. Grid tables
+//! take alignment from colons in the header separator (`+:---+---:+:-:+`) and
+//! may span cells over several columns or rows; multiline tables take it
+//! from how the header text sits over its dashes, as in Pandoc. A multiline
+//! table without a header opens with the column rule, directly followed by
+//! its first row, and closes with the same rule; anything else (a `- - -`
+//! thematic break, a setext underline further down) is left alone. A
+//! `Table: ...` caption line directly before or after either format turns
+//! it into a numbered figure, as for pipe tables.
+//!
+//! This runs before anchor injection: the generated lines keep pointing at
+//! the source lines they came from, and `inject_anchors` keeps scroll-sync
+//! anchors out of the cells.
+
+use super::{Attributes, PreprocessWarning, SourceLine, fenced_code_mask, figures, line_at, raw_typst, typst_string};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref GRID_BORDER: Regex = Regex::new(r"^\+(?:[-=:]+\+)+\s*$").unwrap();
+    static ref DASH_LINE: Regex = Regex::new(r"^-{3,}\s*$").unwrap();
+    static ref COLUMN_RULE: Regex = Regex::new(r"^-+(?: +-+)+\s*$").unwrap();
+    /// Any line of dashes and spaces; inside a multiline table only its
+    /// closing line may look like this.
+    static ref DASHES: Regex = Regex::new(r"^\s*-[- ]*$").unwrap();
+}
+
+/// Fills the second column taken by a wide character in a line laid out by
+/// `columns`.
+const WIDE_PAD: char = '\0';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Default,
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    fn typst(self) -> &'static str {
+        match self {
+            | Align::Default | Align::Left => "left",
+            | Align::Center => "center",
+            | Align::Right => "right",
+        }
+    }
+}
+
+/// One cell with its Markdown content; `lines` pairs each content line with
+/// the source line it was cut from.
+struct Cell {
+    row: usize,
+    colspan: usize,
+    rowspan: usize,
+    lines: Vec<(String, usize)>,
+}
+
+struct Table {
+    /// Relative column widths
+    widths: Vec<usize>,
+    aligns: Vec<Align>,
+    header_rows: usize,
+    /// Cells in reading order (by top row, then left column)
+    cells: Vec<Cell>,
+}
+
+/// Replace grid and multiline tables in `lines` with raw Typst tables.
+pub(super) fn lower_tables(lines: Vec<SourceLine>, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let in_code = fenced_code_mask(&lines);
+    let mut out: Vec<SourceLine> = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let after_blank = i == 0 || lines[i - 1].text.trim().is_empty();
+        let found = if in_code[i] || !after_blank {
+            None
+        } else {
+            parse_grid(&lines, &in_code, i, warnings).or_else(|| parse_multiline(&lines, &in_code, i))
+        };
+        let Some((table, end)) = found else {
+            out.push(lines[i].clone());
+            i += 1;
+            continue;
+        };
+
+        // A caption may sit on either side of the table, one blank line away.
+        let mut caption = None;
+        let before = out.iter().rposition(|l| !l.text.trim().is_empty());
+        if let Some(b) = before
+            && b + 2 >= out.len()
+            && let Some(c) = figures::table_caption(&out[b].text)
+        {
+            caption = Some((c, out[b].line));
+            out.truncate(b);
+        }
+        let mut next = end;
+        if caption.is_none() {
+            let after = (end .. lines.len().min(end + 2)).find(|j| !lines[*j].text.trim().is_empty());
+            if let Some(a) = after
+                && !in_code[a]
+                && let Some(c) = figures::table_caption(&lines[a].text)
+            {
+                caption = Some((c, lines[a].line));
+                next = a + 1;
+            }
+        }
+
+        emit_table(&mut out, &table, lines[i].line, caption, warnings);
+        i = next;
+    }
+    out
+}
+
+fn emit_table(out: &mut Vec<SourceLine>, table: &Table, line: usize, caption: Option<((String, Attributes), usize)>, warnings: &mut Vec<PreprocessWarning>) {
+    let columns: Vec<String> = table.widths.iter().map(|w| format!("{}fr", w.max(&1))).collect();
+    let aligns: Vec<&str> = table.aligns.iter().map(|a| a.typst()).collect();
+    let mut open = format!("#table(columns: ({},), align: ({},)", columns.join(", "), aligns.join(", "));
+    if let Some(((text, attrs), caption_line)) = &caption {
+        let options = figures::figure_options(attrs, *caption_line, warnings);
+        open = format!("#figure(kind: table, caption: tf-md({}){})[{}", typst_string(text), options, open);
+    }
+
+    // Each cell's Markdown sits between two raw Typst lines:
+    // `#table(..., [` / `], [` / ... / `])`.
+    let mut in_header = false;
+    for (n, cell) in table.cells.iter().enumerate() {
+        let header = cell.row < table.header_rows;
+        let mut code = match n {
+            | 0 => format!("{}, ", open),
+            | _ if in_header && !header => String::from("]), "),
+            | _ => String::from("], "),
+        };
+        if header && !in_header {
+            code.push_str("table.header(");
+        }
+        in_header = header;
+        if cell.colspan > 1 || cell.rowspan > 1 {
+            code.push_str(&format!("table.cell(colspan: {}, rowspan: {})", cell.colspan, cell.rowspan));
+        }
+        code.push('[');
+
+        let cell_line = cell.lines.first().map(|(_, l)| *l).unwrap_or(line);
+        out.push(line_at(raw_typst(&code), if n == 0 { line } else { cell_line }));
+        out.push(line_at(String::new(), cell_line));
+        out.extend(cell.lines.iter().map(|(text, l)| line_at(text.clone(), *l)));
+        out.push(line_at(String::new(), cell_line));
+    }
+
+    let mut close = String::from(if in_header { "]))" } else { "])" });
+    if let Some(((_, attrs), _)) = &caption {
+        close.push(']');
+        if let Some(id) = &attrs.id {
+            close.push_str(&format!(" <{}>", id));
+        }
+    }
+    out.push(line_at(raw_typst(&close), line));
+}
+
+/// Trimmed, dedented cell content without leading or trailing blank lines.
+fn tidy(lines: Vec<(String, usize)>) -> Vec<(String, usize)> {
+    let indent = lines
+        .iter()
+        .filter(|(t, _)| !t.trim().is_empty())
+        .map(|(t, _)| t.len() - t.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut lines: Vec<(String, usize)> = lines.into_iter().map(|(t, l)| (t.trim_end().chars().skip(indent).collect(), l)).collect();
+    while lines.last().is_some_and(|(t, _)| t.is_empty()) {
+        lines.pop();
+    }
+    let first = lines.iter().position(|(t, _)| !t.is_empty()).unwrap_or(lines.len());
+    lines.split_off(first)
+}
+
+/// `text` with one entry per display column: wide characters are followed
+/// by `WIDE_PAD`, so indices line up with what the author sees.
+fn columns(text: &str) -> Vec<char> {
+    let mut out = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        out.push(c);
+        if is_wide(c) {
+            out.push(WIDE_PAD);
+        }
+    }
+    out
+}
+
+/// East Asian Wide and Fullwidth characters (CJK, Hangul, kana, fullwidth
+/// forms) and emoji, which take two columns in a monospace font.
+fn is_wide(c: char) -> bool {
+    matches!(c,
+        '\u{1100}' ..= '\u{115F}'
+        | '\u{2E80}' ..= '\u{303E}'
+        | '\u{3041}' ..= '\u{33FF}'
+        | '\u{3400}' ..= '\u{4DBF}'
+        | '\u{4E00}' ..= '\u{9FFF}'
+        | '\u{A000}' ..= '\u{A4CF}'
+        | '\u{A960}' ..= '\u{A97F}'
+        | '\u{AC00}' ..= '\u{D7A3}'
+        | '\u{F900}' ..= '\u{FAFF}'
+        | '\u{FE30}' ..= '\u{FE4F}'
+        | '\u{FF00}' ..= '\u{FF60}'
+        | '\u{FFE0}' ..= '\u{FFE6}'
+        | '\u{1F300}' ..= '\u{1F64F}'
+        | '\u{1F900}' ..= '\u{1F9FF}'
+        | '\u{20000}' ..= '\u{3FFFD}'
+    )
+}
+
+fn slice(chars: &[char], from: usize, to: usize) -> String {
+    chars
+        .get(from.min(chars.len()) .. to.min(chars.len()))
+        .map(|s| s.iter().filter(|c| **c != WIDE_PAD).collect())
+        .unwrap_or_default()
+}
+
+/// Grid table starting at `start`: returns the table and the index of the
+/// first line after it. Malformed grids are reported and left as text.
+fn parse_grid(lines: &[SourceLine], in_code: &[bool], start: usize, warnings: &mut Vec<PreprocessWarning>) -> Option<(Table, usize)> {
+    let first = &lines[start].text;
+    let indent = first.len() - first.trim_start().len();
+    if indent > 3 || !GRID_BORDER.is_match(first.trim_start()) {
+        return None;
+    }
+    let end = (start .. lines.len())
+        .take_while(|j| {
+            let t = lines[*j].text.trim_start();
+            !in_code[*j] && (t.starts_with('+') || t.starts_with('|'))
+        })
+        .last()?
+        + 1;
+    let rows: Vec<Vec<char>> = lines[start .. end].iter().map(|l| columns(l.text.trim())).collect();
+    let malformed = |what: &str, warnings: &mut Vec<PreprocessWarning>| {
+        warnings.push(PreprocessWarning::at(lines[start].line, format!("Grid table left as text: {}", what)));
+        None
+    };
+    if rows.len() < 3 || rows.last().is_none_or(|r| r.first() != Some(&'+')) {
+        return malformed("it must end with a +---+ border line", warnings);
+    }
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let rows: Vec<Vec<char>> = rows
+        .into_iter()
+        .map(|mut r| {
+            r.resize(width, ' ');
+            r
+        })
+        .collect();
+    let borders: Vec<usize> = (0 .. rows.len()).filter(|r| rows[*r][0] == '+').collect();
+    let mut bounds: Vec<usize> = (0 .. width).filter(|c| borders.iter().any(|r| rows[*r][*c] == '+')).collect();
+    bounds.dedup();
+    if bounds.len() < 2 {
+        return malformed("no columns found", warnings);
+    }
+    let ncols = bounds.len() - 1;
+    let nrows = borders.len() - 1;
+
+    // Header: rows above a `+===+` separator.
+    let header_sep = borders.iter().position(|r| rows[*r].contains(&'='));
+    let header_rows = header_sep.unwrap_or(0);
+    let align_row = &rows[borders[header_sep.unwrap_or(0)]];
+    let aligns = (0 .. ncols)
+        .map(|c| {
+            let (l, r) = (align_row[bounds[c] + 1] == ':', align_row[bounds[c + 1] - 1] == ':');
+            match (l, r) {
+                | (true, true) => Align::Center,
+                | (true, false) => Align::Left,
+                | (false, true) => Align::Right,
+                | (false, false) => Align::Default,
+            }
+        })
+        .collect();
+
+    let mut covered = vec![vec![false; ncols]; nrows];
+    let mut cells = Vec::new();
+    for r in 0 .. nrows {
+        for c in 0 .. ncols {
+            if covered[r][c] {
+                continue;
+            }
+            let top = borders[r];
+            let below = &rows[top + 1];
+            let Some(c2) = (c + 1 ..= ncols).find(|k| matches!(below[bounds[*k]], '|' | '+')) else {
+                return malformed("a cell has no right edge", warnings);
+            };
+            let Some(r2) = (r + 1 ..= nrows).find(|k| matches!(rows[borders[*k]][bounds[c] + 1], '-' | '=' | ':')) else {
+                return malformed("a cell has no bottom edge", warnings);
+            };
+            for row in covered.iter_mut().take(r2).skip(r) {
+                for cell in row.iter_mut().take(c2).skip(c) {
+                    *cell = true;
+                }
+            }
+            let content = (top + 1 .. borders[r2])
+                .map(|y| (slice(&rows[y], bounds[c] + 1, bounds[c2]), lines[start + y].line))
+                .collect();
+            cells.push(Cell {
+                row: r,
+                colspan: c2 - c,
+                rowspan: r2 - r,
+                lines: tidy(content),
+            });
+        }
+    }
+
+    Some((
+        Table {
+            widths: bounds.windows(2).map(|w| w[1] - w[0]).collect(),
+            aligns,
+            header_rows,
+            cells,
+        },
+        end,
+    ))
+}
+
+/// Multiline table starting at `start`, with a header (opened by a full
+/// dash line) or without (opened by the column rule, with no blank line
+/// after it).
+fn parse_multiline(lines: &[SourceLine], in_code: &[bool], start: usize) -> Option<(Table, usize)> {
+    let text = |j: usize| lines[j].text.as_str();
+    let usable = |j: usize| j < lines.len() && !in_code[j];
+
+    let (header, rule) = if DASH_LINE.is_match(text(start)) {
+        let rule = (start + 1 .. lines.len())
+            .take_while(|j| usable(*j) && !text(*j).trim().is_empty())
+            .find(|j| COLUMN_RULE.is_match(text(*j)))?;
+        if rule == start + 1 {
+            return None;
+        }
+        (start + 1 .. rule, rule)
+    } else if COLUMN_RULE.is_match(text(start)) {
+        (start .. start, start)
+    } else {
+        return None;
+    };
+
+    // Column ranges run from the start of one dash group to the next.
+    let rule_chars: Vec<char> = text(rule).chars().collect();
+    let groups: Vec<(usize, usize)> = {
+        let mut groups = Vec::new();
+        let mut c = 0;
+        while c < rule_chars.len() {
+            if rule_chars[c] == '-' {
+                let s = c;
+                while c < rule_chars.len() && rule_chars[c] == '-' {
+                    c += 1;
+                }
+                groups.push((s, c));
+            } else {
+                c += 1;
+            }
+        }
+        groups
+    };
+    let ranges: Vec<(usize, usize)> = (0 .. groups.len())
+        .map(|k| (groups[k].0, groups.get(k + 1).map(|g| g.0).unwrap_or(usize::MAX)))
+        .collect();
+
+    // The body ends at a full dash line (or, without a header, the column
+    // rule again) right after a row. Rows are separated by single blank
+    // lines; the search gives up at the first line that can't be in a table.
+    let body_start = rule + 1;
+    if !usable(body_start) || text(body_start).trim().is_empty() {
+        return None;
+    }
+    let mut closing = None;
+    for j in body_start + 1 .. lines.len() {
+        if !usable(j) {
+            return None;
+        }
+        let blank_before = text(j - 1).trim().is_empty();
+        if text(j).trim().is_empty() {
+            if blank_before {
+                return None;
+            }
+        } else if DASHES.is_match(text(j)) {
+            let closes = if header.is_empty() {
+                text(j).trim_end() == text(rule).trim_end()
+            } else {
+                DASH_LINE.is_match(text(j))
+            };
+            if !closes || blank_before {
+                return None;
+            }
+            closing = Some(j);
+            break;
+        }
+    }
+    let closing = closing?;
+
+    let cut = |j: usize, (from, to): (usize, usize)| (slice(&columns(&lines[j].text), from, to), lines[j].line);
+
+    // Rows are separated by blank lines; a headerless table without any
+    // blank line has one row per line.
+    let body: Vec<usize> = (body_start .. closing).collect();
+    let mut row_blocks: Vec<Vec<usize>> = Vec::new();
+    if header.is_empty() && !body.iter().any(|j| text(*j).trim().is_empty()) {
+        row_blocks = body.iter().map(|j| vec![*j]).collect();
+    } else {
+        for j in body {
+            if text(j).trim().is_empty() {
+                row_blocks.push(Vec::new());
+            } else if let Some(block) = row_blocks.last_mut() {
+                block.push(j);
+            } else {
+                row_blocks.push(vec![j]);
+            }
+        }
+        row_blocks.retain(|b| !b.is_empty());
+    }
+
+    // Alignment from the header (or first row) position over the dashes.
+    let sample: Vec<usize> = if header.is_empty() {
+        row_blocks.first().cloned().unwrap_or_default()
+    } else {
+        header.clone().collect()
+    };
+    let aligns = groups
+        .iter()
+        .map(|&(s, e)| {
+            let at = |j: usize, c: usize| columns(&lines[j].text).get(c).is_some_and(|ch| !ch.is_whitespace());
+            let left = sample.iter().any(|j| at(*j, s));
+            let right = sample.iter().any(|j| at(*j, e - 1) && !at(*j, e));
+            match (left, right) {
+                | (true, true) => Align::Default,
+                | (true, false) => Align::Left,
+                | (false, true) => Align::Right,
+                | (false, false) => Align::Center,
+            }
+        })
+        .collect();
+
+    let mut cells = Vec::new();
+    let mut blocks: Vec<Vec<usize>> = Vec::new();
+    if !header.is_empty() {
+        blocks.push(header.collect());
+    }
+    let header_rows = blocks.len();
+    blocks.extend(row_blocks);
+    for (r, block) in blocks.iter().enumerate() {
+        for &range in &ranges {
+            cells.push(Cell {
+                row: r,
+                colspan: 1,
+                rowspan: 1,
+                lines: tidy(block.iter().map(|j| cut(*j, range)).collect()),
+            });
+        }
+    }
+
+    let widths = ranges
+        .iter()
+        .zip(&groups)
+        .map(|(&(s, e), g)| if e == usize::MAX { g.1 - s } else { e - s })
+        .collect();
+    Some((
+        Table {
+            widths,
+            aligns,
+            header_rows,
+            cells,
+        },
+        closing + 1,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::test_support::lines;
+
+    fn run(src: &str) -> (String, Vec<PreprocessWarning>) {
+        let mut warnings = Vec::new();
+        let out = lower_tables(lines(src), &mut warnings);
+        (
+            out.into_iter().map(|l| l.text).filter(|t| !t.is_empty()).collect::<Vec<_>>().join("\n"),
+            warnings,
+        )
+    }
+
+    #[test]
+    fn test_grid_table_header_and_alignment() {
+        let (md, warnings) = run("+-------+-------+\n| Fruit | Price |\n+======:+:=====:+\n| - a   | 1     |\n| - b   |       |\n+-------+-------+");
+        assert!(md.starts_with("<!--raw-typst #table(columns: (8fr, 8fr,), align: (right, center,), table.header([ -->\nFruit"));
+        assert!(md.contains("<!--raw-typst ]), [ -->\n- a\n- b\n<!--raw-typst ], [ -->\n1\n<!--raw-typst ]) -->"));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_grid_table_spans() {
+        let (md, _) = run("+---+---+\n| a | b |\n+---+---+\n| wide  |\n+---+---+\n| c | d |\n+   +---+\n|   | e |\n+---+---+");
+        assert!(md.contains("table.cell(colspan: 2, rowspan: 1)[ -->\nwide"));
+        assert!(md.contains("table.cell(colspan: 1, rowspan: 2)[ -->\nc"));
+    }
+
+    #[test]
+    fn test_grid_table_wide_characters() {
+        let (md, warnings) = run("+--------+------+\n| 홍길동 | 90   |\n+--------+------+");
+        assert_eq!(
+            md,
+            "<!--raw-typst #table(columns: (9fr, 7fr,), align: (left, left,), [ -->\n홍길동\n<!--raw-typst ], [ -->\n90\n<!--raw-typst ]) -->"
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_grid_warns() {
+        let (md, warnings) = run("+---+---+\n| a | b |");
+        assert_eq!(md, "+---+---+\n| a | b |");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_multiline_table_with_header() {
+        let src = "-----------------------------\n Centered   Default     Right\n----------- ------- ---------\n   First    row       12.0\n            spans\n\n  Second    row        5.0\n-----------------------------";
+        let (md, _) = run(src);
+        assert!(md.starts_with("<!--raw-typst #table(columns: (12fr, 8fr, 9fr,), align: (center, left, right,), table.header([ -->\nCentered"));
+        assert!(md.contains("<!--raw-typst ], [ -->\nrow\nspans\n<!--raw-typst ], [ -->\n12.0"));
+        assert!(md.ends_with("5.0\n<!--raw-typst ]) -->"));
+    }
+
+    #[test]
+    fn test_headerless_multiline_table() {
+        let (md, _) = run("------- -------\nFirst   row\n\nSecond  two\n        lines\n------- -------\n\n: Caption");
+        assert!(md.starts_with("<!--raw-typst #figure(kind: table, caption: tf-md(\"Caption\"))[#table(columns: (8fr, 7fr,)"));
+        assert!(md.contains("<!--raw-typst ], [ -->\ntwo\nlines\n<!--raw-typst ])] -->"));
+    }
+
+    #[test]
+    fn test_thematic_break_is_not_a_table() {
+        let src = "Intro.\n\n- - -\n\nSome prose here.\n\nMore prose.\n\nHeading\n-------\n\nAfter.";
+        assert_eq!(run(src).0, src.replace("\n\n", "\n"));
+        // A headerless table must close with the same column rule.
+        let src = "--- ---\na   b\n-------";
+        assert_eq!(run(src).0, src);
+    }
+}
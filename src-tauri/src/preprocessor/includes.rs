@@ -0,0 +1,349 @@
+//! Quarto-style include directives.
+//!
+//! A line consisting only of `{{< include chapters/intro.md >}}` is replaced
+//! by the contents of that file, resolved relative to the file containing the
+//! directive. `shift-heading-level-by=N` demotes (or, when negative, promotes)
+//! the included headings so a chapter written with `#` can sit under a `##`.
+//!
+//! Fenced blocks that pull in other files (source code, see `code_includes`;
+//! data tables, see `data_tables`; charts, see `charts`) are expanded here as
+//! well, since they resolve paths the same way.
+//!
+//! Includes run before anchor injection, so every line of the expanded
+//! document remembers which file and line it came from; anchors and warnings
+//! are mapped back through that record once all passes have run.
+
+use super::charts::{self, ChartBlock};
+use super::code_includes::CodeInclude;
+use super::data_tables::DataTableBlock;
+use super::{AnchorMeta, PreprocessConfig, PreprocessWarning, SourceLine, line_at, safe_mode, variables};
+use crate::utils;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Nested includes deeper than this are almost certainly a mistake.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+lazy_static! {
+    static ref INCLUDE: Regex = Regex::new(r#"^\s*\{\{<\s*include\s+("[^"]+"|'[^']+'|\S+)((?:\s+[\w-]+=\S+)*)\s*>\}\}\s*$"#).unwrap();
+}
+
+/// Where one line of the expanded document came from.
+#[derive(Debug, Clone)]
+pub(super) struct LineOrigin {
+    /// Included file, or `None` for the document being rendered
+    pub file: Option<PathBuf>,
+    /// Zero-based line in that file
+    pub line: usize,
+    /// Byte offset of the start of that line in that file
+    pub offset: usize,
+}
+
+/// The document with all includes inlined, plus a per-line origin record.
+pub(super) struct ExpandedSource {
+    pub text: String,
+    pub origins: Vec<LineOrigin>,
+    /// YAML front matter of the document itself (without delimiters)
+    pub front_matter: Option<String>,
+}
+
+impl ExpandedSource {
+    fn origin(&self, line: usize) -> Option<&LineOrigin> { self.origins.get(line).or_else(|| self.origins.last()) }
+
+    /// Map a warning raised against the expanded text back to its file/line.
+    pub fn remap_warning(&self, warning: &mut PreprocessWarning) {
+        if let Some(origin) = warning.line.and_then(|l| self.origin(l)) {
+            warning.line = Some(origin.line);
+            warning.file = origin.file.as_ref().map(|f| f.to_string_lossy().to_string());
+        }
+    }
+
+    /// Run a line-based pass over the expanded text before anchors exist.
+    /// Output lines must carry the index of the input line they came from
+    /// in `line`, so the origin record and warnings stay accurate.
+    pub fn rewrite_lines(
+        self,
+        pass: impl FnOnce(Vec<SourceLine>, &mut Vec<PreprocessWarning>) -> Vec<SourceLine>,
+        warnings: &mut Vec<PreprocessWarning>,
+    ) -> Self {
+        let lines = self.text.split('\n').enumerate().map(|(i, text)| line_at(text.to_string(), i)).collect();
+        let mut pass_warnings = Vec::new();
+        let lines = pass(lines, &mut pass_warnings);
+        for w in &mut pass_warnings {
+            self.remap_warning(w);
+        }
+        warnings.extend(pass_warnings);
+
+        let origins = lines.iter().filter_map(|l| self.origin(l.line).cloned()).collect();
+        ExpandedSource {
+            text: lines.into_iter().map(|l| l.text).collect::<Vec<_>>().join("\n"),
+            origins,
+            front_matter: self.front_matter,
+        }
+    }
+
+    /// Map anchor positions in the expanded text back to the file they were
+    /// written in.
+    pub fn remap_anchors(&self, anchors: &mut [AnchorMeta]) {
+        let line_starts: Vec<usize> = std::iter::once(0).chain(self.text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        for anchor in anchors.iter_mut() {
+            let Some(origin) = self.origin(anchor.line) else {
+                continue;
+            };
+            let within_line = anchor.offset.saturating_sub(line_starts.get(anchor.line).copied().unwrap_or(0));
+            anchor.offset = origin.offset + within_line;
+            anchor.line = origin.line;
+            anchor.file = origin.file.as_ref().map(|f| f.to_string_lossy().to_string());
+        }
+    }
+}
+
+/// Inline every include directive in `markdown`.
+pub(super) fn expand_includes(markdown: &str, config: &PreprocessConfig, warnings: &mut Vec<PreprocessWarning>) -> ExpandedSource {
+    let mut expanded = ExpandedSource {
+        text: String::with_capacity(markdown.len()),
+        origins: Vec::new(),
+        front_matter: None,
+    };
+    let source_lines: Vec<&str> = markdown.split('\n').collect();
+    if let Some(len) = variables::front_matter_len(&source_lines) {
+        expanded.front_matter = Some(source_lines[1 .. len - 1].join("\n"));
+    }
+    let mut expander = Expander {
+        config,
+        stack: config.source_file.iter().map(|p| p.canonicalize().unwrap_or_else(|_| p.clone())).collect(),
+        out: Vec::new(),
+        charts: Vec::new(),
+        warnings,
+    };
+    expander.expand(markdown, None, &config.base_dir, 0);
+    charts::prune_cache(&config.cache_dir, &expander.charts);
+
+    for (i, (text, origin)) in expander.out.into_iter().enumerate() {
+        if i > 0 {
+            expanded.text.push('\n');
+        }
+        expanded.text.push_str(&text);
+        expanded.origins.push(origin);
+    }
+    expanded
+}
+
+/// A fenced block whose body is being collected before it is replaced.
+struct PendingChart {
+    block: ChartBlock,
+    origin: LineOrigin,
+    body: Vec<String>,
+}
+
+struct Expander<'a> {
+    config: &'a PreprocessConfig,
+    /// Files currently being expanded, outermost first
+    stack: Vec<PathBuf>,
+    out: Vec<(String, LineOrigin)>,
+    /// Chart drawings used by this document
+    charts: Vec<PathBuf>,
+    warnings: &'a mut Vec<PreprocessWarning>,
+}
+
+impl Expander<'_> {
+    fn warn_all(&mut self, problems: Vec<String>, origin: &LineOrigin) {
+        self.warnings.extend(problems.into_iter().map(|message| PreprocessWarning {
+            message,
+            line: Some(origin.line),
+            file: origin.file.as_ref().map(|f| f.to_string_lossy().to_string()),
+        }));
+    }
+
+    fn expand(&mut self, source: &str, file: Option<&Path>, base_dir: &Path, heading_shift: i32) {
+        // Blanked rather than deleted, so line offsets still match the file.
+        let blanked;
+        let source = if self.config.safe_mode {
+            let (text, lines) = safe_mode::blank_raw_typst(source);
+            for line in lines {
+                let origin = LineOrigin {
+                    file: file.map(Path::to_path_buf),
+                    line,
+                    offset: 0,
+                };
+                self.warn_all(vec![safe_mode::RAW_TYPST_REMOVED.to_string()], &origin);
+            }
+            blanked = text;
+            blanked.as_str()
+        } else {
+            source
+        };
+        let mut offset = 0;
+        let mut fence: Option<String> = None;
+        // Set while skipping the placeholder body of a code block that was
+        // replaced by a file's contents.
+        let mut replaced_fence = false;
+        let mut chart: Option<PendingChart> = None;
+        let source_lines: Vec<&str> = source.split('\n').collect();
+        // Front matter is metadata, not content; included files may carry their
+        // own, which is dropped as well.
+        let front_matter = variables::front_matter_len(&source_lines).unwrap_or(0);
+        for (line_no, line) in source_lines.into_iter().enumerate() {
+            let origin = LineOrigin {
+                file: file.map(Path::to_path_buf),
+                line: line_no,
+                offset,
+            };
+            offset += line.len() + 1;
+            if line_no < front_matter {
+                continue;
+            }
+
+            // Leave fenced code alone so directives can be documented.
+            let trimmed = line.trim_start();
+            if let Some(open) = &fence {
+                let closes = trimmed.starts_with(open.as_str()) && trimmed.trim_end().chars().all(|c| c == open.chars().next().unwrap_or('`'));
+                if closes {
+                    fence = None;
+                }
+                if let Some(pending) = &mut chart {
+                    if !closes {
+                        pending.body.push(line.to_string());
+                        continue;
+                    }
+                    if let Some(pending) = chart.take() {
+                        self.finish_chart(pending, base_dir);
+                    }
+                    continue;
+                }
+                if !replaced_fence {
+                    self.out.push((line.to_string(), origin));
+                }
+                continue;
+            }
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                let marker = trimmed.chars().next().unwrap_or('`');
+                fence = Some(trimmed.chars().take_while(|c| *c == marker).collect());
+                replaced_fence = false;
+                if let Some(code) = CodeInclude::parse(line) {
+                    let mut problems = Vec::new();
+                    let replacement = code.expand(self.config, base_dir, &mut problems);
+                    self.warn_all(problems, &origin);
+                    if let Some(replacement) = replacement {
+                        self.out.extend(replacement.into_iter().map(|text| (text, origin.clone())));
+                        replaced_fence = true;
+                    } else {
+                        self.out.push((code.plain_opener(), origin));
+                    }
+                    continue;
+                }
+                if let Some(block) = DataTableBlock::parse(line) {
+                    let mut problems = Vec::new();
+                    let replacement = block.expand(self.config, base_dir, &mut problems);
+                    self.warn_all(problems, &origin);
+                    // On failure the block is dropped; the warning says why.
+                    self.out.extend(replacement.unwrap_or_default().into_iter().map(|text| (text, origin.clone())));
+                    replaced_fence = true;
+                    continue;
+                }
+                if let Some(block) = ChartBlock::parse(line) {
+                    chart = Some(PendingChart {
+                        block,
+                        origin,
+                        body: Vec::new(),
+                    });
+                    continue;
+                }
+                self.out.push((line.to_string(), origin));
+                continue;
+            }
+
+            let Some(caps) = INCLUDE.captures(line) else {
+                self.out.push((shift_heading(line, heading_shift), origin));
+                continue;
+            };
+
+            let target = caps[1].trim_matches(|c| c == '"' || c == '\'');
+            let mut shift = heading_shift;
+            let mut problems = Vec::new();
+            for option in caps[2].split_whitespace() {
+                match option.split_once('=') {
+                    | Some(("shift-heading-level-by", n)) => match n.parse::<i32>() {
+                        | Ok(n) => shift += n,
+                        | Err(_) => problems.push(format!("Invalid shift-heading-level-by value '{}'", n)),
+                    },
+                    | _ => problems.push(format!("Unknown include option '{}'", option)),
+                }
+            }
+            self.warn_all(problems, &origin);
+
+            let path = base_dir.join(target);
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if self.stack.contains(&canonical) {
+                self.warn_all(vec![format!("Include cycle: '{}' includes itself", target)], &origin);
+                continue;
+            }
+            if self.stack.len() > MAX_INCLUDE_DEPTH {
+                self.warn_all(
+                    vec![format!("Includes nested deeper than {} levels; skipping '{}'", MAX_INCLUDE_DEPTH, target)],
+                    &origin,
+                );
+                continue;
+            }
+            if let Err(e) = safe_mode::check_readable(self.config, &path) {
+                self.warn_all(vec![e], &origin);
+                continue;
+            }
+            let content = match fs::read_to_string(&path) {
+                | Ok(content) => content,
+                | Err(e) => {
+                    self.warn_all(vec![format!("Cannot include '{}': {}", target, e)], &origin);
+                    continue;
+                },
+            };
+
+            let included_dir = path.parent().map(Path::to_path_buf).unwrap_or_else(|| base_dir.to_path_buf());
+            self.stack.push(canonical);
+            let start = self.out.len();
+            self.expand(&content, Some(&path), &included_dir, shift);
+            self.stack.pop();
+
+            // Image paths in the included file are relative to that file, not
+            // to the document including it. Rewrite line by line so
+            // the origin record stays aligned.
+            for (text, _) in &mut self.out[start ..] {
+                if text.contains("![") || text.contains("<img") || text.contains("#image(") || text.contains("#fig(") {
+                    *text = utils::rewrite_image_paths_in_markdown(text, &included_dir, None);
+                }
+            }
+        }
+
+        // An unclosed chart block runs to the end of the file, like any fence.
+        if let Some(pending) = chart {
+            self.finish_chart(pending, base_dir);
+        }
+    }
+
+    /// Replace a collected chart block; on failure it is dropped and the
+    /// warning says why.
+    fn finish_chart(&mut self, pending: PendingChart, base_dir: &Path) {
+        let mut problems = Vec::new();
+        let replacement = pending
+            .block
+            .expand(&pending.body.join("\n"), self.config, base_dir, &mut self.charts, &mut problems);
+        self.warn_all(problems, &pending.origin);
+        self.out
+            .extend(replacement.unwrap_or_default().into_iter().map(|text| (text, pending.origin.clone())));
+    }
+}
+
+/// Demote or promote an ATX heading by `shift` levels, clamped to 1..=6.
+fn shift_heading(line: &str, shift: i32) -> String {
+    if shift == 0 || !line.starts_with('#') {
+        return line.to_string();
+    }
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level ..];
+    if level > 6 || !(rest.is_empty() || rest.starts_with(' ') || rest.starts_with('\t')) {
+        return line.to_string();
+    }
+    let new_level = (level as i32 + shift).clamp(1, 6) as usize;
+    format!("{}{}", "#".repeat(new_level), rest)
+}
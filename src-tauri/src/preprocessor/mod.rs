@@ -0,0 +1,745 @@
+mod breaks;
+mod charts;
+mod code_includes;
+mod crossref;
+mod data_tables;
+mod direction;
+mod divs;
+mod figures;
+mod galleries;
+mod grid_tables;
+mod includes;
+mod raw_blocks;
+mod safe_mode;
+mod shortcodes;
+mod variables;
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+lazy_static! {
+    /// Markup written by `build_anchor_markup`, and the `TFANCHOR` markers of
+    /// older versions.
+    static ref ANCHOR_MARKUP: Regex = Regex::new(r"<!--tf-anchor [\w-]+ -->\n?|<!--raw-typst #text\(size: 0\.001pt\)\[TFANCHOR:[^\]]*\] -->\n?").unwrap();
+}
+
+pub use variables::preference_variables;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EditorPosition {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfPosition {
+    pub page: usize,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnchorEntry {
+    pub id: String,
+    pub editor: EditorPosition,
+    /// Included file the anchor points into; absent for the open document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pdf: Option<PdfPosition>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct SourceMapPayload {
+    pub anchors: Vec<AnchorEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnchorMeta {
+    pub id: String,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub file: Option<String>,
+}
+
+/// Non-fatal problem found while preprocessing (e.g. a dangling
+/// cross-reference). The document still renders; the frontend surfaces these
+/// next to the preview.
+#[derive(Debug, Clone, Serialize)]
+pub struct PreprocessWarning {
+    pub message: String,
+    /// Zero-based line in the user's markdown, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Included file the line belongs to; absent for the open document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl PreprocessWarning {
+    fn at(line: usize, message: String) -> Self {
+        Self {
+            message,
+            line: Some(line),
+            file: None,
+        }
+    }
+}
+
+/// Where the markdown being preprocessed lives, so directives that read other
+/// files (includes, ...) can resolve relative paths.
+#[derive(Debug, Clone)]
+pub struct PreprocessConfig {
+    /// Directory relative paths are resolved against
+    pub base_dir: PathBuf,
+    /// The document itself, when it exists on disk
+    pub source_file: Option<PathBuf>,
+    /// Extra `{{ name }}` values supplied by the caller (preferences)
+    pub variables: BTreeMap<String, String>,
+    /// Values that win over every other source (the current mail-merge row)
+    pub overrides: BTreeMap<String, String>,
+    /// Where generated files (chart drawings) are cached between renders
+    pub cache_dir: PathBuf,
+    /// Reject raw Typst and files outside `base_dir` (see `safe_mode`)
+    pub safe_mode: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PreprocessorOutput {
+    pub markdown: String,
+    pub anchors: Vec<AnchorMeta>,
+    pub warnings: Vec<PreprocessWarning>,
+    /// The document's front matter, for the template (`meta.json`), plus
+    /// `detected-dir: rtl` for right-to-left documents
+    pub metadata: serde_json::Value,
+}
+
+/// One line of the markdown being transformed, tagged with the zero-based
+/// line of the user's source it came from. Passes that run after anchor
+/// injection work on these so their warnings point at what the user typed
+/// rather than at the anchored intermediate text.
+#[derive(Debug, Clone)]
+struct SourceLine {
+    text: String,
+    line: usize,
+}
+
+/// Transform user markdown by inlining includes, lowering grid tables,
+/// injecting invisible Typst anchors used for scroll synchronisation,
+/// substituting `{{ variables }}` and Quarto shortcodes, then lowering
+/// Tideflow's markdown extensions (cross-references, figures, ...) to raw
+/// Typst.
+pub fn preprocess_markdown(markdown: &str, config: &PreprocessConfig) -> Result<PreprocessorOutput> {
+    let safe_config;
+    let config = if !config.safe_mode && safe_mode::requested_by(markdown) {
+        safe_config = PreprocessConfig {
+            safe_mode: true,
+            ..config.clone()
+        };
+        &safe_config
+    } else {
+        config
+    };
+
+    let mut warnings = Vec::new();
+    let expanded = includes::expand_includes(markdown, config, &mut warnings);
+    // Counted before grid tables become Typst code
+    let rtl = direction::is_mostly_rtl(&expanded.text);
+    let expanded = expanded.rewrite_lines(grid_tables::lower_tables, &mut warnings);
+    let (lines, mut anchors) = inject_anchors(&expanded.text)?;
+
+    // Passes below report lines of the expanded text; remap them afterwards.
+    let mut pass_warnings = Vec::new();
+    let variables = variables::Variables::collect(config, expanded.front_matter.as_deref(), &mut warnings);
+    let before = config.safe_mode.then(|| lines.iter().map(|l| l.text.clone()).collect::<Vec<_>>());
+    let lines = variables.substitute(lines, &mut pass_warnings);
+    let lines = shortcodes::expand_shortcodes(lines, &variables, &mut pass_warnings);
+    // Raw Typst was blanked while expanding includes, but a value can still
+    // complete a comment around it (`<!--{{ empty }}raw-typst ...`).
+    let lines = match before {
+        | Some(before) => safe_mode::blank_formed_raw_typst(lines, &before, &mut pass_warnings),
+        | None => lines,
+    };
+    let lines = raw_blocks::lower_raw_blocks(lines, config.safe_mode, &mut pass_warnings);
+    let lines = breaks::lower_breaks(lines, &mut pass_warnings);
+    let lines = if config.safe_mode { safe_mode::restrict_images(lines, config, &mut pass_warnings) } else { lines };
+    let lines = galleries::lower_galleries(lines, &mut pass_warnings);
+    let lines = divs::lower_divs(lines, &mut pass_warnings);
+    let lines = crossref::resolve_cross_references(lines, config.safe_mode, &mut pass_warnings);
+    let lines = figures::lower_figures(lines, &mut pass_warnings);
+
+    for w in &mut pass_warnings {
+        expanded.remap_warning(w);
+    }
+    warnings.extend(pass_warnings);
+    expanded.remap_anchors(&mut anchors);
+
+    let mut metadata = variables::front_matter_json(expanded.front_matter.as_deref());
+    if config.safe_mode {
+        safe_mode::sanitize_metadata(&mut metadata, config, &mut warnings);
+    }
+    // The template uses this when the front matter has no `dir:`
+    if rtl {
+        metadata["detected-dir"] = serde_json::Value::String("rtl".to_string());
+    }
+
+    let markdown = lines.into_iter().map(|l| l.text).collect::<Vec<_>>().join("\n");
+    Ok(PreprocessorOutput {
+        markdown,
+        anchors,
+        warnings,
+        metadata,
+    })
+}
+
+fn inject_anchors(markdown: &str) -> Result<(Vec<SourceLine>, Vec<AnchorMeta>)> {
+    let mut insertions: Vec<(usize, String)> = Vec::new();
+    let mut anchors: Vec<AnchorMeta> = Vec::new();
+    let mut seen_offsets: HashSet<usize> = HashSet::new();
+
+    // Ensure there's always a document-start anchor so preview can scroll to
+    // the top even when a cover page is rendered above content.
+    let doc_id = "tf-doc-start".to_string();
+    if !seen_offsets.contains(&0) {
+        let doc_anchor = build_anchor_markup(markdown, 0, &doc_id);
+        insertions.push((0, doc_anchor));
+        anchors.push(AnchorMeta {
+            id: doc_id.clone(),
+            offset: 0,
+            line: 0,
+            column: 0,
+            file: None,
+        });
+        seen_offsets.insert(0usize);
+    }
+
+    let raw_blocks = raw_block_ranges(markdown);
+    let parser = Parser::new_ext(markdown, Options::ENABLE_FOOTNOTES | Options::ENABLE_TASKLISTS);
+    for (event, range) in parser.into_offset_iter() {
+        if let Event::Start(tag) = event {
+            if !is_block_level(&tag) {
+                continue;
+            }
+
+            // SKIP blockquote tags - they cause issues because the anchor gets inserted
+            // between the '>' and the content. We'll still get anchors from the paragraphs
+            // inside the blockquote, which is sufficient for scrolling.
+            if matches!(tag, Tag::BlockQuote(_)) {
+                continue;
+            }
+
+            // Blocks inside a multi-line raw Typst call (table cells) are
+            // anchored once, in front of the whole call.
+            let insertion_offset = match raw_blocks.iter().find(|(start, end)| range.start > *start && range.start < *end) {
+                | Some((start, _)) => *start,
+                | None => range.start,
+            };
+
+            // If we're inserting into a blockquote line (starts with '>'), SKIP it
+            // entirely. Blockquotes (including admonitions) will get anchored
+            // via their inner paragraphs.
+            let mut line_start = insertion_offset;
+            while line_start > 0 && markdown.as_bytes()[line_start - 1] != b'\n' {
+                line_start -= 1;
+            }
+
+            // Check if this line starts with '>' (possibly with whitespace before)
+            let line_text = &markdown[line_start ..];
+            let first_line = line_text.split('\n').next().unwrap_or("");
+            if first_line.trim_start().starts_with('>') {
+                // Skip this anchor entirely - don't insert into blockquote lines
+                continue;
+            }
+
+            if !seen_offsets.insert(insertion_offset) {
+                continue;
+            }
+            let id = format!("tf-{}-{}", range.start, anchors.len());
+            let (line, column) = offset_to_line_column(markdown, range.start);
+            let anchor_markup = build_anchor_markup(markdown, insertion_offset, &id);
+            insertions.push((insertion_offset, anchor_markup));
+            anchors.push(AnchorMeta {
+                id,
+                offset: range.start,
+                line,
+                column,
+                file: None,
+            });
+        }
+    }
+
+    // Splice the anchors in while keeping track of which source line every
+    // output line belongs to. Inserted lines inherit the line they precede.
+    insertions.sort_by_key(|(offset, _)| *offset);
+    let mut lines: Vec<SourceLine> = Vec::new();
+    let mut current = String::new();
+    let mut source_line = 0;
+    let mut cursor = 0;
+    let mut push_text = |text: &str, from_source: bool, current: &mut String, source_line: &mut usize| {
+        for ch in text.chars() {
+            if ch == '\n' {
+                lines.push(SourceLine {
+                    text: std::mem::take(current),
+                    line: *source_line,
+                });
+                if from_source {
+                    *source_line += 1;
+                }
+            } else {
+                current.push(ch);
+            }
+        }
+    };
+    for (offset, snippet) in &insertions {
+        push_text(&markdown[cursor .. *offset], true, &mut current, &mut source_line);
+        push_text(snippet, false, &mut current, &mut source_line);
+        cursor = *offset;
+    }
+    push_text(&markdown[cursor ..], true, &mut current, &mut source_line);
+    lines.push(SourceLine {
+        text: current,
+        line: source_line,
+    });
+
+    Ok((lines, anchors))
+}
+
+/// Byte ranges of tables lowered to raw Typst (see `grid_tables`), from
+/// the start of the opening `<!--raw-typst #table(..., [ -->` line to the
+/// end of the line that closes its last bracket. The Markdown in between is
+/// cell content; anchors there would split the table's cells.
+fn raw_block_ranges(markdown: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    // Start of the open table and its bracket depth
+    let mut open: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in markdown.split('\n') {
+        let code = line.trim().strip_prefix("<!--raw-typst ").and_then(|rest| rest.strip_suffix("-->")).map(str::trim);
+        if let Some(code) = code {
+            match open {
+                | None if code.ends_with('[') && code.contains("table(") => open = Some((offset, 1)),
+                | None => {},
+                | Some((start, depth)) => {
+                    let depth = depth - usize::from(code.starts_with(']')) + usize::from(code.ends_with('['));
+                    if depth == 0 {
+                        ranges.push((start, offset + line.len()));
+                        open = None;
+                    } else {
+                        open = Some((start, depth));
+                    }
+                },
+            }
+        }
+        offset += line.len() + 1;
+    }
+    ranges
+}
+
+/// Mark every line that belongs to a fenced code block (fence lines
+/// included). Passes use this to leave code samples untouched.
+fn fenced_code_mask(lines: &[SourceLine]) -> Vec<bool> {
+    let mut mask = Vec::with_capacity(lines.len());
+    let mut open_fence: Option<(char, usize)> = None;
+    for l in lines {
+        let trimmed = l.text.trim_start();
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map(|c| trimmed.chars().take_while(|x| *x == c).count()).unwrap_or(0);
+        match open_fence {
+            | Some((c, len)) => {
+                mask.push(true);
+                if fence_char == Some(c) && fence_len >= len && trimmed[fence_len ..].trim().is_empty() {
+                    open_fence = None;
+                }
+            },
+            | None if fence_len >= 3 => {
+                mask.push(true);
+                open_fence = fence_char.map(|c| (c, fence_len));
+            },
+            | None => mask.push(false),
+        }
+    }
+    mask
+}
+
+/// Apply `f` to the parts of a line that are not inside inline code spans.
+fn map_outside_inline_code(line: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find('`') {
+        out.push_str(&f(&rest[.. start]));
+        let ticks = rest[start ..].chars().take_while(|c| *c == '`').count();
+        let delimiter = &rest[start .. start + ticks];
+        match rest[start + ticks ..].find(delimiter) {
+            | Some(end) => {
+                let close = start + ticks + end + ticks;
+                out.push_str(&rest[start .. close]);
+                rest = &rest[close ..];
+            },
+            | None => {
+                out.push_str(&rest[start ..]);
+                rest = "";
+            },
+        }
+    }
+    out.push_str(&f(rest));
+    out
+}
+
+/// Flatten text substituted into a line onto that line. Values must not break
+/// the line structure the later passes and the source map rely on.
+fn single_line(value: &str) -> String { value.replace(['\r', '\n'], " ") }
+
+/// Quote `value` as a Typst string literal.
+fn typst_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            | '\\' => out.push_str("\\\\"),
+            | '"' => out.push_str("\\\""),
+            | '\n' => out.push_str("\\n"),
+            | '\r' => {},
+            // `-->` would end the raw-typst comment the string is embedded in.
+            | '>' if out.ends_with("--") => out.push_str("\\u{3e}"),
+            | _ => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Wrap Typst code in the `raw-typst` comment understood by cmarker.
+fn raw_typst(code: &str) -> String { format!("<!--raw-typst {} -->", code) }
+
+/// Scroll-sync anchors injected by `inject_anchors`.
+fn is_anchor_line(text: &str) -> bool {
+    let t = text.trim();
+    t.starts_with("<!--tf-anchor ") && t.ends_with("-->")
+}
+
+/// Remove scroll-sync anchors (and the `TFANCHOR` markers of older
+/// versions) from markdown about to be saved. Raw Typst the user wrote is
+/// kept.
+pub fn strip_anchor_markup(markdown: &str) -> String { ANCHOR_MARKUP.replace_all(markdown, "").into_owned() }
+
+fn line_at(text: String, line: usize) -> SourceLine {
+    SourceLine {
+        text,
+        line,
+    }
+}
+
+/// Pandoc-style attribute block such as `{#fig:arch .wide width=50%}`.
+#[derive(Debug, Clone, Default)]
+struct Attributes {
+    id: Option<String>,
+    classes: Vec<String>,
+    pairs: Vec<(String, String)>,
+}
+
+impl Attributes {
+    /// Parse the inside of an attribute block (without the braces). Values
+    /// may be quoted with single or double quotes to include spaces.
+    fn parse(inner: &str) -> Attributes {
+        let mut attrs = Attributes::default();
+        let mut chars = inner.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let mut token = String::new();
+            let mut quote: Option<char> = None;
+            while let Some(&c) = chars.peek() {
+                match quote {
+                    | Some(q) if c == q => quote = None,
+                    | Some(_) => token.push(c),
+                    | None if c == '"' || c == '\'' => quote = Some(c),
+                    | None if c.is_whitespace() => break,
+                    | None => token.push(c),
+                }
+                chars.next();
+            }
+            if token.is_empty() {
+                break;
+            }
+            if let Some(id) = token.strip_prefix('#') {
+                attrs.id = Some(id.to_string());
+            } else if let Some(class) = token.strip_prefix('.') {
+                attrs.classes.push(class.to_string());
+            } else if let Some((key, value)) = token.split_once('=') {
+                attrs.pairs.push((key.to_string(), value.to_string()));
+            } else {
+                attrs.classes.push(token);
+            }
+        }
+        attrs
+    }
+
+    fn get(&self, key: &str) -> Option<&str> { self.pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str()) }
+
+    fn has_class(&self, class: &str) -> bool { self.classes.iter().any(|c| c == class) }
+}
+
+fn is_block_level(tag: &Tag<'_>) -> bool {
+    matches!(
+        tag,
+        Tag::Paragraph
+            | Tag::Heading { .. }
+            | Tag::BlockQuote(_)
+            | Tag::CodeBlock(_)
+            | Tag::List(_)
+            | Tag::Item
+            | Tag::FootnoteDefinition(_)
+            | Tag::Table(_)
+            | Tag::TableHead
+            | Tag::TableRow
+            | Tag::TableCell
+    )
+}
+
+fn offset_to_line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut line = 0;
+    let mut column = 0;
+    for ch in source[.. offset].chars() {
+        if ch == '\n' {
+            line += 1;
+            column = 0;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+fn build_anchor_markup(source: &str, offset: usize, id: &str) -> String {
+    let mut snippet = String::new();
+
+    // Original logic - ensure we're on a new line
+    if offset > 0 {
+        let preceding = &source[.. offset];
+        if !preceding.ends_with('\n') {
+            snippet.push('\n');
+        }
+    }
+    // A comment keyword of our own rather than raw Typst, so anchors are never
+    // confused with what the user wrote; the template turns it into a label
+    // that `typst query` can find.
+    snippet.push_str("<!--tf-anchor ");
+    snippet.push_str(id);
+    snippet.push_str(" -->\n");
+    snippet
+}
+
+pub fn attach_pdf_positions(anchors: &[AnchorMeta], positions: &HashMap<String, PdfPosition>) -> SourceMapPayload {
+    let entries = anchors
+        .iter()
+        .map(|anchor| AnchorEntry {
+            id: anchor.id.clone(),
+            editor: EditorPosition {
+                offset: anchor.offset,
+                line: anchor.line,
+                column: anchor.column,
+            },
+            file: anchor.file.clone(),
+            pdf: positions.get(&anchor.id).cloned(),
+        })
+        .collect();
+
+    SourceMapPayload {
+        anchors: entries,
+    }
+}
+
+#[allow(dead_code)]
+pub fn anchors_to_lookup(anchors: &[AnchorMeta]) -> HashMap<String, EditorPosition> {
+    anchors
+        .iter()
+        .map(|anchor| {
+            (
+                anchor.id.clone(),
+                EditorPosition {
+                    offset: anchor.offset,
+                    line: anchor.line,
+                    column: anchor.column,
+                },
+            )
+        })
+        .collect()
+}
+
+pub fn pdf_positions_from_query(json_bytes: &[u8]) -> Result<HashMap<String, PdfPosition>> {
+    let value: serde_json::Value = serde_json::from_slice(json_bytes)?;
+    let mut map = HashMap::new();
+    if let Some(entries) = value.as_array() {
+        for entry in entries {
+            if let Some(label) = find_label(entry) {
+                if !label.starts_with("tf-") {
+                    continue;
+                }
+                // Try to find a location object anywhere under this entry. Typst
+                // output varies by version and query shape; search for common
+                // variants such as { location: { page, position: { x,y } } }
+                // or nested fields like 'point', 'pos', or 'rect'.
+                if let Some((page, x, y)) = find_location(entry) {
+                    map.insert(
+                        label,
+                        PdfPosition {
+                            page,
+                            x,
+                            y,
+                        },
+                    );
+                }
+            }
+        }
+    }
+    Ok(map)
+}
+
+fn find_label(value: &serde_json::Value) -> Option<String> {
+    match value {
+        | serde_json::Value::Object(map) => {
+            if let Some(label) = map.get("label").and_then(|v| v.as_str()) {
+                return Some(label.to_owned());
+            }
+            for key in ["value", "target", "node", "fields"] {
+                if let Some(child) = map.get(key) {
+                    if let Some(found) = find_label(child) {
+                        return Some(found);
+                    }
+                }
+            }
+            None
+        },
+        | serde_json::Value::Array(arr) => arr.iter().find_map(find_label),
+        | _ => None,
+    }
+}
+
+/// Recursively search a serde_json::Value for a location-like object and
+/// extract (page, x, y) if possible. Supports keys: location, page, position,
+/// point, pos, rect (rect may provide [x0,y0,x1,y1] coords; we use y0 as
+/// baseline).
+fn find_location(value: &serde_json::Value) -> Option<(usize, f32, f32)> {
+    match value {
+        | serde_json::Value::Object(map) => {
+            // Direct location field
+            if let Some(loc) = map.get("location") {
+                if let Some(res) = extract_page_xy(loc) {
+                    return Some(res);
+                }
+            }
+            // Some outputs might put page/position at top-level
+            if let Some(res) = extract_page_xy(&serde_json::Value::Object(map.clone())) {
+                return Some(res);
+            }
+            // Recurse into children
+            for (_k, v) in map.iter() {
+                if let Some(found) = find_location(v) {
+                    return Some(found);
+                }
+            }
+            None
+        },
+        | serde_json::Value::Array(arr) => arr.iter().find_map(find_location),
+        | _ => None,
+    }
+}
+
+fn extract_page_xy(v: &serde_json::Value) -> Option<(usize, f32, f32)> {
+    if let Some(obj) = v.as_object() {
+        // Page
+        // Page may be numeric or string; accept both
+        let page = obj
+            .get("page")
+            .and_then(|p| p.as_u64().or_else(|| p.as_str().and_then(|s| s.parse::<u64>().ok())))
+            .unwrap_or(1) as usize;
+
+        // Position variants
+        if let Some(pos) = obj.get("position").or_else(|| obj.get("point")).or_else(|| obj.get("pos")) {
+            if let Some(pos_obj) = pos.as_object() {
+                let x = pos_obj
+                    .get("x")
+                    .and_then(|v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse::<f64>().ok())))
+                    .unwrap_or(0.0) as f32;
+                let y = pos_obj
+                    .get("y")
+                    .and_then(|v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse::<f64>().ok())))
+                    .unwrap_or(0.0) as f32;
+                return Some((page, x, y));
+            }
+        }
+
+        // rect variant: [x0, y0, x1, y1]
+        if let Some(rect) = obj.get("rect") {
+            if let Some(arr) = rect.as_array() {
+                if arr.len() >= 2 {
+                    let x = arr[0].as_f64().or_else(|| arr[0].as_str().and_then(|s| s.parse::<f64>().ok())).unwrap_or(0.0) as f32;
+                    let y = arr[1].as_f64().or_else(|| arr[1].as_str().and_then(|s| s.parse::<f64>().ok())).unwrap_or(0.0) as f32;
+                    return Some((page, x, y));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Fixtures shared by the tests of the preprocessor passes.
+#[cfg(test)]
+mod test_support {
+    use super::{PreprocessConfig, SourceLine, line_at};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// `src` split into lines, numbered from 0 as in the editor.
+    pub(super) fn lines(src: &str) -> Vec<SourceLine> { src.lines().enumerate().map(|(i, l)| line_at(l.to_string(), i)).collect() }
+
+    /// The text of `lines` joined back into one string.
+    pub(super) fn text(lines: Vec<SourceLine>) -> String { lines.into_iter().map(|l| l.text).collect::<Vec<_>>().join("\n") }
+
+    /// A document in `base_dir` that caches into `base_dir/.build`.
+    pub(super) fn config(base_dir: &Path, safe_mode: bool) -> PreprocessConfig {
+        PreprocessConfig {
+            base_dir: base_dir.to_path_buf(),
+            source_file: None,
+            variables: Default::default(),
+            overrides: Default::default(),
+            cache_dir: base_dir.join(".build"),
+            safe_mode,
+        }
+    }
+
+    /// A fresh, empty folder in the system's temporary folder.
+    pub(super) fn temp_folder(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tideflow-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_anchor_markup_keeps_user_typst() {
+        let md = "<!--tf-anchor tf-doc-start -->\n# Title\n<!--raw-typst #label(\"tf-mine\") -->\n<!--tf-anchor tf-9-1 -->\nText";
+        assert_eq!(strip_anchor_markup(md), "# Title\n<!--raw-typst #label(\"tf-mine\") -->\nText");
+    }
+
+    #[test]
+    fn test_detected_dir_ignores_generated_typst() {
+        let dir = test_support::temp_folder("direction");
+        let md = "# جدول\n\n+------+------+\n| أ    | ب    |\n+======+======+\n| ج    | د    |\n+------+------+";
+        let out = preprocess_markdown(md, &test_support::config(&dir, false)).unwrap();
+        assert!(out.markdown.contains("#table("));
+        assert_eq!(out.metadata["detected-dir"], "rtl");
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,145 @@
+//! Pandoc raw attributes: Typst written directly in the document.
+//!
+//! ````markdown
+//! ```{=typst}
+//! #set text(fill: blue)
+//! #lorem(20)
+//! ```
+//!
+//! Next page starts here `#pagebreak()`{=typst}.
+//! ````
+//!
+//! Raw Typst blocks and spans are passed to Typst untouched; raw content
+//! for any other format (`{=html}`, `{=latex}`, ...) is dropped, as Pandoc
+//! does when rendering to a format it was not written for. In safe mode
+//! raw Typst is dropped as well, with a warning.
+
+use super::safe_mode::RAW_TYPST_REMOVED;
+use super::{PreprocessWarning, SourceLine, line_at, raw_typst};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref RAW_FENCE: Regex = Regex::new(r"^(\s*)(`{3,}|~{3,})\s*\{=([\w-]+)\}\s*$").unwrap();
+    static ref RAW_SPAN_FORMAT: Regex = Regex::new(r"^\{=([\w-]+)\}").unwrap();
+}
+
+/// An open raw block: fence, indentation and whether it is Typst.
+struct OpenBlock {
+    indent: String,
+    fence: String,
+    typst: bool,
+    line: usize,
+}
+
+/// Lower raw blocks and raw inline spans.
+pub(super) fn lower_raw_blocks(lines: Vec<SourceLine>, safe_mode: bool, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let mut out: Vec<SourceLine> = Vec::with_capacity(lines.len());
+    let mut open: Option<OpenBlock> = None;
+    // Fence of an ordinary code block, whose content is left alone
+    let mut code_fence: Option<(char, usize)> = None;
+    for src in lines {
+        let trimmed = src.text.trim_start();
+        if let Some(block) = &open {
+            if is_closing_fence(trimmed, block.fence.chars().next().unwrap_or('`'), block.fence.len()) {
+                let close = if block.typst { format!("{}-->", block.indent) } else { String::new() };
+                out.push(line_at(close, src.line));
+                open = None;
+            } else if !block.typst {
+                out.push(line_at(String::new(), src.line));
+            } else if src.text.contains("-->") {
+                warnings.push(PreprocessWarning::at(src.line, "Raw Typst cannot contain '-->'; line skipped".to_string()));
+                out.push(line_at(String::new(), src.line));
+            } else {
+                let text = src.text.strip_prefix(block.indent.as_str()).unwrap_or(trimmed);
+                out.push(line_at(format!("{}{}", block.indent, text), src.line));
+            }
+            continue;
+        }
+        if let Some((c, len)) = code_fence {
+            if is_closing_fence(trimmed, c, len) {
+                code_fence = None;
+            }
+            out.push(src);
+            continue;
+        }
+        if let Some(caps) = RAW_FENCE.captures(&src.text) {
+            let typst = caps[3].eq_ignore_ascii_case("typst");
+            if typst && safe_mode {
+                warnings.push(PreprocessWarning::at(src.line, RAW_TYPST_REMOVED.to_string()));
+            }
+            let typst = typst && !safe_mode;
+            let opener = if typst { format!("{}<!--raw-typst", &caps[1]) } else { String::new() };
+            open = Some(OpenBlock {
+                indent: caps[1].to_string(),
+                fence: caps[2].to_string(),
+                typst,
+                line: src.line,
+            });
+            out.push(line_at(opener, src.line));
+            continue;
+        }
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map(|c| trimmed.chars().take_while(|x| *x == c).count()).unwrap_or(0);
+        if let Some(c) = fence_char
+            && fence_len >= 3
+        {
+            code_fence = Some((c, fence_len));
+            out.push(src);
+            continue;
+        }
+        if src.text.contains("{=") {
+            let text = lower_raw_spans(&src.text, src.line, safe_mode, warnings);
+            out.push(line_at(text, src.line));
+            continue;
+        }
+        out.push(src);
+    }
+
+    if let Some(block) = open {
+        warnings.push(PreprocessWarning::at(block.line, "Raw block is never closed".to_string()));
+        if block.typst {
+            out.push(line_at(format!("{}-->", block.indent), block.line));
+        }
+    }
+    out
+}
+
+fn is_closing_fence(trimmed: &str, c: char, len: usize) -> bool {
+    let run = trimmed.chars().take_while(|x| *x == c).count();
+    run >= len && trimmed[run ..].trim().is_empty()
+}
+
+/// Replace `` `code`{=typst} `` spans; spans for other formats are dropped.
+fn lower_raw_spans(line: &str, line_no: usize, safe_mode: bool, warnings: &mut Vec<PreprocessWarning>) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find('`') {
+        out.push_str(&rest[.. start]);
+        let ticks = rest[start ..].chars().take_while(|c| *c == '`').count();
+        let delimiter = &rest[start .. start + ticks];
+        let Some(end) = rest[start + ticks ..].find(delimiter) else {
+            out.push_str(&rest[start ..]);
+            return out;
+        };
+        let close = start + ticks + end + ticks;
+        let Some(caps) = RAW_SPAN_FORMAT.captures(&rest[close ..]) else {
+            out.push_str(&rest[start .. close]);
+            rest = &rest[close ..];
+            continue;
+        };
+        let code = rest[start + ticks .. close - ticks].trim();
+        if !caps[1].eq_ignore_ascii_case("typst") {
+            // Raw content for other formats is not rendered.
+        } else if safe_mode {
+            warnings.push(PreprocessWarning::at(line_no, RAW_TYPST_REMOVED.to_string()));
+        } else if code.contains("-->") {
+            warnings.push(PreprocessWarning::at(line_no, "Raw Typst cannot contain '-->'; span skipped".to_string()));
+        } else {
+            out.push_str(&raw_typst(code));
+        }
+        rest = &rest[close + caps[0].len() ..];
+    }
+    out.push_str(rest);
+    out
+}
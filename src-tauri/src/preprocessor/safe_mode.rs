@@ -0,0 +1,325 @@
+//! Safe mode, for rendering documents received from outside the team.
+//!
+//! Raw Typst can do anything the template can, including reading any file
+//! under the compile root, so in safe mode:
+//! - `<!--raw-typst ... -->` comments and `{=typst}` blocks and spans are
+//!   removed (with a warning) before any pass generates Typst of its own,
+//! - values substituted into the text cannot smuggle such comments in, nor
+//!   complete one around them (`<!--{{ empty }}raw-typst ...`), and are kept
+//!   out of the Typst generated so far,
+//! - includes, code and data files, charts and images must live in the
+//!   document's folder (or the shared assets folder),
+//! - equations containing `#` (a Typst code escape) are not lowered, and
+//!   `{{< env >}}` is unavailable,
+//! - front matter strings the template renders as Markdown (`abstract`,
+//!   `executive-summary`, ...) get the same treatment.
+//!
+//! Safe mode is on for every document when the `safe_mode` preference is
+//! set, and for a single document whose front matter says `safe-mode: true`.
+
+use super::{PreprocessConfig, PreprocessWarning, SourceLine, line_at};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use std::path::Path;
+
+pub(super) const RAW_TYPST_REMOVED: &str = "Safe mode: raw Typst was removed";
+
+lazy_static! {
+    static ref RAW_TYPST_COMMENT: Regex = Regex::new(r"(?is)<!--\s*raw-typst.*?(?:-->|\z)").unwrap();
+    static ref MARKDOWN_IMAGE: Regex = Regex::new(r"!\[([^\]]*)\]\(\s*(<[^>]*>|[^)\s]+)[^)]*\)(?:\{[^}]*\})?").unwrap();
+    // Quoted or bare `src` values (`<img src=../x.png>`)
+    static ref HTML_IMAGE: Regex = Regex::new(r#"(?i)<img\b[^>]*?\bsrc\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))[^>]*>"#).unwrap();
+}
+
+/// Whether the document's own front matter asks for safe mode.
+pub(super) fn requested_by(markdown: &str) -> bool {
+    let lines: Vec<&str> = markdown.split('\n').collect();
+    let Some(len) = super::variables::front_matter_len(&lines) else {
+        return false;
+    };
+    let yaml = serde_yaml::from_str::<YamlValue>(&lines[1 .. len - 1].join("\n")).ok();
+    yaml.as_ref().and_then(|y| y.get("safe-mode")).and_then(YamlValue::as_bool).unwrap_or(false)
+}
+
+/// Blank out raw Typst comments in `source`, keeping its length and line
+/// breaks so offsets into it stay valid. Returns the zero-based lines where
+/// something was removed.
+pub(super) fn blank_raw_typst(source: &str) -> (String, Vec<usize>) {
+    let mut lines = Vec::new();
+    let text = RAW_TYPST_COMMENT.replace_all(source, |caps: &Captures| {
+        let start = caps.get(0).map_or(0, |m| m.start());
+        lines.push(source[.. start].matches('\n').count());
+        blank(&caps[0])
+    });
+    (text.into_owned(), lines)
+}
+
+/// Blank out raw Typst comments that substitution completed: `before` holds
+/// each line's text before variables and shortcodes ran. Comments that touch
+/// no changed line were generated by earlier passes and are kept.
+pub(super) fn blank_formed_raw_typst(lines: Vec<SourceLine>, before: &[String], warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let changed: Vec<bool> = lines.iter().zip(before).map(|(l, b)| l.text != *b).collect();
+    if !changed.contains(&true) {
+        return lines;
+    }
+    let joined = lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+    let mut starts = Vec::with_capacity(lines.len());
+    let mut offset = 0;
+    for l in &lines {
+        starts.push(offset);
+        offset += l.text.len() + 1;
+    }
+    let line_of = |offset: usize| starts.partition_point(|s| *s <= offset) - 1;
+    let text = RAW_TYPST_COMMENT.replace_all(&joined, |caps: &Captures| {
+        let Some(m) = caps.get(0) else {
+            return String::new();
+        };
+        let (first, last) = (line_of(m.start()), line_of(m.end().max(m.start() + 1) - 1));
+        if !changed[first ..= last].contains(&true) {
+            return m.as_str().to_string();
+        }
+        warnings.push(PreprocessWarning::at(lines[first].line, RAW_TYPST_REMOVED.to_string()));
+        blank(m.as_str())
+    });
+    // Blanking keeps line breaks, so the lines still pair up.
+    text.split('\n').zip(&lines).map(|(t, l)| line_at(t.to_string(), l.line)).collect()
+}
+
+/// Blank raw Typst and refused images in every string of the front matter
+/// handed to the template, which renders some of them as Markdown, and
+/// defuse any comment opener left over.
+pub(super) fn sanitize_metadata(value: &mut JsonValue, config: &PreprocessConfig, warnings: &mut Vec<PreprocessWarning>) {
+    sanitize_value("", value, config, warnings);
+}
+
+fn sanitize_value(key: &str, value: &mut JsonValue, config: &PreprocessConfig, warnings: &mut Vec<PreprocessWarning>) {
+    match value {
+        | JsonValue::Object(map) =>
+            for (k, v) in map.iter_mut() {
+                let key = if key.is_empty() { k.clone() } else { format!("{}.{}", key, k) };
+                sanitize_value(&key, v, config, warnings);
+            },
+        | JsonValue::Array(items) => items.iter_mut().for_each(|v| sanitize_value(key, v, config, warnings)),
+        | JsonValue::String(text) => {
+            // Raw Typst here was already reported against its line while
+            // expanding includes.
+            let (blanked, _) = blank_raw_typst(text);
+            let lines = blanked.split('\n').map(|t| line_at(t.to_string(), 0)).collect();
+            let lines = restrict_images(lines, config, &mut Vec::new());
+            let restricted = lines.into_iter().map(|l| l.text).collect::<Vec<_>>().join("\n");
+            if restricted != blanked {
+                warnings.push(PreprocessWarning {
+                    message: format!("Safe mode: an image outside the document's folder was removed from '{}'", key),
+                    line: None,
+                    file: None,
+                });
+            }
+            *text = defuse(&restricted);
+        },
+        | _ => {},
+    }
+}
+
+/// Spaces in place of `text`, byte for byte, keeping its line breaks.
+fn blank(text: &str) -> String {
+    text.chars()
+        .map(|c| if c == '\n' { "\n".to_string() } else { " ".repeat(c.len_utf8()) })
+        .collect()
+}
+
+/// Neutralise comment openers in a substituted value; `&lt;` still renders
+/// as `<`.
+pub(super) fn defuse(value: &str) -> String { value.replace("<!--", "&lt;!--") }
+
+/// Refuse `path` unless it lies inside the document's folder.
+pub(super) fn check_readable(config: &PreprocessConfig, path: &Path) -> Result<(), String> {
+    if !config.safe_mode {
+        return Ok(());
+    }
+    // Missing files fail later with a clearer message.
+    let Ok(resolved) = path.canonicalize() else {
+        return Ok(());
+    };
+    let root = config.base_dir.canonicalize().unwrap_or_else(|_| config.base_dir.clone());
+    if resolved.starts_with(&root) {
+        Ok(())
+    } else {
+        Err(format!("Safe mode: '{}' is outside the document's folder", path.display()))
+    }
+}
+
+/// Replace images that point outside the document's folder with their alt
+/// text.
+pub(super) fn restrict_images(lines: Vec<SourceLine>, config: &PreprocessConfig, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    lines
+        .into_iter()
+        .map(|src| {
+            if !src.text.contains("![") && !src.text.to_ascii_lowercase().contains("<img") {
+                return src;
+            }
+            let mut refused = Vec::new();
+            let text = MARKDOWN_IMAGE.replace_all(&src.text, |caps: &Captures| {
+                let target = caps[2].trim_start_matches('<').trim_end_matches('>');
+                if image_allowed(config, target) {
+                    caps[0].to_string()
+                } else {
+                    refused.push(target.to_string());
+                    caps[1].to_string()
+                }
+            });
+            let text = HTML_IMAGE.replace_all(&text, |caps: &Captures| {
+                let target = caps.get(1).or(caps.get(2)).or(caps.get(3)).map_or("", |m| m.as_str());
+                if image_allowed(config, target) {
+                    caps[0].to_string()
+                } else {
+                    refused.push(target.to_string());
+                    String::new()
+                }
+            });
+            for target in refused {
+                warnings.push(PreprocessWarning::at(src.line, format!("Safe mode: image '{}' is outside the document's folder", target)));
+            }
+            line_at(text.into_owned(), src.line)
+        })
+        .collect()
+}
+
+fn image_allowed(config: &PreprocessConfig, target: &str) -> bool {
+    let lower = target.to_ascii_lowercase();
+    // Typst cannot fetch URLs, so these never read anything.
+    if lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("data:") {
+        return true;
+    }
+    // The shared assets folder at the compile root
+    let relative = target.trim_start_matches('/');
+    if (target.starts_with("/assets/") || target.starts_with("assets/")) && !relative.split('/').any(|part| part == "..") {
+        return true;
+    }
+    let target = target.strip_prefix("file://").unwrap_or(target);
+    // Typst reads a missing absolute path relative to the compile root,
+    // which holds every other document.
+    if Path::new(target).has_root() && !Path::new(target).exists() {
+        return false;
+    }
+    check_readable(config, &config.base_dir.join(target)).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::test_support::{config, temp_folder};
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A fresh folder holding `doc/` (the document's folder) and a sibling
+    /// `outside/secret.txt`.
+    fn folders(name: &str) -> (PathBuf, PathBuf) {
+        let root = temp_folder(&format!("safe-mode-{}", name));
+        fs::create_dir_all(root.join("doc")).unwrap();
+        fs::create_dir_all(root.join("outside")).unwrap();
+        fs::write(root.join("outside/secret.txt"), "secret").unwrap();
+        fs::write(root.join("doc/inside.txt"), "fine").unwrap();
+        (root.join("doc"), root)
+    }
+
+    #[test]
+    fn test_requested_by_front_matter() {
+        assert!(requested_by("---\nsafe-mode: true\n---\n# Doc"));
+        assert!(!requested_by("---\nsafe-mode: false\n---\n# Doc"));
+        assert!(!requested_by("# Doc\n\n---\nsafe-mode: true\n---"));
+    }
+
+    #[test]
+    fn test_blank_raw_typst_keeps_offsets() {
+        let source = "a <!--raw-typst #x -->b\n<!-- RAW-TYPST\n#y -->\nc <!--raw-typst unclosed";
+        let (text, lines) = blank_raw_typst(source);
+        assert_eq!(text.len(), source.len());
+        assert_eq!(text.matches('\n').count(), 3);
+        assert!(text.starts_with(&format!("a {}b\n", " ".repeat(20))));
+        assert!(text.ends_with(&format!("\nc {}", " ".repeat(22))));
+        assert_eq!(lines, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_substitution_cannot_form_raw_typst() {
+        let md = "---\nsafe-mode: true\ne: \"\"\n---\n\n<!--{{ e }}raw-typst #read(\"a\") -->\n\n<!--{{< env HOME >}}raw-typst #read(\"b\") -->\n\n<!--\n{{ e }}raw-typst #read(\"c\") -->\n\nv: {{ e }}<!--raw-typst #read(\"d\") -->";
+        let (base_dir, root) = folders("splice");
+        // Safe mode comes from the front matter here.
+        let mut config = config(&base_dir, true);
+        config.safe_mode = false;
+        let out = crate::preprocessor::preprocess_markdown(md, &config).unwrap();
+        assert!(!out.markdown.contains("raw-typst"), "{}", out.markdown);
+        assert_eq!(out.warnings.iter().filter(|w| w.message == RAW_TYPST_REMOVED).count(), 4);
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn test_metadata_is_sanitized() {
+        let md = "---\nsafe-mode: true\ntitle: \"A <!-- note\"\nabstract: |\n  Intro <!--raw-typst #read(\"/etc/passwd\") -->\n  ![Leak](../outside/secret.txt)\n---\n\n# Doc";
+        let (base_dir, root) = folders("metadata");
+        let out = crate::preprocessor::preprocess_markdown(md, &config(&base_dir, true)).unwrap();
+        let abstract_text = out.metadata["abstract"].as_str().unwrap();
+        assert!(!abstract_text.contains("raw-typst") && !abstract_text.contains("secret"), "{}", abstract_text);
+        assert!(abstract_text.contains("Leak"));
+        assert_eq!(out.metadata["title"], "A &lt;!-- note");
+        let messages: Vec<&str> = out.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(messages[0], RAW_TYPST_REMOVED);
+        assert_eq!(messages[1], "Safe mode: an image outside the document's folder was removed from 'abstract'");
+        assert_eq!(messages.len(), 2);
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn test_executive_summary_is_sanitized() {
+        let md =
+            "---\nclass: report\nexecutive-summary: |\n  Key points <!--\n  raw-typst #read(\"/etc/passwd\") -->\n  <img src=\"/etc/passwd\">\n---\n\n# Doc";
+        let (base_dir, root) = folders("summary");
+        let out = crate::preprocessor::preprocess_markdown(md, &config(&base_dir, true)).unwrap();
+        let summary = out.metadata["executive-summary"].as_str().unwrap();
+        assert!(summary.starts_with("Key points"));
+        assert!(!summary.contains("raw-typst") && !summary.contains("passwd"), "{}", summary);
+        assert_eq!(out.warnings.len(), 2, "{:?}", out.warnings);
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn test_check_readable() {
+        let (base_dir, root) = folders("readable");
+        let config = config(&base_dir, true);
+        assert!(check_readable(&config, &base_dir.join("inside.txt")).is_ok());
+        assert!(check_readable(&config, &base_dir.join("../outside/secret.txt")).is_err());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("outside/secret.txt"), base_dir.join("link.txt")).unwrap();
+            assert!(check_readable(&config, &base_dir.join("link.txt")).is_err());
+        }
+        let mut open = config.clone();
+        open.safe_mode = false;
+        assert!(check_readable(&open, &base_dir.join("../outside/secret.txt")).is_ok());
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn test_restrict_images() {
+        let (base_dir, root) = folders("images");
+        let config = config(&base_dir, true);
+        let lines = vec![
+            line_at("![Kept](inside.txt) ![Gone](../outside/secret.txt)".to_string(), 0),
+            line_at("<img src=\"/assets/../outside/secret.txt\"> ![Web](https://example.com/a.png)".to_string(), 1),
+            line_at("![Shared](/assets/logo.png)".to_string(), 2),
+            line_at("a<img src=../outside/secret.txt alt=x>b<IMG SRC=/etc/passwd>c<img src='inside.txt'>".into(), 3),
+        ];
+        let mut warnings = Vec::new();
+        let out = restrict_images(lines, &config, &mut warnings);
+        assert_eq!(out[0].text, "![Kept](inside.txt) Gone");
+        assert_eq!(out[1].text, " ![Web](https://example.com/a.png)");
+        assert_eq!(out[2].text, "![Shared](/assets/logo.png)");
+        assert_eq!(out[3].text, "abc<img src='inside.txt'>");
+        assert_eq!(warnings.len(), 4);
+        assert_eq!(warnings[1].line, Some(1));
+        assert!(warnings[3].message.contains("'/etc/passwd'"));
+        fs::remove_dir_all(root).ok();
+    }
+}
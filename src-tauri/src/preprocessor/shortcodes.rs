@@ -0,0 +1,164 @@
+//! Quarto's built-in shortcodes.
+//!
+//! ```markdown
+//! # {{< meta title >}}, version {{< var product.version >}}
+//!
+//! {{< pagebreak >}}
+//!
+//! {{< video https://example.com/intro.mp4 title="Introduction" >}}
+//! ```
+//!
+//! Supported: `pagebreak`, `meta` (front matter), `var` (the variables file),
+//! `env` (environment variables, with an optional default), `video` (a link,
+//! since a PDF cannot play it), `kbd` and `lipsum`. `include` is expanded
+//! earlier, with the other includes, when it sits on a line of its own.
+//! Anything else is dropped with a warning. `{{{< name >}}}` produces a
+//! literal `{{< name >}}`, as in Quarto.
+
+use super::variables::Variables;
+use super::{PreprocessWarning, SourceLine, fenced_code_mask, is_anchor_line, line_at, map_outside_inline_code, raw_typst, single_line};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+lazy_static! {
+    static ref SHORTCODE: Regex = Regex::new(r#"(\{)?(\{\{<\s*([A-Za-z][\w-]*)((?:\s+(?:[A-Za-z][\w-]*=)?(?:"[^"]*"|'[^']*'|[^\s"'>]+))*)\s*>\}\})(\})?"#).unwrap();
+    static ref ARGUMENT: Regex = Regex::new(r#"(?:([A-Za-z][\w-]*)=)?("[^"]*"|'[^']*'|[^\s"'>]+)"#).unwrap();
+    static ref LIPSUM_RANGE: Regex = Regex::new(r"^(\d+)(?:-(\d+))?$").unwrap();
+}
+
+/// Paragraph count above which `lipsum` is assumed to be a typo.
+const MAX_LIPSUM_PARAGRAPHS: usize = 50;
+
+/// Arguments of one shortcode call: positional values and `key=value` pairs.
+#[derive(Default)]
+struct Arguments {
+    positional: Vec<String>,
+    named: Vec<(String, String)>,
+}
+
+impl Arguments {
+    fn parse(input: &str) -> Self {
+        let mut args = Arguments::default();
+        for caps in ARGUMENT.captures_iter(input) {
+            let value = unquote(&caps[2]);
+            match caps.get(1) {
+                | Some(key) => args.named.push((key.as_str().to_string(), value)),
+                | None => args.positional.push(value),
+            }
+        }
+        args
+    }
+
+    fn get(&self, key: &str) -> Option<&str> { self.named.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str()) }
+}
+
+fn unquote(value: &str) -> String {
+    let quoted = value.len() >= 2 && ((value.starts_with('"') && value.ends_with('"')) || (value.starts_with('\'') && value.ends_with('\'')));
+    if quoted { value[1 .. value.len() - 1].to_string() } else { value.to_string() }
+}
+
+/// Replace shortcodes outside code in every line.
+pub(super) fn expand_shortcodes(lines: Vec<SourceLine>, variables: &Variables, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+    let in_code = fenced_code_mask(&lines);
+    lines
+        .into_iter()
+        .zip(in_code)
+        .map(|(src, code)| {
+            if code || !src.text.contains("{{<") || is_anchor_line(&src.text) || variables.skips(&src.text) {
+                return src;
+            }
+            let text = map_outside_inline_code(&src.text, |segment| {
+                SHORTCODE
+                    .replace_all(segment, |caps: &Captures| {
+                        let (open, close) = (caps.get(1).is_some(), caps.get(5).is_some());
+                        if open && close {
+                            return caps[2].to_string();
+                        }
+                        let args = Arguments::parse(&caps[4]);
+                        let expanded = expand(&caps[3], &args, variables, src.line, warnings).unwrap_or_else(|| caps[2].to_string());
+                        let (prefix, suffix) = (if open { "{" } else { "" }, if close { "}" } else { "" });
+                        format!("{}{}{}", prefix, single_line(&expanded), suffix)
+                    })
+                    .into_owned()
+            });
+            line_at(text, src.line)
+        })
+        .collect()
+}
+
+/// Expansion of one shortcode, or `None` to leave it as written.
+fn expand(name: &str, args: &Arguments, variables: &Variables, line: usize, warnings: &mut Vec<PreprocessWarning>) -> Option<String> {
+    let first = args.positional.first().map(String::as_str);
+    match name {
+        | "pagebreak" => Some(raw_typst("#pagebreak()")),
+        | "meta" | "var" => {
+            let Some(key) = first else {
+                warnings.push(PreprocessWarning::at(line, format!("Shortcode '{}' needs a key", name)));
+                return None;
+            };
+            let value = if name == "meta" { variables.meta(key) } else { variables.var(key) };
+            if value.is_none() {
+                let source = if name == "meta" { "the front matter" } else { "the variables file" };
+                warnings.push(PreprocessWarning::at(line, format!("'{}' is not defined in {}", key, source)));
+            }
+            value
+        },
+        | "env" if variables.safe_mode() => {
+            warnings.push(PreprocessWarning::at(line, "Safe mode: shortcode 'env' is not available".to_string()));
+            Some(String::new())
+        },
+        | "env" => {
+            let Some(key) = first else {
+                warnings.push(PreprocessWarning::at(line, "Shortcode 'env' needs a variable name".to_string()));
+                return None;
+            };
+            match (std::env::var(key), args.positional.get(1)) {
+                | (Ok(value), _) => Some(value),
+                | (Err(_), Some(default)) => Some(default.clone()),
+                | (Err(_), None) => {
+                    warnings.push(PreprocessWarning::at(line, format!("Environment variable '{}' is not set", key)));
+                    Some(String::new())
+                },
+            }
+        },
+        | "video" => {
+            let Some(url) = first.or_else(|| args.get("src")) else {
+                warnings.push(PreprocessWarning::at(line, "Shortcode 'video' needs a URL".to_string()));
+                return None;
+            };
+            let title = args.get("title").unwrap_or(url);
+            Some(format!("[▶ {}](<{}>)", title.replace(['[', ']'], ""), url))
+        },
+        | "kbd" => {
+            let keys = first.or_else(|| args.get("win")).or_else(|| args.get("linux")).or_else(|| args.get("mac"))?;
+            Some(format!("`{}`", keys.replace('`', "")))
+        },
+        | "lipsum" => {
+            let count = match first.and_then(|s| LIPSUM_RANGE.captures(s)) {
+                | Some(caps) => {
+                    // `lipsum 3` is three paragraphs, `lipsum 2-4` also three.
+                    let first: usize = caps[1].parse().unwrap_or(1);
+                    match caps.get(2).and_then(|m| m.as_str().parse::<usize>().ok()) {
+                        | Some(last) => last.saturating_sub(first) + 1,
+                        | None => first,
+                    }
+                },
+                | None if first.is_none() => 5,
+                | None => {
+                    warnings.push(PreprocessWarning::at(line, format!("Invalid lipsum count '{}'", first.unwrap_or_default())));
+                    return None;
+                },
+            };
+            let count = count.min(MAX_LIPSUM_PARAGRAPHS);
+            Some(raw_typst(&format!("#range({}).map(_ => lorem(60)).join(parbreak())", count)))
+        },
+        | "include" => {
+            warnings.push(PreprocessWarning::at(line, "Shortcode 'include' must be on a line of its own".to_string()));
+            Some(String::new())
+        },
+        | other => {
+            warnings.push(PreprocessWarning::at(line, format!("Unsupported Quarto shortcode '{}' was removed", other)));
+            Some(String::new())
+        },
+    }
+}
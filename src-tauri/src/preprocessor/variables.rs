@@ -0,0 +1,294 @@
+//! `{{ name }}` substitution.
+//!
+//! Values are looked up, in order of precedence, in:
+//! 1. caller overrides (the current row of a mail merge),
+//! 2. the document's YAML front matter,
+//! 3. the nearest `_variables.yml` / `variables.yml` found in the document's
+//!    directory or its parents (up to the enclosing git repository root),
+//! 4. the current preferences, as `prefs.<field>` (e.g. `prefs.cover_title`),
+//! 5. built-ins: `date`, `year`, `filename` and `git.rev`.
+//!
+//! Nested mappings are addressed with dots (`product.version`). Substitution
+//! skips code spans and fenced code; `\{{ name }}` produces a literal
+//! `{{ name }}`. Unknown names are left as written and reported. In safe
+//! mode values cannot open raw Typst comments.
+
+use super::{PreprocessConfig, PreprocessWarning, SourceLine, fenced_code_mask, is_anchor_line, line_at, map_outside_inline_code, safe_mode, single_line};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const VARIABLE_FILES: [&str; 2] = ["_variables.yml", "variables.yml"];
+
+lazy_static! {
+    static ref VARIABLE: Regex = Regex::new(r"(\\)?\{\{\s*([A-Za-z_][\w.-]*)\s*\}\}").unwrap();
+}
+
+/// All values visible to `{{ name }}` in one document.
+pub(super) struct Variables {
+    values: BTreeMap<String, String>,
+    /// Front matter alone, for Quarto's `{{< meta >}}`
+    meta: BTreeMap<String, String>,
+    /// The variables file alone, for Quarto's `{{< var >}}`
+    file_values: BTreeMap<String, String>,
+    base_dir: PathBuf,
+    safe_mode: bool,
+    /// Resolved on first use; spawning git on every render is not free.
+    git_rev: OnceCell<Option<String>>,
+}
+
+impl Variables {
+    pub fn collect(config: &PreprocessConfig, front_matter: Option<&str>, warnings: &mut Vec<PreprocessWarning>) -> Self {
+        let mut values = BTreeMap::new();
+
+        let now = chrono::Local::now();
+        values.insert("date".to_string(), now.format("%Y-%m-%d").to_string());
+        values.insert("year".to_string(), now.format("%Y").to_string());
+        let file_name = config.source_file.as_ref().and_then(|p| p.file_name()).map(|n| n.to_string_lossy().to_string());
+        values.insert("filename".to_string(), file_name.unwrap_or_else(|| "untitled.md".to_string()));
+
+        values.extend(config.variables.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        let mut file_values = BTreeMap::new();
+        if let Some(path) = find_variables_file(&config.base_dir) {
+            let read = safe_mode::check_readable(config, &path)
+                .and_then(|_| fs::read_to_string(&path).map_err(|e| e.to_string()))
+                .and_then(|s| serde_yaml::from_str::<YamlValue>(&s).map_err(|e| e.to_string()));
+            match read {
+                | Ok(yaml) => flatten_yaml("", &yaml, &mut file_values),
+                | Err(e) => warnings.push(PreprocessWarning {
+                    message: format!("Cannot read variables from '{}': {}", path.display(), e),
+                    line: None,
+                    file: None,
+                }),
+            }
+        }
+
+        values.extend(file_values.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        let mut meta = BTreeMap::new();
+        if let Some(yaml) = front_matter.and_then(|fm| serde_yaml::from_str::<YamlValue>(fm).ok()) {
+            flatten_yaml("", &yaml, &mut meta);
+        }
+        values.extend(meta.iter().map(|(k, v)| (k.clone(), v.clone())));
+        values.extend(config.overrides.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        Variables {
+            values,
+            meta,
+            file_values,
+            base_dir: config.base_dir.clone(),
+            safe_mode: config.safe_mode,
+            git_rev: OnceCell::new(),
+        }
+    }
+
+    /// Front matter value, as Quarto's `{{< meta name >}}` sees it.
+    pub fn meta(&self, name: &str) -> Option<String> { self.meta.get(name).map(|v| self.checked(v)) }
+
+    /// Variables file value, as Quarto's `{{< var name >}}` sees it.
+    pub fn var(&self, name: &str) -> Option<String> { self.file_values.get(name).map(|v| self.checked(v)) }
+
+    pub fn safe_mode(&self) -> bool { self.safe_mode }
+
+    /// In safe mode values stay out of raw Typst generated by earlier passes
+    /// (included code, charts, table captions), where a quote in a value
+    /// could end a string literal. The user's own raw Typst is gone by then.
+    pub fn skips(&self, text: &str) -> bool { self.safe_mode && text.trim_start().starts_with("<!--raw-typst") }
+
+    fn checked(&self, value: &str) -> String { if self.safe_mode { safe_mode::defuse(value) } else { value.to_string() } }
+
+    fn get(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.values.get(name) {
+            return Some(self.checked(value));
+        }
+        if name == "git.rev" {
+            return self.git_rev.get_or_init(|| git_revision(&self.base_dir)).clone();
+        }
+        None
+    }
+
+    /// Replace `{{ name }}` outside code in every line.
+    pub fn substitute(&self, lines: Vec<SourceLine>, warnings: &mut Vec<PreprocessWarning>) -> Vec<SourceLine> {
+        let in_code = fenced_code_mask(&lines);
+        lines
+            .into_iter()
+            .zip(in_code)
+            .map(|(src, code)| {
+                if code || !src.text.contains("{{") || is_anchor_line(&src.text) || self.skips(&src.text) {
+                    return src;
+                }
+                let text = map_outside_inline_code(&src.text, |segment| {
+                    VARIABLE
+                        .replace_all(segment, |caps: &Captures| {
+                            if caps.get(1).is_some() {
+                                return caps[0][1 ..].to_string();
+                            }
+                            match self.get(&caps[2]) {
+                                | Some(value) => single_line(&value),
+                                | None => {
+                                    warnings.push(PreprocessWarning::at(src.line, format!("Undefined variable '{}'", &caps[2])));
+                                    caps[0].to_string()
+                                },
+                            }
+                        })
+                        .into_owned()
+                });
+                line_at(text, src.line)
+            })
+            .collect()
+    }
+}
+
+/// Number of lines taken by a YAML front matter block at the top of
+/// `lines`, including both `---` delimiters. A leading `---` that is not
+/// followed by a YAML mapping is an ordinary thematic break.
+pub(super) fn front_matter_len(lines: &[&str]) -> Option<usize> {
+    if lines.first().map(|l| l.trim_end()) != Some("---") {
+        return None;
+    }
+    let close = lines.iter().skip(1).position(|l| matches!(l.trim_end(), "---" | "..."))? + 1;
+    let body = lines[1 .. close].join("\n");
+    match serde_yaml::from_str::<YamlValue>(&body) {
+        | Ok(YamlValue::Mapping(_)) => Some(close + 1),
+        | _ => None,
+    }
+}
+
+/// Front matter as JSON, or an empty object. Keys that are not strings are
+/// dropped.
+pub(super) fn front_matter_json(front_matter: Option<&str>) -> JsonValue {
+    let yaml = front_matter.and_then(|fm| serde_yaml::from_str::<YamlValue>(fm).ok());
+    match yaml.as_ref().map(yaml_to_json) {
+        | Some(value @ JsonValue::Object(_)) => value,
+        | _ => JsonValue::Object(serde_json::Map::new()),
+    }
+}
+
+fn yaml_to_json(value: &YamlValue) -> JsonValue {
+    match value {
+        | YamlValue::Mapping(map) => JsonValue::Object(map.iter().filter_map(|(k, v)| Some((yaml_scalar(k)?, yaml_to_json(v)))).collect()),
+        | YamlValue::Sequence(items) => JsonValue::Array(items.iter().map(yaml_to_json).collect()),
+        | YamlValue::Tagged(tagged) => yaml_to_json(&tagged.value),
+        | YamlValue::String(s) => JsonValue::String(s.clone()),
+        | YamlValue::Bool(b) => JsonValue::Bool(*b),
+        // Numbers stay text so version numbers like 1.10 survive.
+        | YamlValue::Number(n) => JsonValue::String(n.to_string()),
+        | YamlValue::Null => JsonValue::Null,
+    }
+}
+
+/// Preferences as `prefs.<field>` variables.
+pub fn preference_variables(prefs: &JsonValue) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    flatten_json("prefs", prefs, &mut out);
+    out
+}
+
+fn flatten_json(prefix: &str, value: &JsonValue, out: &mut BTreeMap<String, String>) {
+    match value {
+        | JsonValue::Object(map) => {
+            for (k, v) in map {
+                flatten_json(&join_key(prefix, k), v, out);
+            }
+        },
+        | JsonValue::String(s) => {
+            out.insert(prefix.to_string(), s.clone());
+        },
+        | JsonValue::Number(n) => {
+            out.insert(prefix.to_string(), n.to_string());
+        },
+        | JsonValue::Bool(b) => {
+            out.insert(prefix.to_string(), b.to_string());
+        },
+        | JsonValue::Null | JsonValue::Array(_) => {},
+    }
+}
+
+fn flatten_yaml(prefix: &str, value: &YamlValue, out: &mut BTreeMap<String, String>) {
+    match value {
+        | YamlValue::Mapping(map) => {
+            for (k, v) in map {
+                if let Some(key) = yaml_scalar(k) {
+                    flatten_yaml(&join_key(prefix, &key), v, out);
+                }
+            }
+        },
+        | YamlValue::Sequence(items) if !prefix.is_empty() => {
+            let parts: Vec<String> = items.iter().filter_map(yaml_scalar).collect();
+            out.insert(prefix.to_string(), parts.join(", "));
+        },
+        | YamlValue::Tagged(tagged) => flatten_yaml(prefix, &tagged.value, out),
+        | other if !prefix.is_empty() => {
+            if let Some(s) = yaml_scalar(other) {
+                out.insert(prefix.to_string(), s);
+            }
+        },
+        | _ => {},
+    }
+}
+
+fn yaml_scalar(value: &YamlValue) -> Option<String> {
+    match value {
+        | YamlValue::String(s) => Some(s.clone()),
+        | YamlValue::Number(n) => Some(n.to_string()),
+        | YamlValue::Bool(b) => Some(b.to_string()),
+        | _ => None,
+    }
+}
+
+fn join_key(prefix: &str, key: &str) -> String { if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) } }
+
+/// Nearest variables file in `dir` or, inside a git repository, its parents
+/// up to the repository root. Outside a repository only `dir` is searched,
+/// so a stray file in a home or temp folder is never picked up.
+fn find_variables_file(dir: &Path) -> Option<PathBuf> {
+    let repo_depth = dir.ancestors().position(|a| a.join(".git").exists()).unwrap_or(0);
+    dir.ancestors()
+        .take(repo_depth + 1)
+        .find_map(|ancestor| VARIABLE_FILES.iter().map(|name| ancestor.join(name)).find(|p| p.is_file()))
+}
+
+fn git_revision(dir: &Path) -> Option<String> {
+    let mut cmd = Command::new("git");
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = cmd.args(["rev-parse", "--short", "HEAD"]).current_dir(dir).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::test_support::{config, temp_folder};
+
+    #[test]
+    fn test_variables_file_stays_in_the_repository() {
+        let root = temp_folder("variables");
+        let doc = root.join("repo/docs");
+        fs::create_dir_all(&doc).unwrap();
+        fs::write(root.join("_variables.yml"), "stray: yes").unwrap();
+        // Outside a repository only the document's folder counts
+        assert_eq!(find_variables_file(&doc), None);
+
+        fs::create_dir_all(root.join("repo/.git")).unwrap();
+        fs::write(root.join("repo/variables.yml"), "product: Tideflow").unwrap();
+        assert_eq!(find_variables_file(&doc), Some(root.join("repo/variables.yml")));
+
+        let mut warnings = Vec::new();
+        let variables = Variables::collect(&config(&doc, true), None, &mut warnings);
+        assert_eq!(variables.var("product"), None);
+        assert_eq!(warnings.len(), 1);
+        fs::remove_dir_all(&root).ok();
+    }
+}
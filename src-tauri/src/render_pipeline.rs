@@ -6,12 +6,15 @@
 /// render_markdown, export_markdown, and render_typst functions.
 use crate::utils;
 use anyhow::{Result, anyhow};
+use lazy_static::lazy_static;
 use serde_json::Value as JsonValue;
+use std::collections::HashSet;
 use std::fs;
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Mutex;
 use tauri::{AppHandle, Emitter};
 
 /// Configuration for a render operation
@@ -23,9 +26,231 @@ pub struct RenderConfig<'a> {
 }
 
 /// Result of preferences setup including updated JSON value
-#[allow(dead_code)]
 pub struct PrefsSetupResult {
     pub prefs_json: JsonValue,
+    /// Render untrusted documents: no raw Typst, no files outside the
+    /// document's folder
+    pub safe_mode: bool,
+}
+
+/// Length preferences the template turns into Typst lengths (as JSON
+/// pointers), with the value used when the stored one is not a length.
+const LENGTH_PREFS: [(&str, &str); 4] = [
+    ("/cover_image_width", "60%"),
+    ("/default_image_width", "80%"),
+    ("/paragraph_spacing", "0.65em"),
+    ("/header_footer/logo_height", "0.8cm"),
+];
+
+/// Image preferences (as JSON pointers) copied into the assets folder so
+/// Typst can read them.
+const IMAGE_PREFS: [&str; 4] = ["/cover_image", "/cover_logo", "/cover_background", "/header_footer/logo"];
+
+/// Numbering pattern preferences (as JSON pointers), with the value used
+/// when the stored one is not a pattern Typst accepts.
+const NUMBERING_PREFS: [(&str, &str); 2] = [("/heading_numbering", "1.1"), ("/page_numbering", "1")];
+
+/// Preferences (as JSON pointers) that take one of a few words, with the
+/// value used for anything else.
+const CHOICE_PREFS: [(&str, &[&str], &str); 2] = [
+    ("/header_footer/first_page", &["same", "footer", "none"], "same"),
+    ("/header_footer/logo_position", &["left", "right"], "left"),
+];
+
+/// Show `message` among the template warnings of the frontend.
+fn emit_template_warning(app_handle: &AppHandle, message: String) {
+    let warn = serde_json::json!({
+        "warning": message,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    app_handle.emit("template-warning", warn).ok();
+}
+
+/// Preference name as shown in warnings ("header_footer.logo").
+fn pref_name(pointer: &str) -> String { pointer.trim_start_matches('/').replace('/', ".") }
+
+/// Tell the frontend a preference was replaced by its default.
+fn warn_invalid_pref(app_handle: &AppHandle, pointer: &str, what: &str, raw: &str, default: &str) {
+    let message = format!("Preference '{}' is not {} ('{}'); using {}", pref_name(pointer), what, raw, default);
+    emit_template_warning(app_handle, message);
+}
+
+/// Replace length preferences with their canonical form, falling back to the
+/// default for anything that does not parse.
+fn normalize_length_prefs(prefs_val: &mut JsonValue, app_handle: &AppHandle) {
+    for (pointer, default) in LENGTH_PREFS {
+        let Some(value) = prefs_val.pointer_mut(pointer) else {
+            continue;
+        };
+        let Some(raw) = value.as_str().map(str::to_string) else {
+            continue;
+        };
+        let normalized = utils::typst::normalize_length(&raw).unwrap_or_else(|| {
+            warn_invalid_pref(app_handle, pointer, "a length", &raw, default);
+            default.to_string()
+        });
+        *value = JsonValue::String(normalized);
+    }
+}
+
+/// Fall back to the default for numbering patterns Typst would reject.
+fn check_numbering_prefs(prefs_val: &mut JsonValue, app_handle: &AppHandle) {
+    for (pointer, default) in NUMBERING_PREFS {
+        let Some(value) = prefs_val.pointer_mut(pointer) else {
+            continue;
+        };
+        let raw = value.as_str().unwrap_or_default().to_string();
+        if !utils::typst::is_numbering_pattern(raw.trim()) {
+            warn_invalid_pref(app_handle, pointer, "a numbering pattern", &raw, default);
+            *value = JsonValue::String(default.to_string());
+        } else {
+            *value = JsonValue::String(raw.trim().to_string());
+        }
+    }
+}
+
+/// Fall back to the default for choices the template does not know.
+fn check_choice_prefs(prefs_val: &mut JsonValue, app_handle: &AppHandle) {
+    for (pointer, choices, default) in CHOICE_PREFS {
+        let Some(value) = prefs_val.pointer_mut(pointer) else {
+            continue;
+        };
+        let raw = value.as_str().unwrap_or_default().to_string();
+        if !choices.contains(&raw.as_str()) {
+            warn_invalid_pref(app_handle, pointer, &format!("one of {}", choices.join(", ")), &raw, default);
+            *value = JsonValue::String(default.to_string());
+        }
+    }
+}
+
+/// Fall back to the long date for cover date formats the template cannot
+/// display.
+fn check_date_prefs(prefs_val: &mut JsonValue, app_handle: &AppHandle) {
+    let Some(value) = prefs_val.pointer_mut("/cover_date_format") else {
+        return;
+    };
+    let raw = value.as_str().unwrap_or_default().to_string();
+    *value = JsonValue::String(if utils::typst::is_date_format(raw.trim()) {
+        raw.trim().to_string()
+    } else {
+        warn_invalid_pref(app_handle, "/cover_date_format", "a date format", &raw, "long");
+        "long".to_string()
+    });
+}
+
+/// Check the optional lengths and font weights of the typography
+/// preferences. Empty values keep the theme's; invalid ones are cleared.
+fn check_typography_prefs(prefs_val: &mut JsonValue, app_handle: &AppHandle) {
+    let mut lengths = vec!["/typography/first_line_indent".to_string(), "/typography/heading_tracking".to_string()];
+    let mut weights = Vec::new();
+    let levels = prefs_val.pointer("/typography/heading_levels").and_then(JsonValue::as_array).map_or(0, Vec::len);
+    for i in 0 .. levels {
+        lengths.push(format!("/typography/heading_levels/{}/size", i));
+        weights.push(format!("/typography/heading_levels/{}/weight", i));
+    }
+
+    for pointer in &lengths {
+        let Some(value) = prefs_val.pointer_mut(pointer) else {
+            continue;
+        };
+        let raw = value.as_str().unwrap_or_default().trim().to_string();
+        if raw.is_empty() {
+            continue;
+        }
+        // Indents, letter spacing and font sizes cannot be percentages
+        let normalized = utils::typst::normalize_length(&raw).filter(|l| !l.ends_with('%')).unwrap_or_else(|| {
+            warn_invalid_pref(app_handle, pointer, "a length", &raw, "the theme's");
+            String::new()
+        });
+        *value = JsonValue::String(normalized);
+    }
+    for pointer in &weights {
+        let Some(value) = prefs_val.pointer_mut(pointer) else {
+            continue;
+        };
+        let raw = value.as_str().unwrap_or_default().trim().to_string();
+        if raw.is_empty() {
+            continue;
+        }
+        let normalized = utils::typst::normalize_font_weight(&raw).unwrap_or_else(|| {
+            warn_invalid_pref(app_handle, pointer, "a font weight", &raw, "the theme's");
+            String::new()
+        });
+        *value = JsonValue::String(normalized);
+    }
+}
+
+/// Fall back to English (and no region) for codes Typst would reject.
+/// Codes are case-insensitive in the preferences ("DE", "ch").
+fn check_language_prefs(prefs_val: &mut JsonValue, app_handle: &AppHandle) {
+    if let Some(value) = prefs_val.pointer_mut("/language") {
+        let raw = value.as_str().unwrap_or_default().to_string();
+        let code = raw.trim().to_ascii_lowercase();
+        *value = JsonValue::String(if utils::typst::is_language_code(&code) {
+            code
+        } else {
+            warn_invalid_pref(app_handle, "/language", "a language code", &raw, "en");
+            "en".to_string()
+        });
+    }
+    if let Some(value) = prefs_val.pointer_mut("/region") {
+        let raw = value.as_str().unwrap_or_default().to_string();
+        let code = raw.trim().to_ascii_uppercase();
+        *value = JsonValue::String(if code.is_empty() || utils::typst::is_region_code(&code) {
+            code
+        } else {
+            warn_invalid_pref(app_handle, "/region", "a region code", &raw, "no region");
+            String::new()
+        });
+    }
+}
+
+/// Font roles (as JSON pointers) the template turns into fallback chains.
+const FONT_PREFS: [&str; 8] = [
+    "/fonts/main",
+    "/fonts/mono",
+    "/fonts/main_fallback",
+    "/fonts/mono_fallback",
+    "/fonts/headings",
+    "/fonts/cjk",
+    "/fonts/emoji",
+    "/fonts/math",
+];
+
+lazy_static! {
+    /// Missing font families already reported, so the warning is not
+    /// repeated on every render.
+    static ref MISSING_FONTS_WARNED: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Trim the font lists, drop empty and repeated families, and warn once per
+/// session about families that are not installed (Typst skips those).
+fn check_font_prefs(prefs_val: &mut JsonValue, app_handle: &AppHandle) {
+    for pointer in FONT_PREFS {
+        let Some(value) = prefs_val.pointer_mut(pointer) else {
+            continue;
+        };
+        let families: Vec<String> = match value {
+            | JsonValue::String(family) => vec![family.trim().to_string()],
+            | JsonValue::Array(items) => {
+                let mut families: Vec<String> = Vec::new();
+                for family in items.iter().filter_map(JsonValue::as_str).map(str::trim) {
+                    if !family.is_empty() && !families.iter().any(|f| f.eq_ignore_ascii_case(family)) {
+                        families.push(family.to_string());
+                    }
+                }
+                *value = JsonValue::Array(families.iter().cloned().map(JsonValue::String).collect());
+                families
+            },
+            | _ => continue,
+        };
+        for family in families.iter().filter(|f| !f.is_empty() && !crate::commands::is_font_installed(f)) {
+            if MISSING_FONTS_WARNED.lock().unwrap().insert(family.to_lowercase()) {
+                let message = format!("Font '{}' ({}) is not installed and will be skipped", family, pref_name(pointer));
+                emit_template_warning(app_handle, message);
+            }
+        }
+    }
 }
 
 /// Ensures the cmarker asset exists (Windows-only workaround for incomplete
@@ -144,58 +369,87 @@ fn detect_image_format(path: &Path) -> Result<Option<&'static str>> {
     Ok(None)
 }
 
-/// Handle cover image path rewriting and copying to assets directory.
-/// Returns the updated prefs JSON value with cover_image path rewritten if
-/// necessary.
-fn handle_cover_image(prefs_val: &mut JsonValue, app_handle: &AppHandle) -> Result<()> {
-    if let Some(ci) = prefs_val.get("cover_image").and_then(|v| v.as_str()) {
-        if !ci.is_empty() && !ci.starts_with("/assets/") {
-            let mut img_path = PathBuf::from(ci);
-            if !img_path.is_absolute() {
-                let maybe = utils::get_content_dir(app_handle)?.join(&img_path);
-                if maybe.exists() {
-                    img_path = maybe;
-                }
+/// Copy image preferences into the assets directory and point them at the
+/// copies.
+fn handle_pref_images(prefs_val: &mut JsonValue, app_handle: &AppHandle) -> Result<()> {
+    for pointer in IMAGE_PREFS {
+        let Some(path) = prefs_val.pointer(pointer).and_then(|v| v.as_str()).map(str::to_string) else {
+            continue;
+        };
+        if let Some(asset) = copy_pref_image(&path, app_handle)?
+            && let Some(value) = prefs_val.pointer_mut(pointer)
+        {
+            *value = JsonValue::String(asset);
+        }
+    }
+    Ok(())
+}
+
+/// Copy an image named in the preferences to the assets directory. Returns
+/// its `/assets/...` path, or `None` if it is empty, already an asset or
+/// missing.
+fn copy_pref_image(ci: &str, app_handle: &AppHandle) -> Result<Option<String>> {
+    // Already in the assets folder (as the Browse buttons leave it)
+    if let Some(rest) = ci.strip_prefix("assets/") {
+        return Ok(Some(format!("/assets/{}", rest)));
+    }
+    if !ci.is_empty() && !ci.starts_with("/assets/") {
+        let mut img_path = PathBuf::from(ci);
+        if !img_path.is_absolute() {
+            let maybe = utils::get_content_dir(app_handle)?.join(&img_path);
+            if maybe.exists() {
+                img_path = maybe;
             }
-            if img_path.exists() {
-                let assets_dir = utils::get_assets_dir(app_handle)?;
-
-                // Detect actual image format and correct extension if needed
-                let detected_ext = detect_image_format(&img_path)?;
-
-                let _original_fname = img_path.file_name().unwrap().to_string_lossy();
-                let stem = img_path
-                    .file_stem()
-                    .map(|s| s.to_string_lossy().to_string())
-                    .unwrap_or_else(|| "image".to_string());
-
-                // Use detected extension if available, otherwise keep original
-                let correct_ext = if let Some(ext) = detected_ext {
-                    ext
-                } else {
-                    img_path.extension().and_then(|e| e.to_str()).unwrap_or("jpg")
-                };
-
-                let mut fname = utils::sanitize_filename(&format!("{}.{}", stem, correct_ext));
-                let mut dest = assets_dir.join(&fname);
-
-                // Deduplicate if necessary
-                let mut counter: u32 = 1;
-                while dest.exists() {
-                    fname = utils::sanitize_filename(&format!("{}-{}.{}", stem, counter, correct_ext));
-                    dest = assets_dir.join(&fname);
-                    counter += 1;
-                    if counter > 1000 {
-                        break;
-                    }
+        }
+        if img_path.exists() {
+            let assets_dir = utils::get_assets_dir(app_handle)?;
+
+            // Detect actual image format and correct extension if needed
+            let detected_ext = detect_image_format(&img_path)?;
+
+            let _original_fname = img_path.file_name().unwrap().to_string_lossy();
+            let stem = img_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "image".to_string());
+
+            // Use detected extension if available, otherwise keep original
+            let correct_ext = if let Some(ext) = detected_ext {
+                ext
+            } else {
+                img_path.extension().and_then(|e| e.to_str()).unwrap_or("jpg")
+            };
+
+            let mut fname = utils::sanitize_filename(&format!("{}.{}", stem, correct_ext));
+            let mut dest = assets_dir.join(&fname);
+
+            // Reuse the copy an earlier render made; rename only when another
+            // image has the name
+            let mut counter: u32 = 1;
+            while dest.exists() && !same_contents(&img_path, &dest) {
+                fname = utils::sanitize_filename(&format!("{}-{}.{}", stem, counter, correct_ext));
+                dest = assets_dir.join(&fname);
+                counter += 1;
+                if counter > 1000 {
+                    break;
                 }
+            }
 
+            if !same_contents(&img_path, &dest) {
                 fs::copy(&img_path, &dest)?;
-                prefs_val["cover_image"] = JsonValue::String(format!("/assets/{}", fname));
             }
+            return Ok(Some(format!("/assets/{}", fname)));
         }
     }
-    Ok(())
+    Ok(None)
+}
+
+/// Whether both files exist and hold the same bytes.
+fn same_contents(a: &Path, b: &Path) -> bool {
+    let (Ok(meta_a), Ok(meta_b)) = (fs::metadata(a), fs::metadata(b)) else {
+        return false;
+    };
+    meta_a.len() == meta_b.len() && matches!((fs::read(a), fs::read(b)), (Ok(x), Ok(y)) if x == y)
 }
 
 /// Setup preferences for rendering: read canonical prefs.json, handle cover
@@ -211,8 +465,15 @@ pub fn setup_prefs(config: &RenderConfig, path_type: &str) -> Result<PrefsSetupR
         JsonValue::Object(serde_json::Map::new())
     };
 
-    // Handle cover image rewriting
-    handle_cover_image(&mut prefs_val, config.app_handle)?;
+    // Copy the cover image and logo into the assets folder
+    handle_pref_images(&mut prefs_val, config.app_handle)?;
+    normalize_length_prefs(&mut prefs_val, config.app_handle);
+    check_numbering_prefs(&mut prefs_val, config.app_handle);
+    check_date_prefs(&mut prefs_val, config.app_handle);
+    check_choice_prefs(&mut prefs_val, config.app_handle);
+    check_typography_prefs(&mut prefs_val, config.app_handle);
+    check_language_prefs(&mut prefs_val, config.app_handle);
+    check_font_prefs(&mut prefs_val, config.app_handle);
 
     // Emit render-debug event
     let toc_flag = prefs_val.get("toc").and_then(|v| v.as_bool()).unwrap_or(true);
@@ -230,11 +491,20 @@ pub fn setup_prefs(config: &RenderConfig, path_type: &str) -> Result<PrefsSetupR
     let prefs_json = serde_json::to_string_pretty(&prefs_val)?;
     fs::write(config.build_dir.join("prefs.json"), &prefs_json)?;
 
+    let safe_mode = prefs_val.get("safe_mode").and_then(|v| v.as_bool()).unwrap_or(false);
     Ok(PrefsSetupResult {
         prefs_json: prefs_val,
+        safe_mode,
     })
 }
 
+/// Write the document's front matter where the template reads it
+/// (`meta.json`).
+pub fn write_metadata(config: &RenderConfig, metadata: &JsonValue) -> Result<()> {
+    fs::write(config.build_dir.join("meta.json"), serde_json::to_string_pretty(metadata)?)?;
+    Ok(())
+}
+
 /// Setup template for rendering: copy tideflow.typ and sync theme assets,
 /// emit template inspection events.
 pub fn setup_template(config: &RenderConfig, path_type: &str) -> Result<()> {
@@ -1,11 +1,14 @@
-use crate::preprocessor::{AnchorMeta, PdfPosition, SourceMapPayload, attach_pdf_positions, pdf_positions_from_query, preprocess_markdown};
+use crate::preprocessor::{
+    AnchorMeta, PdfPosition, PreprocessConfig, PreprocessWarning, SourceMapPayload, attach_pdf_positions, pdf_positions_from_query,
+    preference_variables, preprocess_markdown,
+};
 use crate::render_pipeline::{self, RenderConfig};
 use crate::utils;
 use anyhow::{Context, Result, anyhow};
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::SystemTime;
 use tauri::{AppHandle, Emitter};
@@ -15,6 +18,9 @@ use tokio::sync::Mutex;
 pub struct RenderedDocument {
     pub pdf_path: String,
     pub source_map: SourceMapPayload,
+    /// Non-fatal preprocessing problems (dangling references, ...)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<PreprocessWarning>,
 }
 
 // A global mutex to ensure only one render happens at a time
@@ -22,12 +28,34 @@ lazy_static::lazy_static! {
     static ref RENDER_MUTEX: Arc<Mutex<()>> = Arc::new(Mutex::new(()));
 }
 
+// Mail merges use a build directory of their own, so previews keep rendering
+// during a batch; this only keeps two batches apart.
+lazy_static::lazy_static! {
+    static ref MAIL_MERGE_MUTEX: Arc<Mutex<()>> = Arc::new(Mutex::new(()));
+    static ref PATTERN_COLUMN: regex::Regex = regex::Regex::new(r"\{\{\s*([\w.-]+)\s*\}\}").unwrap();
+}
+
 // Map of file paths to last modification time to avoid duplicate renders
 lazy_static::lazy_static! {
     static ref LAST_RENDER_TIMES: Arc<Mutex<std::collections::HashMap<String, SystemTime>>> =
         Arc::new(Mutex::new(std::collections::HashMap::new()));
 }
 
+/// Forward preprocessing warnings to the frontend so they can be shown next to
+/// the preview rather than only appearing as odd output in the PDF.
+fn emit_preprocess_warnings(app_handle: &AppHandle, warnings: &[PreprocessWarning]) {
+    if warnings.is_empty() {
+        return;
+    }
+    for w in warnings {
+        match w.line {
+            | Some(line) => println!("[renderer] preprocess warning (line {}): {}", line + 1, w.message),
+            | None => println!("[renderer] preprocess warning: {}", w.message),
+        }
+    }
+    let _ = app_handle.emit("preprocess-warnings", warnings);
+}
+
 fn build_source_map(app_handle: &AppHandle, typst_path: &Path, build_dir: &Path, content_dir: &Path, anchors: &[AnchorMeta]) -> SourceMapPayload {
     if anchors.is_empty() {
         return SourceMapPayload::default();
@@ -194,7 +222,7 @@ pub async fn render_markdown(app_handle: &AppHandle, file_path: &str) -> Result<
     };
 
     // Setup preferences (handles cover image rewriting and debug events)
-    render_pipeline::setup_prefs(&config, "markdown")?;
+    let prefs = render_pipeline::setup_prefs(&config, "markdown")?;
 
     // 2) Copy the markdown content to build/content.md (with preprocessing + image
     //    path rewrites)
@@ -208,19 +236,29 @@ pub async fn render_markdown(app_handle: &AppHandle, file_path: &str) -> Result<
     // tokens. Export remains untouched.
     let md_content_raw = fs::read_to_string(path)?;
     let base_dir = path.parent().unwrap_or(Path::new("."));
+    let preprocess_config = PreprocessConfig {
+        base_dir: base_dir.to_path_buf(),
+        source_file: Some(path.to_path_buf()),
+        variables: preference_variables(&prefs.prefs_json),
+        overrides: BTreeMap::new(),
+        cache_dir: build_dir.clone(),
+        safe_mode: prefs.safe_mode,
+    };
     // Resolve assets/ paths to the global content/assets directory so images work
     // from any doc folder
     let assets_root = utils::get_assets_dir(app_handle).ok();
     let assets_root_ref = assets_root.as_deref();
 
     // Clean (export) version: do NOT inject visible tokens
-    let preprocess_clean = preprocess_markdown(&md_content_raw)?;
+    let preprocess_clean = preprocess_markdown(&md_content_raw, &preprocess_config)?;
+    emit_preprocess_warnings(app_handle, &preprocess_clean.warnings);
     let md_content_clean = utils::rewrite_image_paths_in_markdown(&preprocess_clean.markdown, base_dir, assets_root_ref);
     fs::write(build_dir.join("content.md"), &md_content_clean)?;
+    render_pipeline::write_metadata(&config, &preprocess_clean.metadata)?;
 
     // Preview version: inject preview-only tokens (these will NOT be used for
     // exports)
-    let preprocess_preview = preprocess_markdown(&md_content_raw)?;
+    let preprocess_preview = preprocess_markdown(&md_content_raw, &preprocess_config)?;
     let md_content_preview = utils::rewrite_image_paths_in_markdown(&preprocess_preview.markdown, base_dir, assets_root_ref);
     fs::write(build_dir.join("content.preview.md"), &md_content_preview)?;
     // Also write debug copies into workspace for developer inspection
@@ -268,6 +306,7 @@ pub async fn render_markdown(app_handle: &AppHandle, file_path: &str) -> Result<
     let document = RenderedDocument {
         pdf_path: preview_pdf.to_string_lossy().to_string(),
         source_map,
+        warnings: preprocess_clean.warnings,
     };
 
     Ok(document)
@@ -304,17 +343,27 @@ pub async fn export_markdown(app_handle: &AppHandle, file_path: &str) -> Result<
     };
 
     // Setup preferences
-    render_pipeline::setup_prefs(&config, "markdown-export")?;
+    let prefs = render_pipeline::setup_prefs(&config, "markdown-export")?;
 
     // 2) Copy the markdown content to build/content.md (with image path rewrites)
     let md_content_raw = fs::read_to_string(path)?;
     let base_dir = path.parent().unwrap_or(Path::new("."));
     let assets_root = utils::get_assets_dir(app_handle).ok();
     let assets_root_ref = assets_root.as_deref();
+    let preprocess_config = PreprocessConfig {
+        base_dir: base_dir.to_path_buf(),
+        source_file: Some(path.to_path_buf()),
+        variables: preference_variables(&prefs.prefs_json),
+        overrides: BTreeMap::new(),
+        cache_dir: build_dir.clone(),
+        safe_mode: prefs.safe_mode,
+    };
     // For export, do NOT inject visible tokens — output must be clean for users
-    let preprocess = preprocess_markdown(&md_content_raw)?;
+    let preprocess = preprocess_markdown(&md_content_raw, &preprocess_config)?;
+    emit_preprocess_warnings(app_handle, &preprocess.warnings);
     let md_content = utils::rewrite_image_paths_in_markdown(&preprocess.markdown, base_dir, assets_root_ref);
     fs::write(build_dir.join("content.md"), md_content)?;
+    render_pipeline::write_metadata(&config, &preprocess.metadata)?;
 
     // Setup template
     render_pipeline::setup_template(&config, "markdown-export")?;
@@ -342,6 +391,192 @@ pub async fn export_markdown(app_handle: &AppHandle, file_path: &str) -> Result<
     Ok(final_pdf.to_string_lossy().to_string())
 }
 
+/// Outcome of a mail-merge export.
+#[derive(Debug, Serialize)]
+pub struct MailMergeResult {
+    pub output_dir: String,
+    pub files: Vec<String>,
+    /// Rows that could not be rendered; the rest of the batch still runs
+    pub failures: Vec<MailMergeFailure>,
+    /// Preprocessing warnings, prefixed with their row
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MailMergeFailure {
+    /// One-based data row
+    pub row: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MailMergeProgress {
+    current: usize,
+    total: usize,
+    file: Option<String>,
+}
+
+/// Render `template_path` once per row of `data_path` (CSV/TSV/JSON), with
+/// the row's columns available as `{{ column }}` variables. Output names come
+/// from `file_pattern`, e.g. `{{ last_name }}-certificate.pdf`; `{{ row }}` is
+/// the one-based row number. Existing files in `output_dir` are never
+/// overwritten.
+pub async fn export_mail_merge(app_handle: &AppHandle, template_path: &str, data_path: &str, output_dir: &str, file_pattern: &str) -> Result<MailMergeResult> {
+    let path = Path::new(template_path);
+    if !path.exists() {
+        return Err(anyhow!("File does not exist: {}", template_path));
+    }
+    let data = utils::data::load_data_file(Path::new(data_path))?;
+    if data.rows.is_empty() {
+        return Err(anyhow!("No rows found in {}", data_path));
+    }
+    let unknown = unknown_pattern_columns(file_pattern, &data.columns);
+    if !unknown.is_empty() {
+        return Err(anyhow!(
+            "The file name pattern uses columns missing from {}: {} (available: {})",
+            data_path,
+            unknown.join(", "),
+            data.columns.join(", ")
+        ));
+    }
+    let output_dir = PathBuf::from(output_dir);
+    fs::create_dir_all(&output_dir)?;
+
+    let _lock = MAIL_MERGE_MUTEX.lock().await;
+
+    let content_dir = utils::get_content_dir(app_handle)?;
+    let build_dir = content_dir.join(".build").join("mail-merge");
+    fs::create_dir_all(&build_dir)?;
+    let config = RenderConfig {
+        app_handle,
+        build_dir: build_dir.clone(),
+        content_dir: content_dir.clone(),
+        typst_root: content_dir.clone(),
+    };
+    let prefs = render_pipeline::setup_prefs(&config, "mail-merge")?;
+    render_pipeline::setup_template(&config, "mail-merge")?;
+    let typst_path = utils::get_typst_path(app_handle)
+        .context("Typst binary not found. Please install Typst system-wide or download and place in bin/typst/<platform>/ directory.")?;
+
+    let md_content_raw = fs::read_to_string(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let assets_root = utils::get_assets_dir(app_handle).ok();
+    let mut preprocess_config = PreprocessConfig {
+        base_dir: base_dir.to_path_buf(),
+        source_file: Some(path.to_path_buf()),
+        variables: preference_variables(&prefs.prefs_json),
+        overrides: BTreeMap::new(),
+        cache_dir: build_dir.clone(),
+        safe_mode: prefs.safe_mode,
+    };
+
+    let records = data.records();
+    let total = records.len();
+    let mut result = MailMergeResult {
+        output_dir: output_dir.to_string_lossy().to_string(),
+        files: Vec::new(),
+        failures: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let mut used_names: HashSet<String> = HashSet::new();
+
+    for (index, mut record) in records.into_iter().enumerate() {
+        let row = index + 1;
+        record.insert("row".to_string(), row.to_string());
+        let file_name = unique_file_name(&merge_file_name(file_pattern, &record), &mut used_names, &output_dir);
+        app_handle
+            .emit(
+                "mail-merge-progress",
+                MailMergeProgress {
+                    current: row,
+                    total,
+                    file: Some(file_name.clone()),
+                },
+            )
+            .ok();
+
+        preprocess_config.overrides = record;
+        let rendered = preprocess_markdown(&md_content_raw, &preprocess_config).and_then(|preprocess| {
+            result.warnings.extend(preprocess.warnings.iter().map(|w| format!("Row {}: {}", row, w.message)));
+            let md_content = utils::rewrite_image_paths_in_markdown(&preprocess.markdown, base_dir, assets_root.as_deref());
+            fs::write(build_dir.join("content.md"), md_content)?;
+            render_pipeline::write_metadata(&config, &preprocess.metadata)?;
+            let build_name = format!("mail_merge_{}.pdf", row);
+            render_pipeline::compile_typst(&config, &typst_path, &build_name)?;
+            let target = output_dir.join(&file_name);
+            // Copy rather than rename: the output folder may be on another drive.
+            fs::copy(build_dir.join(&build_name), &target)?;
+            let _ = fs::remove_file(build_dir.join(&build_name));
+            Ok(target)
+        });
+        match rendered {
+            | Ok(target) => result.files.push(target.to_string_lossy().to_string()),
+            | Err(e) => result.failures.push(MailMergeFailure {
+                row,
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    app_handle
+        .emit(
+            "mail-merge-progress",
+            MailMergeProgress {
+                current: total,
+                total,
+                file: None,
+            },
+        )
+        .ok();
+    Ok(result)
+}
+
+/// Columns named in a file name pattern that the data file doesn't have.
+fn unknown_pattern_columns(pattern: &str, columns: &[String]) -> Vec<String> {
+    let mut unknown: Vec<String> = Vec::new();
+    for caps in PATTERN_COLUMN.captures_iter(pattern) {
+        let name = caps[1].to_string();
+        if name != "row" && !columns.contains(&name) && !unknown.contains(&name) {
+            unknown.push(name);
+        }
+    }
+    unknown
+}
+
+/// Expand `{{ column }}` in a file name pattern and make the result safe to
+/// use as a file name. Non-ASCII names are kept as is.
+fn merge_file_name(pattern: &str, record: &BTreeMap<String, String>) -> String {
+    let expanded = PATTERN_COLUMN.replace_all(pattern, |caps: &regex::Captures| record.get(&caps[1]).cloned().unwrap_or_default());
+    let name: String = expanded
+        .chars()
+        .map(|c| if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') { '-' } else { c })
+        .collect();
+    let name = name.trim();
+    let stem = match name.get(name.len().saturating_sub(4) ..) {
+        | Some(ext) if ext.eq_ignore_ascii_case(".pdf") => &name[.. name.len() - 4],
+        | _ => name,
+    };
+    // An empty column would otherwise leave just the extension (`pdf.pdf`).
+    let stem = stem.trim().trim_matches('.');
+    if stem.is_empty() {
+        return format!("document-{}.pdf", record.get("row").map(String::as_str).unwrap_or("0"));
+    }
+    format!("{}.pdf", stem)
+}
+
+/// Append `-2`, `-3`, ... when two rows produce the same file name or the
+/// name is already taken in `dir`.
+fn unique_file_name(name: &str, used: &mut HashSet<String>, dir: &Path) -> String {
+    let stem = name.strip_suffix(".pdf").or_else(|| name.strip_suffix(".PDF")).unwrap_or(name);
+    let mut candidate = name.to_string();
+    let mut n = 2;
+    while !used.insert(candidate.to_lowercase()) || dir.join(&candidate).exists() {
+        candidate = format!("{}-{}.pdf", stem, n);
+        n += 1;
+    }
+    candidate
+}
+
 /// Renders Typst content directly to PDF (always full render)
 pub async fn render_typst(app_handle: &AppHandle, content: &str, _format: &str, current_file: Option<&str>) -> Result<RenderedDocument> {
     // Acquire render lock to prevent multiple simultaneous renders
@@ -361,11 +596,6 @@ pub async fn render_typst(app_handle: &AppHandle, content: &str, _format: &str,
     let temp_content_name = format!("temp_{}.md", uuid);
     let temp_content_path = build_dir.join(&temp_content_name);
 
-    // Preprocess content to rewrite image paths so Typst/cmarker can resolve them
-    // properly For ad-hoc typst renders, include visible tokens to aid preview
-    // extraction
-    let preprocess = preprocess_markdown(content)?;
-
     // Determine base directory for image path resolution
     // Use the current file's parent directory if available, otherwise fall back to
     // content_dir
@@ -375,6 +605,32 @@ pub async fn render_typst(app_handle: &AppHandle, content: &str, _format: &str,
         content_dir.clone()
     };
 
+    // Preprocess content to rewrite image paths so Typst/cmarker can resolve them
+    // properly For ad-hoc typst renders, include visible tokens to aid preview
+    // extraction
+    // Setup render configuration - always use content_dir as Typst root
+    let config = RenderConfig {
+        app_handle,
+        build_dir: build_dir.clone(),
+        content_dir: content_dir.clone(),
+        typst_root: content_dir.clone(),
+    };
+
+    // Setup preferences (before preprocessing, which exposes them as variables)
+    let prefs = render_pipeline::setup_prefs(&config, "typst-temp")?;
+
+    let preprocess_config = PreprocessConfig {
+        base_dir: base_dir.clone(),
+        source_file: current_file.map(PathBuf::from),
+        variables: preference_variables(&prefs.prefs_json),
+        overrides: BTreeMap::new(),
+        cache_dir: build_dir.clone(),
+        safe_mode: prefs.safe_mode,
+    };
+    let preprocess = preprocess_markdown(content, &preprocess_config)?;
+    emit_preprocess_warnings(app_handle, &preprocess.warnings);
+    render_pipeline::write_metadata(&config, &preprocess.metadata)?;
+
     // Rewrite image paths so Typst can resolve them
     let assets_root = utils::get_assets_dir(app_handle).ok();
     let assets_root_ref = assets_root.as_deref();
@@ -392,17 +648,6 @@ pub async fn render_typst(app_handle: &AppHandle, content: &str, _format: &str,
 
     fs::write(&temp_content_path, &processed)?;
 
-    // Setup render configuration - always use content_dir as Typst root
-    let config = RenderConfig {
-        app_handle,
-        build_dir: build_dir.clone(),
-        content_dir: content_dir.clone(),
-        typst_root: content_dir.clone(),
-    };
-
-    // Setup preferences
-    render_pipeline::setup_prefs(&config, "typst-temp")?;
-
     // Ensure the content is available as content.md (required by template)
     fs::copy(&temp_content_path, build_dir.join("content.md"))?;
 
@@ -428,5 +673,40 @@ pub async fn render_typst(app_handle: &AppHandle, content: &str, _format: &str,
     Ok(RenderedDocument {
         pdf_path: output_path.to_string_lossy().to_string(),
         source_map,
+        warnings: preprocess.warnings,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(pairs: &[(&str, &str)]) -> BTreeMap<String, String> { pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect() }
+
+    #[test]
+    fn test_merge_file_name() {
+        let row = record(&[("name", "Zoë / Ng"), ("row", "3")]);
+        assert_eq!(merge_file_name("{{ name }}-certificate", &row), "Zoë - Ng-certificate.pdf");
+        assert_eq!(merge_file_name("{{row}}.PDF", &row), "3.pdf");
+        assert_eq!(merge_file_name("{{ blank }}.pdf", &record(&[("blank", " "), ("row", "7")])), "document-7.pdf");
+    }
+
+    #[test]
+    fn test_unknown_pattern_columns() {
+        let columns = vec!["name".to_string(), "city".to_string()];
+        assert!(unknown_pattern_columns("{{ name }}-{{ row }}", &columns).is_empty());
+        assert_eq!(unknown_pattern_columns("{{ nmae }}-{{ nmae }}-{{ zip }}", &columns), vec!["nmae", "zip"]);
+    }
+
+    #[test]
+    fn test_unique_file_name() {
+        let dir = std::env::temp_dir().join(format!("tideflow-mail-merge-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("taken.pdf"), b"").unwrap();
+        let mut used = HashSet::new();
+        assert_eq!(unique_file_name("a.pdf", &mut used, &dir), "a.pdf");
+        assert_eq!(unique_file_name("A.pdf", &mut used, &dir), "A-2.pdf");
+        assert_eq!(unique_file_name("taken.pdf", &mut used, &dir), "taken-2.pdf");
+        fs::remove_dir_all(&dir).ok();
+    }
+}
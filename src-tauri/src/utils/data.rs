@@ -0,0 +1,103 @@
+//! Tabular data files (CSV, TSV, JSON) read by mail merge and data tables.
+
+use anyhow::{Result, anyhow};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Rows of string cells under named columns.
+#[derive(Debug, Clone, Default)]
+pub struct DataTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl DataTable {
+    /// Each row as a column → value map.
+    pub fn records(&self) -> Vec<BTreeMap<String, String>> {
+        self.rows
+            .iter()
+            .map(|row| self.columns.iter().cloned().zip(row.iter().cloned()).collect())
+            .collect()
+    }
+}
+
+/// Load a data file, choosing the format from its extension.
+///
+/// CSV/TSV files need a header row. JSON files must hold an array of objects;
+/// nested objects become dotted column names (`address.city`).
+pub fn load_data_file(path: &Path) -> Result<DataTable> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match ext.as_str() {
+        | "csv" => load_delimited(path, b','),
+        | "tsv" => load_delimited(path, b'\t'),
+        | "json" => load_json(path),
+        | _ => Err(anyhow!("Unsupported data file '{}' (expected .csv, .tsv or .json)", path.display())),
+    }
+}
+
+fn load_delimited(path: &Path, delimiter: u8) -> Result<DataTable> {
+    let mut reader = csv::ReaderBuilder::new().delimiter(delimiter).flexible(true).from_path(path)?;
+    let columns: Vec<String> = reader.headers()?.iter().map(|h| h.trim().to_string()).collect();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let mut row: Vec<String> = record.iter().map(str::to_string).collect();
+        row.resize(columns.len(), String::new());
+        rows.push(row);
+    }
+    Ok(DataTable { columns, rows })
+}
+
+fn load_json(path: &Path) -> Result<DataTable> {
+    let value: JsonValue = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let JsonValue::Array(items) = value else {
+        return Err(anyhow!("'{}' must contain an array of objects", path.display()));
+    };
+
+    let mut table = DataTable::default();
+    let mut records = Vec::with_capacity(items.len());
+    for item in &items {
+        if !item.is_object() {
+            return Err(anyhow!("'{}' must contain an array of objects", path.display()));
+        }
+        let mut flat = Vec::new();
+        flatten("", item, &mut flat);
+        for (key, _) in &flat {
+            if !table.columns.contains(key) {
+                table.columns.push(key.clone());
+            }
+        }
+        records.push(flat.into_iter().collect::<BTreeMap<_, _>>());
+    }
+    table.rows = records
+        .into_iter()
+        .map(|mut record| table.columns.iter().map(|c| record.remove(c).unwrap_or_default()).collect())
+        .collect();
+    Ok(table)
+}
+
+fn flatten(prefix: &str, value: &JsonValue, out: &mut Vec<(String, String)>) {
+    let key = |k: &str| if prefix.is_empty() { k.to_string() } else { format!("{}.{}", prefix, k) };
+    match value {
+        | JsonValue::Object(map) => {
+            for (k, v) in map {
+                flatten(&key(k), v, out);
+            }
+        },
+        | JsonValue::Array(items) => {
+            let parts: Vec<String> = items.iter().map(cell).collect();
+            out.push((prefix.to_string(), parts.join(", ")));
+        },
+        | other => out.push((prefix.to_string(), cell(other))),
+    }
+}
+
+fn cell(value: &JsonValue) -> String {
+    match value {
+        | JsonValue::String(s) => s.clone(),
+        | JsonValue::Null => String::new(),
+        | other => other.to_string(),
+    }
+}
@@ -3,13 +3,15 @@
 //! This module provides essential utilities organized by domain:
 //! - `paths`: Directory path resolution (app, content, assets, templates,
 //!   styles, typst binary)
+//! - `data`: CSV/TSV/JSON data files (mail merge, data tables)
 //! - `filesystem`: File operations (directory copying, filename sanitization)
 //! - `initialization`: Application setup (directory creation, resource copying,
 //!   default configs)
 //! - `typst`: Typst-specific utilities (image path rewriting for
-//!   Markdown/HTML/Typst)
+//!   Markdown/HTML/Typst, length validation)
 //! - `logger`: Production-safe logging utilities
 
+pub mod data;
 pub mod filesystem;
 pub mod initialization;
 pub mod logger;
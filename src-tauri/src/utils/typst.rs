@@ -1,10 +1,76 @@
-//! Typst-specific utilities for image path rewriting in Markdown.
+//! Typst-specific utilities for image path rewriting in Markdown and for
+//! checking lengths, font weights, languages and numbering patterns handed
+//! to the template.
 
 use crate::utils::filesystem::sanitize_filename;
+use lazy_static::lazy_static;
 use regex::Regex;
 use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 
+lazy_static! {
+    static ref LENGTH: Regex = Regex::new(r"^([0-9]+(?:\.[0-9]+)?|\.[0-9]+)\s*(%|pt|mm|cm|in|em|px)$").unwrap();
+    // An optional label word, then counting symbols joined by separators,
+    // optionally in brackets or followed by a closing mark ("Chapter 1",
+    // "1.a", "(i)", "1 / 1", "一、").
+    static ref NUMBERING: Regex = Regex::new(&format!(
+        r"^(?:\p{{L}}+ )?[(\[]?[{0}](?: ?[.\-–:/] ?[{0}])*[.)\]:、]?$",
+        regex::escape(COUNTING_SYMBOLS)
+    ))
+    .unwrap();
+    // Date pattern components `date.display` accepts for a date without a
+    // time (kept in sync with `date-pattern` in tideflow.typ).
+    static ref DATE_PATTERN: Regex = Regex::new(concat!(
+        r"^(?:[^\[\]]|\[(?:year(?: padding:(?:zero|space|none)| repr:(?:full|last_two))*",
+        r"|month(?: padding:(?:zero|space|none)| repr:(?:numerical|long|short))*",
+        r"|day(?: padding:(?:zero|space|none))*|weekday(?: repr:(?:long|short|sunday|monday))*)\])*$"
+    ))
+    .unwrap();
+}
+
+/// Parse a length typed by the user ("60%", "0.65em", "12 pt") into the
+/// canonical form the template's `parse-length` reads, so preference strings
+/// never have to be evaluated as Typst code. `None` if it is not a length.
+pub fn normalize_length(value: &str) -> Option<String> {
+    let caps = LENGTH.captures(value.trim())?;
+    let number: f64 = caps[1].parse().ok()?;
+    Some(format!("{}{}", number, &caps[2]))
+}
+
+/// Font weight names Typst accepts.
+const FONT_WEIGHTS: [&str; 9] = ["thin", "extralight", "light", "regular", "medium", "semibold", "bold", "extrabold", "black"];
+
+/// Parse a font weight typed by the user ("Bold", "600") into a name or
+/// number Typst accepts. `None` if it is not a weight.
+pub fn normalize_font_weight(value: &str) -> Option<String> {
+    let value = value.trim().to_ascii_lowercase().replace([' ', '-'], "");
+    match value.parse::<u16>() {
+        | Ok(n) => (100 ..= 900).contains(&n).then(|| n.to_string()),
+        | Err(_) => FONT_WEIGHTS.contains(&value.as_str()).then_some(value),
+    }
+}
+
+/// Whether `code` is a language code Typst accepts ("en", "de", "fil").
+pub fn is_language_code(code: &str) -> bool { (2 ..= 3).contains(&code.len()) && code.bytes().all(|b| b.is_ascii_lowercase()) }
+
+/// Whether `code` is a region code Typst accepts ("US", "CH").
+pub fn is_region_code(code: &str) -> bool { code.len() == 2 && code.bytes().all(|b| b.is_ascii_uppercase()) }
+
+/// Counting symbols of Typst numbering patterns (kept in sync with
+/// `counting-symbol` in tideflow.typ).
+const COUNTING_SYMBOLS: &str = "1aAiIαΑ一壹あいアイא가ㄱ*";
+
+/// Whether `pattern` is a numbering pattern the template can hand to Typst
+/// ("1.1", "i", "Chapter 1"): at most one label word before counting
+/// symbols and separators, so "Page" or "Section" alone is not one.
+pub fn is_numbering_pattern(pattern: &str) -> bool { pattern.chars().count() <= 32 && NUMBERING.is_match(pattern) }
+
+/// Whether `format` is a date format the template knows ("long", "iso",
+/// ...) or a Typst date pattern it can display ("[day].[month].[year]").
+pub fn is_date_format(format: &str) -> bool {
+    matches!(format, "long" | "day-month-year" | "numeric" | "iso") || (format.contains('[') && DATE_PATTERN.is_match(format))
+}
+
 /// Rewrite image sources in Markdown and HTML to absolute, normalized paths.
 /// This helps Typst resolve images when we compile from a different working
 /// directory.
@@ -169,9 +235,10 @@ pub fn rewrite_image_paths_in_markdown(input: &str, base_dir: &Path, assets_root
     // Replace Markdown image syntax: ![alt](path "title")
     // We'll conservatively capture inside the parentheses and split off a title if
     // present.
-    let re_md_img = Regex::new(r"!\[[^\]]*\]\(([^)]+)\)").unwrap();
+    let re_md_img = Regex::new(r"!\[([^\]]*)\]\(([^)]+)\)").unwrap();
     let result = re_md_img.replace_all(input, |caps: &regex::Captures| {
-        let inside = caps.get(1).map(|m| m.as_str()).unwrap_or("").trim();
+        let alt = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+        let inside = caps.get(2).map(|m| m.as_str()).unwrap_or("").trim();
 
         // Extract path and optional title: path [whitespace title]
         let mut path_part = inside;
@@ -199,10 +266,12 @@ pub fn rewrite_image_paths_in_markdown(input: &str, base_dir: &Path, assets_root
 
         let abs = absolute_norm(base_dir, path_part, assets_root, true);
 
+        // Keep the alt text: included files are rewritten before the figures
+        // pass, which uses it as the caption.
         if let Some(title) = title_part {
-            format!("![]({} {})", abs, title)
+            format!("![{}]({} {})", alt, abs, title)
         } else {
-            format!("![]({})", abs)
+            format!("![{}]({})", alt, abs)
         }
     });
 
@@ -220,17 +289,108 @@ pub fn rewrite_image_paths_in_markdown(input: &str, base_dir: &Path, assets_root
         format!("<img{} src={}{}{}{}>", before, quote, abs, after_quote, after)
     });
 
-    // Replace raw Typst calls: #fig("path" ...) and #image('path' ...)
-    let re_raw_typst = Regex::new(r#"#(fig|image)\(\s*([\"'])([^\"']+)([\"'])"#).unwrap();
+    // Replace raw Typst calls: #fig("path" ...), #image('path' ...) and the
+    // preprocessor's tf-image("path" ...)
+    let re_raw_typst = Regex::new(r#"(#fig|#image|\btf-image)\(\s*([\"'])([^\"']+)([\"'])"#).unwrap();
     let result = re_raw_typst.replace_all(&result, |caps: &regex::Captures| {
-        let func = caps.get(1).map(|m| m.as_str()).unwrap_or("fig");
+        let func = caps.get(1).map(|m| m.as_str()).unwrap_or("#fig");
         let quote = caps.get(2).map(|m| m.as_str()).unwrap_or("\"");
         let path = caps.get(3).map(|m| m.as_str()).unwrap_or("");
 
         let abs = absolute_norm(base_dir, path, assets_root, false);
 
-        format!("#{}({}{}{}", func, quote, abs, quote)
+        format!("{}({}{}{}", func, quote, abs, quote)
     });
 
     result.into_owned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_length() {
+        assert_eq!(normalize_length("60%").as_deref(), Some("60%"));
+        assert_eq!(normalize_length(" 12 pt ").as_deref(), Some("12pt"));
+        assert_eq!(normalize_length(".5em").as_deref(), Some("0.5em"));
+        assert_eq!(normalize_length("2.50cm").as_deref(), Some("2.5cm"));
+        for bad in ["", "12", "pt", "-1cm", "1e3pt", "1cm + 2pt", "12 PT"] {
+            assert_eq!(normalize_length(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_normalize_font_weight() {
+        assert_eq!(normalize_font_weight("Bold").as_deref(), Some("bold"));
+        assert_eq!(normalize_font_weight("Extra-Light").as_deref(), Some("extralight"));
+        assert_eq!(normalize_font_weight("semi bold").as_deref(), Some("semibold"));
+        assert_eq!(normalize_font_weight(" 600 ").as_deref(), Some("600"));
+        for bad in ["", "heavy", "50", "950", "6OO"] {
+            assert_eq!(normalize_font_weight(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_is_date_format() {
+        for good in [
+            "long",
+            "iso",
+            "[day].[month].[year]",
+            "[weekday repr:long], [month repr:long] [day padding:none]",
+        ] {
+            assert!(is_date_format(good), "{}", good);
+        }
+        assert!(is_date_format("[year repr:last_two]-[month padding:none]"));
+        for bad in [
+            "",
+            "Long",
+            "dd/mm/yyyy",
+            "[hour]:[minute]",
+            "[day repr:long]",
+            "[month repr:full]",
+            "[year",
+            "[[day]]",
+        ] {
+            assert!(!is_date_format(bad), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_is_numbering_pattern() {
+        for good in [
+            "1",
+            "1.1",
+            "1.a",
+            "I.1",
+            "i",
+            "a)",
+            "(1)",
+            "[a]",
+            "1.1.",
+            "1-1",
+            "1 / 1",
+            "Chapter 1",
+            "Part I",
+            "一、",
+            "*",
+        ] {
+            assert!(is_numbering_pattern(good), "{}", good);
+        }
+        for bad in [
+            "",
+            "Page",
+            "Section",
+            "Chapter",
+            "Page 1 of",
+            "The chapter 1",
+            "1..1",
+            "1\n1",
+            "#1",
+            "1)))",
+            "x",
+        ] {
+            assert!(!is_numbering_pattern(bad), "{}", bad);
+        }
+    }
+}
@@ -3,10 +3,16 @@ import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { open } from '@tauri-apps/plugin-dialog';
 import type {
   BackendRenderedDocument,
+  CoverAuthor,
   FileEntry,
+  Fonts,
+  HeaderFooter,
+  MailMergeProgress,
+  MailMergeResult,
   Preferences,
   RenderedDocument,
   SourceMap,
+  Typography,
 } from './types';
 
 // File operations
@@ -14,7 +20,7 @@ export async function readMarkdownFile(path: string): Promise<string> {
   return invoke('read_markdown_file', { path });
 }
 
-import { scrubRawTypstAnchors } from './utils/scrubAnchors';
+import { scrubRawTypstAnchors, stripRawTypst } from './utils/scrubAnchors';
 
 export async function writeMarkdownFile(path: string, content: string): Promise<void> {
   // Ensure any injected preview-only raw-typst anchors are removed before
@@ -34,7 +40,7 @@ export async function exportCleanMarkdown(content: string, suggestedName?: strin
   
   if (!filePath) return null;
   
-  const cleaned = scrubRawTypstAnchors(content);
+  const cleaned = stripRawTypst(content);
   await invoke('write_markdown_file', { path: filePath, content: cleaned });
   return filePath;
 }
@@ -118,8 +124,10 @@ function normalizeSourceMap(map: SourceMap | undefined): SourceMap {
   if (!Array.isArray(map.anchors)) {
     return { anchors: [] };
   }
+  // Anchors from included files point into other documents; scroll sync
+  // only tracks the file open in the editor.
   return {
-    anchors: map.anchors.map((anchor) => ({
+    anchors: map.anchors.filter((anchor) => !anchor.file).map((anchor) => ({
       id: anchor.id,
       editor: anchor.editor,
       pdf: anchor.pdf,
@@ -131,6 +139,7 @@ function normalizeRenderedDocument(doc: BackendRenderedDocument): RenderedDocume
   return {
     pdfPath: doc.pdf_path,
     sourceMap: normalizeSourceMap(doc.source_map),
+    warnings: Array.isArray(doc.warnings) ? doc.warnings : [],
   };
 }
 
@@ -206,6 +215,23 @@ export async function exportMarkdown(filePath: string): Promise<string> {
   return invoke('export_markdown', { filePath });
 }
 
+export async function exportMailMerge(
+  templatePath: string,
+  dataPath: string,
+  outputDir: string,
+  filePattern: string
+): Promise<MailMergeResult> {
+  return invoke('export_mail_merge', { templatePath, dataPath, outputDir, filePattern });
+}
+
+export async function listenForMailMergeProgress(
+  callback: (progress: MailMergeProgress) => void
+): Promise<UnlistenFn> {
+  return listen('mail-merge-progress', (event) => {
+    callback(event.payload as MailMergeProgress);
+  });
+}
+
 // Preferences operations
 interface BackendPreferences {
   theme_id?: string;
@@ -214,17 +240,39 @@ interface BackendPreferences {
   toc: boolean;
   toc_title?: string;
   toc_two_column?: boolean;
+  toc_depth?: number;
+  toc_placement?: Preferences['toc_placement'];
+  toc_leaders?: boolean;
+  toc_unnumbered?: boolean;
+  toc_mini?: boolean;
+  list_of_figures?: boolean;
+  list_of_tables?: boolean;
   two_column_layout?: boolean;
   cover_page?: boolean;
   cover_title?: string;
   cover_writer?: string;
   cover_image?: string;
   cover_image_width?: string;
+  cover_layout?: Preferences['cover_layout'];
+  cover_subtitle?: string;
+  cover_authors?: CoverAuthor[];
+  cover_organization?: string;
+  cover_version?: string;
+  cover_date?: string;
+  cover_date_format?: string;
+  cover_logo?: string;
+  cover_background?: string;
   numberSections?: boolean; // backend serialized camelCase
   number_sections?: boolean; // tolerate snake just in case
+  heading_numbering?: string;
+  heading_numbering_depth?: number;
+  page_numbering?: string;
+  page_number_format?: string;
+  roman_front_matter?: boolean;
+  first_page_number?: number;
   default_image_width: string;
   default_image_alignment: string;
-  fonts: { main: string; mono: string };
+  fonts: Fonts;
   font_size: number;
   page_bg_color: string;
   font_color: string;
@@ -232,13 +280,18 @@ interface BackendPreferences {
   accent_color: string;
   line_height: number;
   paragraph_spacing: string;
+  language?: string;
+  region?: string;
+  typography?: Typography;
   page_numbers: boolean;
   header_title: boolean;
   header_text: string;
+  header_footer?: HeaderFooter;
   render_debounce_ms: number;
   focused_preview_enabled?: boolean;
   preserve_scroll_position: boolean;
   confirm_exit_on_unsaved?: boolean;
+  safe_mode?: boolean;
   // Optional typst_path forwarded from backend
   typst_path?: string;
 }
@@ -251,12 +304,34 @@ export async function getPreferences(): Promise<Preferences> {
     margin: raw.margin,
     toc: raw.toc,
     toc_title: raw.toc_title ?? '',
+    toc_depth: raw.toc_depth ?? 3,
+    toc_placement: raw.toc_placement ?? 'after_cover',
+    toc_leaders: raw.toc_leaders ?? true,
+    toc_unnumbered: raw.toc_unnumbered ?? true,
+    toc_mini: raw.toc_mini ?? false,
+    list_of_figures: raw.list_of_figures ?? false,
+    list_of_tables: raw.list_of_tables ?? false,
     cover_page: raw.cover_page ?? false,
     cover_title: raw.cover_title ?? '',
     cover_writer: raw.cover_writer ?? '',
     cover_image: raw.cover_image ?? '',
     cover_image_width: raw.cover_image_width ?? '60%',
+    cover_layout: raw.cover_layout ?? 'centered',
+    cover_subtitle: raw.cover_subtitle ?? '',
+    cover_authors: raw.cover_authors ?? [],
+    cover_organization: raw.cover_organization ?? '',
+    cover_version: raw.cover_version ?? '',
+    cover_date: raw.cover_date ?? '',
+    cover_date_format: raw.cover_date_format ?? 'long',
+    cover_logo: raw.cover_logo ?? '',
+    cover_background: raw.cover_background ?? '',
     number_sections: raw.numberSections ?? raw.number_sections ?? true,
+    heading_numbering: raw.heading_numbering ?? '1.1',
+    heading_numbering_depth: raw.heading_numbering_depth ?? 6,
+    page_numbering: raw.page_numbering ?? '1',
+    page_number_format: raw.page_number_format ?? '{page}',
+    roman_front_matter: raw.roman_front_matter ?? false,
+    first_page_number: raw.first_page_number ?? 1,
     default_image_width: raw.default_image_width,
     default_image_alignment: raw.default_image_alignment,
     fonts: raw.fonts,
@@ -267,13 +342,18 @@ export async function getPreferences(): Promise<Preferences> {
     accent_color: raw.accent_color ?? '#1e40af',
     line_height: raw.line_height ?? 1.5,
     paragraph_spacing: raw.paragraph_spacing ?? '0.65em',
+    language: raw.language ?? 'en',
+    region: raw.region ?? '',
+    typography: raw.typography,
     page_numbers: raw.page_numbers ?? false,
     header_title: raw.header_title ?? false,
     header_text: raw.header_text ?? '',
+    header_footer: raw.header_footer,
     render_debounce_ms: raw.render_debounce_ms ?? 400,
     focused_preview_enabled: raw.focused_preview_enabled,
     preserve_scroll_position: raw.preserve_scroll_position,
     confirm_exit_on_unsaved: raw.confirm_exit_on_unsaved ?? true,
+    safe_mode: raw.safe_mode ?? false,
     // Optional typst_path is forwarded from the backend
     typst_path: raw.typst_path ?? undefined,
   };
@@ -287,13 +367,35 @@ export async function setPreferences(preferences: Preferences): Promise<void> {
     toc: preferences.toc,
     toc_title: preferences.toc_title,
     toc_two_column: preferences.toc_two_column,
+    toc_depth: preferences.toc_depth,
+    toc_placement: preferences.toc_placement,
+    toc_leaders: preferences.toc_leaders,
+    toc_unnumbered: preferences.toc_unnumbered,
+    toc_mini: preferences.toc_mini,
+    list_of_figures: preferences.list_of_figures,
+    list_of_tables: preferences.list_of_tables,
     two_column_layout: preferences.two_column_layout,
     cover_page: preferences.cover_page,
     cover_title: preferences.cover_title,
     cover_writer: preferences.cover_writer,
     cover_image: preferences.cover_image,
     cover_image_width: preferences.cover_image_width,
+    cover_layout: preferences.cover_layout,
+    cover_subtitle: preferences.cover_subtitle,
+    cover_authors: preferences.cover_authors,
+    cover_organization: preferences.cover_organization,
+    cover_version: preferences.cover_version,
+    cover_date: preferences.cover_date,
+    cover_date_format: preferences.cover_date_format,
+    cover_logo: preferences.cover_logo,
+    cover_background: preferences.cover_background,
     numberSections: preferences.number_sections, // Send as numberSections for Rust
+    heading_numbering: preferences.heading_numbering,
+    heading_numbering_depth: preferences.heading_numbering_depth,
+    page_numbering: preferences.page_numbering,
+    page_number_format: preferences.page_number_format,
+    roman_front_matter: preferences.roman_front_matter,
+    first_page_number: preferences.first_page_number,
     default_image_width: preferences.default_image_width,
     default_image_alignment: preferences.default_image_alignment,
     fonts: preferences.fonts,
@@ -304,13 +406,18 @@ export async function setPreferences(preferences: Preferences): Promise<void> {
     accent_color: preferences.accent_color,
     line_height: preferences.line_height,
     paragraph_spacing: preferences.paragraph_spacing,
+    language: preferences.language,
+    region: preferences.region,
+    typography: preferences.typography,
     page_numbers: preferences.page_numbers,
     header_title: preferences.header_title,
     header_text: preferences.header_text,
+    header_footer: preferences.header_footer,
     render_debounce_ms: preferences.render_debounce_ms,
     focused_preview_enabled: preferences.focused_preview_enabled,
     preserve_scroll_position: preferences.preserve_scroll_position,
     confirm_exit_on_unsaved: preferences.confirm_exit_on_unsaved,
+    safe_mode: preferences.safe_mode,
     // Forward typst_path to backend if present
     typst_path: preferences.typst_path,
   };
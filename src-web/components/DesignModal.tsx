@@ -14,7 +14,9 @@ import {
   DocumentTab, 
   TypographyTab, 
   SpacingTab, 
+  HeaderFooterTab,
   StructureTab, 
+  CoverTab,
   ImagesTab, 
   PresetsTab, 
   AdvancedTab,
@@ -250,7 +252,9 @@ const DesignModal: React.FC = () => {
     { id: 'document', label: 'Document', icon: '📄' },
     { id: 'typography', label: 'Typography', icon: '🔤' },
     { id: 'spacing', label: 'Spacing & Layout', icon: '📐' },
+    { id: 'headerfooter', label: 'Header & Footer', icon: '📰' },
     { id: 'structure', label: 'Structure', icon: '🗂️' },
+    { id: 'cover', label: 'Cover', icon: '📕' },
     { id: 'images', label: 'Images', icon: '🖼️' },
     { id: 'presets', label: 'Presets', icon: '💾' },
     { id: 'advanced', label: 'Advanced', icon: '⚙️' },
@@ -343,9 +347,19 @@ const DesignModal: React.FC = () => {
               <SpacingTab local={local} mutate={mutate} />
             )}
 
+            {/* Header & Footer Tab */}
+            {activeTab === 'headerfooter' && (
+              <HeaderFooterTab local={local} mutate={mutate} />
+            )}
+
             {/* Structure Tab */}
             {activeTab === 'structure' && (
-              <StructureTab local={local} mutate={mutate} handleBrowseCoverImage={handleBrowseCoverImage} currentFile={currentFile} />
+              <StructureTab local={local} mutate={mutate} />
+            )}
+
+            {/* Cover Tab */}
+            {activeTab === 'cover' && (
+              <CoverTab local={local} mutate={mutate} handleBrowseCoverImage={handleBrowseCoverImage} currentFile={currentFile} />
             )}
 
             {/* Images Tab */}
@@ -116,6 +116,11 @@ const AdvancedTab: React.FC<TabProps> = ({ local, mutate }) => {
           <input type="checkbox" checked={local.confirm_exit_on_unsaved} onChange={e => mutate({ confirm_exit_on_unsaved: (e.target as HTMLInputElement).checked })} />
           <span>Confirm Exit on Unsaved Changes</span>
         </label>
+        <label className="checkbox-label">
+          <input type="checkbox" checked={local.safe_mode || false} onChange={e => mutate({ safe_mode: (e.target as HTMLInputElement).checked })} />
+          <span>Safe Mode</span>
+        </label>
+        <div className="helper-text">For documents from outside your team: raw Typst is removed and only files in the document's folder can be included or shown. A single document can opt in with <code>safe-mode: true</code> in its front matter.</div>
 
         <div className="design-section">
           <h3>Typst binary</h3>
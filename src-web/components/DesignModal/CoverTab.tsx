@@ -0,0 +1,240 @@
+import React from 'react';
+import type { TabProps } from './types';
+import type { CoverAuthor, Preferences } from '../../types';
+import * as api from '../../api';
+import { logger as designLogger } from '../../utils/logger';
+
+interface CoverTabProps extends TabProps {
+  handleBrowseCoverImage: () => void;
+  currentFile?: string | null;
+}
+
+const CoverTab: React.FC<CoverTabProps> = ({ local, mutate, handleBrowseCoverImage, currentFile }) => {
+  const authors = local.cover_authors ?? [];
+
+  // Get document name from current file for default cover title
+  const getDefaultCoverTitle = () => {
+    if (!currentFile) return '';
+    const fileName = currentFile.split(/[\\/]/).pop() || currentFile;
+    return fileName.replace(/\.md$/i, '').replace(/\.markdown$/i, '');
+  };
+
+  const updateAuthor = (index: number, patch: Partial<CoverAuthor>) =>
+    mutate({ cover_authors: authors.map((a, i) => (i === index ? { ...a, ...patch } : a)) });
+
+  const browseImage = async (key: 'cover_logo' | 'cover_background') => {
+    const picked = await api.showOpenDialog([{ name: 'Images', extensions: ['png', 'jpg', 'jpeg', 'gif', 'svg'] }], false);
+    if (picked) {
+      const path = await api.importImageFromPath(picked);
+      mutate(key === 'cover_logo' ? { cover_logo: path } : { cover_background: path });
+    }
+  };
+
+  return (
+    <div className="tab-panel">
+      <h3>Cover Page</h3>
+      <div className="form-grid one-col">
+        <label className="checkbox-label">
+          <input 
+            type="checkbox" 
+            checked={local.cover_page} 
+            onChange={e => {
+              const isEnabled = e.target.checked;
+              // When enabling cover page, auto-populate title if empty
+              if (isEnabled && !local.cover_title) {
+                const defaultTitle = getDefaultCoverTitle();
+                if (defaultTitle) {
+                  mutate({ cover_page: isEnabled, cover_title: defaultTitle });
+                  return;
+                }
+              }
+              mutate({ cover_page: isEnabled });
+            }} 
+          /> 
+          <span>Show a Cover Page</span>
+        </label>
+        {local.cover_page && (
+          <>
+            <label>Layout
+              <select value={local.cover_layout ?? 'centered'} onChange={e => mutate({ cover_layout: e.target.value as Preferences['cover_layout'] })}>
+                <option value="centered">Centered</option>
+                <option value="left">Left-aligned</option>
+                <option value="banner">Banner</option>
+              </select>
+            </label>
+            <label>Cover Title
+              <input
+                placeholder="Document title"
+                value={local.cover_title}
+                onChange={e => mutate({ cover_title: e.target.value })}
+              />
+            </label>
+            <label>Subtitle
+              <input
+                value={local.cover_subtitle ?? ''}
+                onChange={e => mutate({ cover_subtitle: e.target.value })}
+              />
+            </label>
+            <label>Authors</label>
+            {authors.map((author, index) => (
+              <div className="input-with-button" key={index}>
+                <input
+                  placeholder="Name"
+                  value={author.name}
+                  onChange={e => updateAuthor(index, { name: e.target.value })}
+                />
+                <input
+                  placeholder="Affiliation (optional)"
+                  value={author.affiliation}
+                  onChange={e => updateAuthor(index, { affiliation: e.target.value })}
+                />
+                <button type="button" onClick={() => mutate({ cover_authors: authors.filter((_, i) => i !== index) })} title="Remove author">✕</button>
+              </div>
+            ))}
+            <div>
+              <button type="button" onClick={() => mutate({ cover_authors: [...authors, { name: '', affiliation: '' }] })}>Add Author</button>
+            </div>
+            {authors.length === 0 && (
+              <label>Cover Writer
+                <input
+                  placeholder="Author or organization"
+                  value={local.cover_writer}
+                  onChange={e => mutate({ cover_writer: e.target.value })}
+                />
+              </label>
+            )}
+            <div className="form-grid">
+              <label>Organization
+                <input
+                  value={local.cover_organization ?? ''}
+                  onChange={e => mutate({ cover_organization: e.target.value })}
+                />
+              </label>
+              <label>Version
+                <input
+                  placeholder="e.g. 1.2"
+                  value={local.cover_version ?? ''}
+                  onChange={e => mutate({ cover_version: e.target.value })}
+                />
+              </label>
+            </div>
+            <div className="form-grid">
+              <label>Date
+                <input
+                  placeholder="today or 2025-03-14"
+                  value={local.cover_date ?? ''}
+                  onChange={e => mutate({ cover_date: e.target.value })}
+                />
+              </label>
+              <label>Date Format
+                <select value={local.cover_date_format ?? 'long'} onChange={e => mutate({ cover_date_format: e.target.value })}>
+                  <option value="long">March 14, 2025</option>
+                  <option value="day-month-year">14 March 2025</option>
+                  <option value="numeric">14/03/2025</option>
+                  <option value="iso">2025-03-14</option>
+                </select>
+              </label>
+            </div>
+            <label>Cover Image
+              <div className="input-with-button">
+                <input
+                  placeholder="Relative path (e.g., assets/image.png)"
+                  value={local.cover_image}
+                  onChange={e => {
+                    const newValue = e.target.value;
+                    // If clearing the field, delete the old image
+                    if (!newValue && local.cover_image) {
+                      import('../../api').then(({ deleteFile }) => {
+                        deleteFile(local.cover_image).catch(err => 
+                          designLogger.warn('Failed to delete cover image', err)
+                        );
+                      });
+                    }
+                    mutate({ cover_image: newValue });
+                  }}
+                />
+                <button
+                  type="button"
+                  onClick={handleBrowseCoverImage}
+                >
+                  Browse…
+                </button>
+                {local.cover_image && (
+                  <button
+                    type="button"
+                    onClick={() => {
+                      import('../../api').then(({ deleteFile }) => {
+                        deleteFile(local.cover_image).catch(err => 
+                          designLogger.warn('Failed to delete cover image', err)
+                        );
+                      });
+                      mutate({ cover_image: '' });
+                    }}
+                    title="Clear cover image"
+                  >
+                    ✕
+                  </button>
+                )}
+              </div>
+              <div className="helper-text">Browse to import image to assets, or provide relative path</div>
+            </label>
+            <label>Cover Image Width
+              <div className="slider-group">
+                <input 
+                  type="range" 
+                  min="10"
+                  max="100"
+                  step="5"
+                  value={parseInt(local.cover_image_width.replace('%', '')) || 60}
+                  onChange={e => mutate({ cover_image_width: `${e.target.value}%` })}
+                />
+                <input
+                  type="number"
+                  className="slider-value-input"
+                  min="10"
+                  max="100"
+                  step="5"
+                  value={parseInt(local.cover_image_width.replace('%', '')) || 60}
+                  onChange={e => mutate({ cover_image_width: `${e.target.value}%` })}
+                />
+              </div>
+              <div className="helper-text">Size of the cover image as percentage of page width</div>
+            </label>
+            <label>Logo
+              <div className="input-with-button">
+                <input
+                  placeholder="Relative path (e.g., assets/logo.png)"
+                  value={local.cover_logo ?? ''}
+                  onChange={e => mutate({ cover_logo: e.target.value })}
+                />
+                <button type="button" onClick={() => browseImage('cover_logo')}>Browse…</button>
+                {local.cover_logo && (
+                  <button type="button" onClick={() => mutate({ cover_logo: '' })} title="Remove logo">✕</button>
+                )}
+              </div>
+            </label>
+            <label>Background Image
+              <div className="input-with-button">
+                <input
+                  placeholder="Relative path (e.g., assets/background.jpg)"
+                  value={local.cover_background ?? ''}
+                  onChange={e => mutate({ cover_background: e.target.value })}
+                />
+                <button type="button" onClick={() => browseImage('cover_background')}>Browse…</button>
+                {local.cover_background && (
+                  <button type="button" onClick={() => mutate({ cover_background: '' })} title="Remove background">✕</button>
+                )}
+              </div>
+              <div className="helper-text">Fills the whole cover page, edge to edge</div>
+            </label>
+            <div className="helper-text">
+              The front matter fills in the cover too and wins over these fields: <code>title</code>, <code>subtitle</code>, <code>author</code> (a name, a list, or entries with <code>name</code> and <code>affiliation</code>), <code>organization</code>, <code>version</code>, <code>date</code> and <code>date-format</code>.
+            </div>
+          </>
+        )}
+      </div>
+    </div>
+  );
+};
+
+export default CoverTab;
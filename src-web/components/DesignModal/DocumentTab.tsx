@@ -21,6 +21,31 @@ const DocumentTab: React.FC<TabProps> = ({ local, mutate }) => {
             <option value="us-legal">US Legal (8.5 × 14 in)</option>
           </select>
         </label>
+        <div className="form-grid">
+          <label>Language
+            <input
+              list="document-languages"
+              placeholder="en"
+              value={local.language ?? 'en'}
+              onChange={e => mutate({ language: e.target.value })}
+            />
+            <datalist id="document-languages">
+              <option value="en">English</option>
+              <option value="de">Deutsch</option>
+              <option value="fr">Français</option>
+              <option value="ja">日本語</option>
+              <option value="ko">한국어</option>
+            </datalist>
+          </label>
+          <label>Region
+            <input
+              placeholder="Optional (e.g. GB, CH)"
+              value={local.region ?? ''}
+              onChange={e => mutate({ region: e.target.value })}
+            />
+          </label>
+        </div>
+        <div className="helper-text">Sets hyphenation, quotation marks and generated text such as “Note” and “Contents”. Front matter <code>lang: de-DE</code> overrides it.</div>
         <label>Horizontal Margin
           <div className="slider-group">
             <input 
@@ -0,0 +1,178 @@
+import React from 'react';
+import type { TabProps } from './types';
+import type { HeaderFooter, PageSlots } from '../../types';
+import { defaultHeaderFooter } from '../../stores/preferencesStore';
+import * as api from '../../api';
+
+const SLOT_NAMES: (keyof PageSlots)[] = ['left', 'center', 'right'];
+
+const HeaderFooterTab: React.FC<TabProps> = ({ local, mutate }) => {
+  const hf = local.header_footer ?? defaultHeaderFooter;
+  const update = (patch: Partial<HeaderFooter>) => mutate({ header_footer: { ...hf, ...patch } });
+  const updateSlot = (region: 'header' | 'footer', slot: keyof PageSlots, value: string) => {
+    const slots = { ...hf[region], [slot]: value };
+    update(region === 'header' ? { header: slots } : { footer: slots });
+  };
+
+  const browseLogo = async () => {
+    const picked = await api.showOpenDialog([{ name: 'Images', extensions: ['png', 'jpg', 'jpeg', 'gif', 'svg'] }], false);
+    if (picked) {
+      update({ logo: await api.importImageFromPath(picked) });
+    }
+  };
+
+  const slotRow = (region: 'header' | 'footer') => (
+    <div className="form-grid">
+      {SLOT_NAMES.map(slot => (
+        <label key={slot}>{slot.charAt(0).toUpperCase() + slot.slice(1)}
+          <input
+            placeholder={region === 'footer' && slot === 'center' ? 'e.g. Page {page} of {total}' : ''}
+            value={hf[region][slot]}
+            onChange={e => updateSlot(region, slot, e.target.value)}
+          />
+        </label>
+      ))}
+    </div>
+  );
+
+  return (
+    <div className="tab-panel">
+      <h3>Quick Setup</h3>
+      <div className="form-grid one-col">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={local.page_numbers}
+            onChange={e => mutate({ page_numbers: e.target.checked })}
+          />
+          <span>Page Numbers</span>
+        </label>
+        {local.page_numbers && (
+          <label>Page Number Text
+            <select value={local.page_number_format ?? '{page}'} onChange={e => mutate({ page_number_format: e.target.value })}>
+              <option value="{page}">1</option>
+              <option value="Page {page}">Page 1</option>
+              <option value="{page} / {total}">1 / 10</option>
+              <option value="Page {page} of {total}">Page 1 of 10</option>
+            </select>
+          </label>
+        )}
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={local.header_title}
+            onChange={e => mutate({ header_title: e.target.checked })}
+          />
+          <span>Document Title in Header</span>
+        </label>
+        {local.header_title && (
+          <label>Header Text
+            <input
+              placeholder="Enter header text"
+              value={local.header_text}
+              onChange={e => mutate({ header_text: e.target.value })}
+            />
+            <div className="helper-text">Text to display in page header</div>
+          </label>
+        )}
+      </div>
+
+      <h3>Header</h3>
+      {slotRow('header')}
+      <h3>Footer</h3>
+      {slotRow('footer')}
+      <div className="helper-text">
+        Filling in a header or footer replaces the quick setup above. Placeholders: <code>{'{page}'}</code>, <code>{'{total}'}</code>, <code>{'{title}'}</code>, <code>{'{chapter}'}</code>, <code>{'{date}'}</code>, <code>{'{author}'}</code>. Title, author and date come from the front matter, or from the cover page.
+      </div>
+
+      <h3>Page Numbering</h3>
+      <div className="form-grid">
+        <label>Style
+          <select value={local.page_numbering ?? '1'} onChange={e => mutate({ page_numbering: e.target.value })}>
+            <option value="1">1, 2, 3</option>
+            <option value="i">i, ii, iii</option>
+            <option value="I">I, II, III</option>
+            <option value="a">a, b, c</option>
+            <option value="A">A, B, C</option>
+          </select>
+        </label>
+        <label>Start At
+          <input
+            type="number"
+            min="1"
+            value={local.first_page_number ?? 1}
+            onChange={e => mutate({ first_page_number: Math.max(1, parseInt(e.target.value || '1', 10)) })}
+          />
+        </label>
+      </div>
+      <div className="form-grid one-col">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={local.roman_front_matter ?? false}
+            onChange={e => mutate({ roman_front_matter: e.target.checked })}
+          />
+          <span>Roman Numerals for Front Matter</span>
+        </label>
+        <div className="helper-text">Number the table of contents and lists i, ii, iii; the main content then starts again at the number above</div>
+      </div>
+
+      <h3>Layout</h3>
+      <div className="form-grid one-col">
+        <label>First Page
+          <select value={hf.first_page} onChange={e => update({ first_page: e.target.value as HeaderFooter['first_page'] })}>
+            <option value="same">Same as the other pages</option>
+            <option value="footer">Footer only</option>
+            <option value="none">No header or footer</option>
+          </select>
+          <div className="helper-text">The first page of the main content, after the cover and table of contents</div>
+        </label>
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={hf.mirror_even_pages}
+            onChange={e => update({ mirror_even_pages: e.target.checked })}
+          />
+          <span>Mirror on Even Pages</span>
+        </label>
+        <div className="helper-text">Swap left and right on even pages, for documents printed on both sides</div>
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={hf.rules}
+            onChange={e => update({ rules: e.target.checked })}
+          />
+          <span>Separator Lines</span>
+        </label>
+        <label>Logo
+          <div className="input-with-button">
+            <input
+              placeholder="Relative path (e.g., assets/logo.png)"
+              value={hf.logo}
+              onChange={e => update({ logo: e.target.value })}
+            />
+            <button type="button" onClick={browseLogo}>Browse…</button>
+            {hf.logo && (
+              <button type="button" onClick={() => update({ logo: '' })} title="Remove logo">✕</button>
+            )}
+          </div>
+        </label>
+        {hf.logo && (
+          <div className="form-grid">
+            <label>Logo Side
+              <select value={hf.logo_position} onChange={e => update({ logo_position: e.target.value as HeaderFooter['logo_position'] })}>
+                <option value="left">Left</option>
+                <option value="right">Right</option>
+              </select>
+            </label>
+            <label>Logo Height
+              <input value={hf.logo_height} onChange={e => update({ logo_height: e.target.value })} />
+            </label>
+          </div>
+        )}
+      </div>
+    </div>
+  );
+};
+
+export default HeaderFooterTab;
@@ -26,7 +26,7 @@ const ImagesTab: React.FC<TabProps> = ({ local, mutate }) => {
               onChange={e => mutate({ default_image_width: `${e.target.value}%` })}
             />
           </div>
-          <div className="helper-text">Default width for images inserted into the document</div>
+          <div className="helper-text">Width of images without a <code>width</code> of their own</div>
         </label>
         <label>Default Alignment
           <select value={local.default_image_alignment} onChange={e => mutate({ default_image_alignment: e.target.value })}>
@@ -34,7 +34,7 @@ const ImagesTab: React.FC<TabProps> = ({ local, mutate }) => {
             <option value="center">Center</option>
             <option value="right">Right</option>
           </select>
-          <div className="helper-text">Where images are positioned by default</div>
+          <div className="helper-text">Position of images without an <code>align</code> of their own</div>
         </label>
       </div>
     </div>
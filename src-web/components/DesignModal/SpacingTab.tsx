@@ -47,32 +47,6 @@ const SpacingTab: React.FC<TabProps> = ({ local, mutate }) => {
           </div>
           <div className="helper-text">Space between paragraphs</div>
         </label>
-        <label className="checkbox-label">
-          <input 
-            type="checkbox" 
-            checked={local.page_numbers}
-            onChange={e => mutate({ page_numbers: e.target.checked })}
-          /> 
-          <span>Page Numbers</span>
-        </label>
-        <label className="checkbox-label">
-          <input 
-            type="checkbox" 
-            checked={local.header_title}
-            onChange={e => mutate({ header_title: e.target.checked })}
-          /> 
-          <span>Document Title in Header</span>
-        </label>
-        {local.header_title && (
-          <label>Header Text
-            <input
-              placeholder="Enter header text"
-              value={local.header_text}
-              onChange={e => mutate({ header_text: e.target.value })}
-            />
-            <div className="helper-text">Text to display in page header</div>
-          </label>
-        )}
       </div>
     </div>
   );
@@ -1,20 +1,8 @@
 import React from 'react';
 import type { TabProps } from './types';
-import { logger as designLogger } from '../../utils/logger';
-
-interface StructureTabProps extends TabProps {
-  handleBrowseCoverImage: () => void;
-  currentFile?: string | null;
-}
-
-const StructureTab: React.FC<StructureTabProps> = ({ local, mutate, handleBrowseCoverImage, currentFile }) => {
-  // Get document name from current file for default cover title
-  const getDefaultCoverTitle = () => {
-    if (!currentFile) return '';
-    const fileName = currentFile.split(/[\\/]/).pop() || currentFile;
-    return fileName.replace(/\.md$/i, '').replace(/\.markdown$/i, '');
-  };
+import type { Preferences } from '../../types';
 
+const StructureTab: React.FC<TabProps> = ({ local, mutate }) => {
   return (
     <div className="tab-panel">
       <h3>Document Structure</h3>
@@ -23,6 +11,41 @@ const StructureTab: React.FC<StructureTabProps> = ({ local, mutate, handleBrowse
           <input type="checkbox" checked={local.number_sections} onChange={e => mutate({ number_sections: e.target.checked })} /> 
           <span>Number Sections</span>
         </label>
+        {local.number_sections && (
+          <div className="form-grid">
+            <label>Numbering Style
+              <input
+                list="heading-numbering-presets"
+                placeholder="1.1"
+                value={local.heading_numbering ?? '1.1'}
+                onChange={e => mutate({ heading_numbering: e.target.value })}
+              />
+              <datalist id="heading-numbering-presets">
+                <option value="1.1" />
+                <option value="1.a" />
+                <option value="I.1" />
+                <option value="A.1" />
+                <option value="Chapter 1" />
+                <option value="Part I" />
+              </datalist>
+            </label>
+            <label>Numbered Levels
+              <select
+                value={local.heading_numbering_depth ?? 6}
+                onChange={e => mutate({ heading_numbering_depth: parseInt(e.target.value, 10) })}
+              >
+                <option value={1}>Level 1 only</option>
+                <option value={2}>Levels 1–2</option>
+                <option value={3}>Levels 1–3</option>
+                <option value={4}>Levels 1–4</option>
+                <option value={6}>All levels</option>
+              </select>
+            </label>
+          </div>
+        )}
+        {local.number_sections && (
+          <div className="helper-text">1 = numbers, a/A = letters, i/I = roman numerals. A word before the pattern (“Chapter 1”) labels top-level headings only.</div>
+        )}
         <label className="checkbox-label">
           <input type="checkbox" checked={local.toc} onChange={e => mutate({ toc: e.target.checked })} /> 
           <span>Table of Contents</span>
@@ -35,7 +58,7 @@ const StructureTab: React.FC<StructureTabProps> = ({ local, mutate, handleBrowse
                 value={local.toc_title}
                 onChange={e => mutate({ toc_title: e.target.value })}
               />
-              <div className="helper-text">Optional heading above the table of contents</div>
+              <div className="helper-text">Optional heading above the table of contents; <code>auto</code> uses “Contents” in the document language</div>
             </label>
             <label className="checkbox-label">
               <input 
@@ -45,110 +68,76 @@ const StructureTab: React.FC<StructureTabProps> = ({ local, mutate, handleBrowse
               /> 
               <span>Two-Column TOC</span>
             </label>
-          </>
-        )}
-        <label className="checkbox-label">
-          <input 
-            type="checkbox" 
-            checked={local.cover_page} 
-            onChange={e => {
-              const isEnabled = e.target.checked;
-              // When enabling cover page, auto-populate title if empty
-              if (isEnabled && !local.cover_title) {
-                const defaultTitle = getDefaultCoverTitle();
-                if (defaultTitle) {
-                  mutate({ cover_page: isEnabled, cover_title: defaultTitle });
-                  return;
-                }
-              }
-              mutate({ cover_page: isEnabled });
-            }} 
-          /> 
-          <span>Cover Page</span>
-        </label>
-        {local.cover_page && (
-          <>
-            <label>Cover Title
+            <div className="form-grid">
+              <label>Placement
+                <select
+                  value={local.toc_placement ?? 'after_cover'}
+                  onChange={e => mutate({ toc_placement: e.target.value as Preferences['toc_placement'] })}
+                >
+                  <option value="after_cover">After the cover</option>
+                  <option value="after_abstract">After the abstract</option>
+                  <option value="end">End of document</option>
+                </select>
+              </label>
+              <label>Depth
+                <select
+                  value={local.toc_depth ?? 3}
+                  onChange={e => mutate({ toc_depth: parseInt(e.target.value, 10) })}
+                >
+                  <option value={1}>Level 1</option>
+                  <option value={2}>Levels 1–2</option>
+                  <option value={3}>Levels 1–3</option>
+                  <option value={4}>Levels 1–4</option>
+                  <option value={6}>All levels</option>
+                </select>
+              </label>
+            </div>
+            <label className="checkbox-label">
               <input
-                placeholder="Document title"
-                value={local.cover_title}
-                onChange={e => mutate({ cover_title: e.target.value })}
+                type="checkbox"
+                checked={local.toc_leaders ?? true}
+                onChange={e => mutate({ toc_leaders: e.target.checked })}
               />
+              <span>Dot Leaders</span>
             </label>
-            <label>Cover Writer
+            <label className="checkbox-label">
               <input
-                placeholder="Author or organization"
-                value={local.cover_writer}
-                onChange={e => mutate({ cover_writer: e.target.value })}
+                type="checkbox"
+                checked={local.toc_unnumbered ?? true}
+                onChange={e => mutate({ toc_unnumbered: e.target.checked })}
               />
+              <span>Include Unnumbered Headings</span>
             </label>
-            <label>Cover Image
-              <div className="input-with-button">
-                <input
-                  placeholder="Relative path (e.g., assets/image.png)"
-                  value={local.cover_image}
-                  onChange={e => {
-                    const newValue = e.target.value;
-                    // If clearing the field, delete the old image
-                    if (!newValue && local.cover_image) {
-                      import('../../api').then(({ deleteFile }) => {
-                        deleteFile(local.cover_image).catch(err => 
-                          designLogger.warn('Failed to delete cover image', err)
-                        );
-                      });
-                    }
-                    mutate({ cover_image: newValue });
-                  }}
-                />
-                <button
-                  type="button"
-                  onClick={handleBrowseCoverImage}
-                >
-                  Browse…
-                </button>
-                {local.cover_image && (
-                  <button
-                    type="button"
-                    onClick={() => {
-                      import('../../api').then(({ deleteFile }) => {
-                        deleteFile(local.cover_image).catch(err => 
-                          designLogger.warn('Failed to delete cover image', err)
-                        );
-                      });
-                      mutate({ cover_image: '' });
-                    }}
-                    title="Clear cover image"
-                  >
-                    ✕
-                  </button>
-                )}
-              </div>
-              <div className="helper-text">Browse to import image to assets, or provide relative path</div>
-            </label>
-            <label>Cover Image Width
-              <div className="slider-group">
-                <input 
-                  type="range" 
-                  min="10"
-                  max="100"
-                  step="5"
-                  value={parseInt(local.cover_image_width.replace('%', '')) || 60}
-                  onChange={e => mutate({ cover_image_width: `${e.target.value}%` })}
-                />
-                <input
-                  type="number"
-                  className="slider-value-input"
-                  min="10"
-                  max="100"
-                  step="5"
-                  value={parseInt(local.cover_image_width.replace('%', '')) || 60}
-                  onChange={e => mutate({ cover_image_width: `${e.target.value}%` })}
-                />
-              </div>
-              <div className="helper-text">Size of the cover image as percentage of page width</div>
-            </label>
+            <div className="helper-text">The abstract comes from the front matter (<code>abstract:</code>). Add <code>{'{.unlisted}'}</code> after a heading to leave it out of the table of contents.</div>
           </>
         )}
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={local.toc_mini ?? false}
+            onChange={e => mutate({ toc_mini: e.target.checked })}
+          />
+          <span>Chapter Contents</span>
+        </label>
+        {local.toc_mini && (
+          <div className="helper-text">Lists the sections of each chapter (top-level heading) below its heading</div>
+        )}
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={local.list_of_figures || false}
+            onChange={e => mutate({ list_of_figures: e.target.checked })}
+          />
+          <span>List of Figures</span>
+        </label>
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={local.list_of_tables || false}
+            onChange={e => mutate({ list_of_tables: e.target.checked })}
+          />
+          <span>List of Tables</span>
+        </label>
       </div>
     </div>
   );
@@ -1,9 +1,64 @@
 import React, { useEffect } from 'react';
 import type { TabProps } from './types';
+import type { Fonts, HeadingStyle, Typography } from '../../types';
 import { useFontStore } from '../../stores/fontStore';
+import { defaultTypography } from '../../stores/preferencesStore';
+
+const HEADING_LEVELS = [1, 2, 3, 4];
+
+type FallbackRole = 'main_fallback' | 'mono_fallback' | 'headings' | 'cjk' | 'emoji' | 'math';
+
+const FALLBACK_ROLES: { role: FallbackRole; label: string; help: string }[] = [
+  { role: 'main_fallback', label: 'Body Text', help: 'After the body font' },
+  { role: 'mono_fallback', label: 'Code', help: 'After the monospace font' },
+  { role: 'headings', label: 'Headings', help: 'Instead of the body font for headings' },
+  { role: 'cjk', label: 'Chinese, Japanese & Korean', help: 'After the fonts of every role, e.g. Noto Sans CJK JP' },
+  { role: 'emoji', label: 'Emoji', help: 'Last in every role, e.g. Noto Color Emoji' },
+  { role: 'math', label: 'Math', help: 'For equations, e.g. New Computer Modern Math' },
+];
+
+// Select values for the tri-state (theme default / on / off) settings
+const toChoice = (value: boolean | null) => (value === null ? '' : value ? 'on' : 'off');
+const fromChoice = (value: string) => (value === '' ? null : value === 'on');
 
 const TypographyTab: React.FC<TabProps> = ({ local, mutate }) => {
   const { fonts: systemFonts, monoFonts, isLoading: loading, loadFonts } = useFontStore();
+  const typo = local.typography ?? defaultTypography;
+  const update = (patch: Partial<Typography>) => mutate({ typography: { ...typo, ...patch } });
+  const updateChain = (role: FallbackRole, families: string[]) => mutate({ fonts: { ...local.fonts, [role]: families } as Fonts });
+  const fontChain = (role: FallbackRole) => {
+    const families = local.fonts[role] ?? [];
+    return (
+      <>
+        {families.map((family, index) => (
+          <div className="input-with-button" key={index}>
+            <select
+              value={family}
+              onChange={e => updateChain(role, families.map((f, i) => (i === index ? e.target.value : f)))}
+              disabled={loading}
+            >
+              {!systemFonts.includes(family) && <option value={family}>{family ? `${family} (not installed)` : 'Choose a font…'}</option>}
+              {systemFonts.map(font => (
+                <option key={font} value={font}>{font}</option>
+              ))}
+            </select>
+            <button type="button" onClick={() => updateChain(role, families.filter((_, i) => i !== index))} title="Remove font">✕</button>
+          </div>
+        ))}
+        <div>
+          <button type="button" onClick={() => updateChain(role, [...families, ''])}>Add Font</button>
+        </div>
+      </>
+    );
+  };
+  const updateLevel = (level: number, patch: Partial<HeadingStyle>) => {
+    const levels = [...typo.heading_levels];
+    while (levels.length < level) {
+      levels.push({ size: '', weight: '' });
+    }
+    levels[level - 1] = { ...levels[level - 1], ...patch };
+    update({ heading_levels: levels });
+  };
 
   useEffect(() => {
     // Load fonts from cache or system (only once per app session)
@@ -47,6 +102,22 @@ const TypographyTab: React.FC<TabProps> = ({ local, mutate }) => {
           <div className="font-preview" data-font-role="mono" data-font-value={local.fonts.mono}>{`code {sample} <tag/> \u2014 ${local.fonts.mono}`}</div>
           <div className="helper-text">Used for code blocks and inline code</div>
         </label>
+      </div>
+
+      <h3>Fallback Fonts</h3>
+      <div className="helper-text">Tried in order for characters the fonts before them lack, such as Chinese, Japanese, Korean or emoji</div>
+      <div className="form-grid one-col">
+        {FALLBACK_ROLES.map(({ role, label, help }) => (
+          <div key={role}>
+            <label>{label}</label>
+            {fontChain(role)}
+            <div className="helper-text">{help}</div>
+          </div>
+        ))}
+      </div>
+
+      <h3>Size & Color</h3>
+      <div className="form-grid one-col">
         <label>Font Size
           <div className="slider-group">
             <input 
@@ -138,6 +209,113 @@ const TypographyTab: React.FC<TabProps> = ({ local, mutate }) => {
           <div className="helper-text">Used for links and highlights</div>
         </label>
       </div>
+
+      <h3>Paragraphs</h3>
+      <div className="form-grid">
+        <label>Alignment
+          <select value={toChoice(typo.justify)} onChange={e => update({ justify: fromChoice(e.target.value) })}>
+            <option value="">Theme default</option>
+            <option value="on">Justified</option>
+            <option value="off">Ragged right</option>
+          </select>
+        </label>
+        <label>Hyphenation
+          <select value={toChoice(typo.hyphenate)} onChange={e => update({ hyphenate: fromChoice(e.target.value) })}>
+            <option value="">Automatic</option>
+            <option value="on">On</option>
+            <option value="off">Off</option>
+          </select>
+        </label>
+        <label>First-Line Indent
+          <input
+            placeholder="None (e.g. 1.5em)"
+            value={typo.first_line_indent}
+            onChange={e => update({ first_line_indent: e.target.value })}
+          />
+        </label>
+      </div>
+      <div className="form-grid one-col">
+        <div className="helper-text">Automatic hyphenation breaks words only in justified paragraphs</div>
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={typo.widow_orphan_control}
+            onChange={e => update({ widow_orphan_control: e.target.checked })}
+          />
+          <span>Avoid Widows and Orphans</span>
+        </label>
+        <div className="helper-text">Keeps single lines of a paragraph from being left alone at the top or bottom of a page</div>
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={typo.ligatures}
+            onChange={e => update({ ligatures: e.target.checked })}
+          />
+          <span>Ligatures</span>
+        </label>
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={typo.oldstyle_figures}
+            onChange={e => update({ oldstyle_figures: e.target.checked })}
+          />
+          <span>Old-Style Figures</span>
+        </label>
+        <div className="helper-text">OpenType features; fonts without them are left unchanged</div>
+      </div>
+
+      <h3>Headings</h3>
+      <div className="form-grid one-col">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={typo.keep_with_next}
+            onChange={e => update({ keep_with_next: e.target.checked })}
+          />
+          <span>Keep with Next Paragraph</span>
+        </label>
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={typo.small_caps_headings}
+            onChange={e => update({ small_caps_headings: e.target.checked })}
+          />
+          <span>Small Caps</span>
+        </label>
+        <label>Letter Spacing
+          <input
+            placeholder="Theme default (e.g. 0.05em)"
+            value={typo.heading_tracking}
+            onChange={e => update({ heading_tracking: e.target.value })}
+          />
+        </label>
+      </div>
+      {HEADING_LEVELS.map(level => {
+        const style = typo.heading_levels[level - 1] ?? { size: '', weight: '' };
+        return (
+          <div className="form-grid" key={level}>
+            <label>Level {level} Size
+              <input
+                placeholder="Theme default"
+                value={style.size}
+                onChange={e => updateLevel(level, { size: e.target.value })}
+              />
+            </label>
+            <label>Level {level} Weight
+              <select value={style.weight} onChange={e => updateLevel(level, { weight: e.target.value })}>
+                <option value="">Theme default</option>
+                <option value="light">Light</option>
+                <option value="regular">Regular</option>
+                <option value="medium">Medium</option>
+                <option value="semibold">Semibold</option>
+                <option value="bold">Bold</option>
+                <option value="black">Black</option>
+              </select>
+            </label>
+          </div>
+        );
+      })}
+      <div className="helper-text">Sizes in pt, or in em relative to the body text. Empty fields keep the theme's style.</div>
     </div>
   );
 };
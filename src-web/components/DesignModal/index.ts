@@ -2,7 +2,9 @@ export { default as ThemesTab } from './ThemesTab';
 export { default as DocumentTab } from './DocumentTab';
 export { default as TypographyTab } from './TypographyTab';
 export { default as SpacingTab } from './SpacingTab';
+export { default as HeaderFooterTab } from './HeaderFooterTab';
 export { default as StructureTab } from './StructureTab';
+export { default as CoverTab } from './CoverTab';
 export { default as ImagesTab } from './ImagesTab';
 export { default as PresetsTab } from './PresetsTab';
 export { default as AdvancedTab } from './AdvancedTab';
@@ -1,6 +1,6 @@
 import type { Preferences } from '../../types';
 
-export type TabSection = 'themes' | 'document' | 'typography' | 'spacing' | 'structure' | 'images' | 'presets' | 'advanced';
+export type TabSection = 'themes' | 'document' | 'typography' | 'spacing' | 'headerfooter' | 'structure' | 'cover' | 'images' | 'presets' | 'advanced';
 
 export interface TabProps {
   local: Preferences;
@@ -0,0 +1,133 @@
+import { useEffect, useState } from 'react';
+import './DesignModal.css';
+import { showOpenDialog, exportMailMerge, listenForMailMergeProgress } from '../api';
+import type { MailMergeProgress, MailMergeResult } from '../types';
+
+interface MailMergeModalProps {
+  open: boolean;
+  templatePath: string;
+  onClose: () => void;
+  onDone: (result: MailMergeResult) => void;
+  onError: (err: unknown) => void;
+}
+
+const DEFAULT_PATTERN = 'document-{{ row }}.pdf';
+
+export function MailMergeModal({ open, templatePath, onClose, onDone, onError }: MailMergeModalProps) {
+  const templateDir = templatePath.replace(/[\\/][^\\/]*$/, '');
+  const [dataPath, setDataPath] = useState('');
+  const [outputDir, setOutputDir] = useState(templateDir);
+  const [pattern, setPattern] = useState(DEFAULT_PATTERN);
+  const [running, setRunning] = useState(false);
+  const [progress, setProgress] = useState<MailMergeProgress | null>(null);
+
+  useEffect(() => {
+    if (!open) return;
+    let unlisten: (() => void) | undefined;
+    listenForMailMergeProgress(setProgress).then((fn) => { unlisten = fn; });
+    return () => unlisten?.();
+  }, [open]);
+
+  if (!open) return null;
+
+  const handlePickData = async () => {
+    const picked = await showOpenDialog([{ name: 'Data', extensions: ['csv', 'tsv', 'json'] }]);
+    if (picked) setDataPath(picked);
+  };
+
+  const handlePickOutput = async () => {
+    const picked = await showOpenDialog(undefined, true);
+    if (picked) setOutputDir(picked);
+  };
+
+  const handleRun = async () => {
+    setRunning(true);
+    setProgress(null);
+    try {
+      const result = await exportMailMerge(templatePath, dataPath.trim(), outputDir.trim(), pattern.trim() || DEFAULT_PATTERN);
+      onDone(result);
+    } catch (err) {
+      onError(err);
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const canRun = !running && dataPath.trim().length > 0 && outputDir.trim().length > 0;
+
+  return (
+    <div className="design-modal-overlay" role="dialog" aria-modal="true" aria-labelledby="mailmerge-modal-title">
+      <div className="design-modal image-modal" onKeyDown={(e) => { if (e.key === 'Escape' && !running) onClose(); }}>
+        <div className="design-modal-header">
+          <h2 id="mailmerge-modal-title">Mail Merge</h2>
+        </div>
+
+        <div className="design-section">
+          <div className="form-grid">
+            <label htmlFor="mailmerge-data">Data file (CSV, TSV or JSON)
+              <input
+                id="mailmerge-data"
+                type="text"
+                placeholder="recipients.csv"
+                value={dataPath}
+                onChange={(e) => setDataPath(e.target.value)}
+              />
+            </label>
+            <label>
+              Pick data
+              <button type="button" onClick={handlePickData} disabled={running}>
+                Pick…
+              </button>
+            </label>
+            <label htmlFor="mailmerge-output">Output folder
+              <input
+                id="mailmerge-output"
+                type="text"
+                value={outputDir}
+                onChange={(e) => setOutputDir(e.target.value)}
+              />
+            </label>
+            <label>
+              Pick folder
+              <button type="button" onClick={handlePickOutput} disabled={running}>
+                Pick…
+              </button>
+            </label>
+          </div>
+        </div>
+
+        <div className="design-section">
+          <div className="form-grid one-col">
+            <label htmlFor="mailmerge-pattern">File name pattern
+              <input
+                id="mailmerge-pattern"
+                type="text"
+                placeholder={DEFAULT_PATTERN}
+                value={pattern}
+                onChange={(e) => setPattern(e.target.value)}
+              />
+            </label>
+            <div className="helper-text">
+              Each column of the data file is available as a variable, e.g. <code>{'{{ last_name }}'}</code> in the document and
+              in the pattern. <code>{'{{ row }}'}</code> is the row number.
+            </div>
+            {running && progress && (
+              <div className="helper-text">Rendering {progress.current} of {progress.total}{progress.file ? `: ${progress.file}` : ''}</div>
+            )}
+          </div>
+        </div>
+
+        <div className="design-footer">
+          <div className="design-footer-actions">
+            <button className="secondary" type="button" onClick={onClose} disabled={running}>Cancel</button>
+          </div>
+          <button className="primary" type="button" disabled={!canRun} onClick={handleRun}>
+            {running ? 'Rendering…' : 'Generate PDFs'}
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
+
+export default MailMergeModal;
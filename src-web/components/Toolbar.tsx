@@ -2,11 +2,12 @@ import React, { useRef, useState } from 'react';
 import { useEditorStore } from '../stores/editorStore';
 import { useUIStore } from '../stores/uiStore';
 import DesignModal from './DesignModal';
+import MailMergeModal from './MailMergeModal';
 import { invoke } from '@tauri-apps/api/core';
 import { save, open } from '@tauri-apps/plugin-dialog';
 import { handleError, showSuccess } from '../utils/errorHandler';
 import { readMarkdownFile, createFile, writeMarkdownFile, getDocumentsDirectory } from '../api';
-import { scrubRawTypstAnchors } from '../utils/scrubAnchors';
+import { scrubRawTypstAnchors, stripRawTypst } from '../utils/scrubAnchors';
 import './Toolbar.css';
 
 const Toolbar: React.FC = () => {
@@ -34,6 +35,7 @@ const Toolbar: React.FC = () => {
   const [isFullscreen, setIsFullscreen] = React.useState(false);
   const [newFileModalOpen, setNewFileModalOpen] = useState(false);
   const [newFileNameInput, setNewFileNameInput] = useState('');
+  const [mailMergeOpen, setMailMergeOpen] = useState(false);
 
   // Close dropdown when clicking outside
   React.useEffect(() => {
@@ -193,7 +195,7 @@ const Toolbar: React.FC = () => {
       
       if (!filePath) return;
       
-      const cleaned = scrubRawTypstAnchors(content);
+      const cleaned = stripRawTypst(content);
       await writeMarkdownFile(filePath, cleaned);
       setSaveDropdownOpen(false);
       addToast({ type: 'success', message: 'Clean Markdown exported successfully' });
@@ -203,6 +205,17 @@ const Toolbar: React.FC = () => {
     }
   };
 
+  const handleOpenMailMerge = () => {
+    setSaveDropdownOpen(false);
+    const { currentFile, modified } = editor;
+    // The backend renders the template from disk, so it must be saved first.
+    if (!currentFile || currentFile === 'instructions.md' || modified) {
+      addToast({ type: 'warning', message: 'Save the document before running a mail merge' });
+      return;
+    }
+    setMailMergeOpen(true);
+  };
+
   const handleFallbackChange = async (e: React.ChangeEvent<HTMLInputElement>) => {
     const file = e.target.files?.[0];
     if (!file) return;
@@ -413,6 +426,13 @@ const Toolbar: React.FC = () => {
                   >
                     ✨ Export Clean MD
                   </button>
+                  <button
+                    className="dropdown-item"
+                    onClick={handleOpenMailMerge}
+                    title="Generate one PDF per row of a CSV or JSON file"
+                  >
+                    ✉️ Mail Merge…
+                  </button>
                 </div>
               )}
             </div>
@@ -468,6 +488,29 @@ const Toolbar: React.FC = () => {
       )}
       
       {designModalOpen && <DesignModal />}
+      {mailMergeOpen && editor.currentFile && (
+        <MailMergeModal
+          open={mailMergeOpen}
+          templatePath={editor.currentFile}
+          onClose={() => setMailMergeOpen(false)}
+          onDone={(result) => {
+            setMailMergeOpen(false);
+            const failed = result.failures.length;
+            addToast({
+              type: failed > 0 ? 'warning' : 'success',
+              message: failed > 0
+                ? `Generated ${result.files.length} PDFs; ${failed} rows failed`
+                : `Generated ${result.files.length} PDFs in ${result.output_dir}`,
+            });
+            result.failures.forEach((f) => console.warn(`[MailMerge] row ${f.row}: ${f.error}`));
+            result.warnings.forEach((w) => console.warn(`[MailMerge] ${w}`));
+          }}
+          onError={(err) => {
+            addToast({ type: 'error', message: 'Mail merge failed' });
+            handleError(err, { operation: 'mail merge', component: 'Toolbar' });
+          }}
+        />
+      )}
     </div>
   );
 };
@@ -42,6 +42,7 @@ Use the toolbar or shortcuts for common actions:
 #### **Document Structure**
 
 - **Headings:** Use \`H1\`, \`H2\`, \`H3\` buttons or (\`Ctrl+Alt+1/2/3\`).
+- **Heading Options:** Add an attribute block after a heading: \`# Preface {.unnumbered .unlisted}\`. \`.unnumbered\` (or \`-\`) leaves out the number, \`.unlisted\` keeps the heading out of the table of contents, and \`#sec:name\` gives it a label you can reference.
 - **Lists:** Create bullet (\`Ctrl+Shift+8\`), numbered (\`Ctrl+Shift+7\`), or task lists (\`Ctrl+Shift+9\`).
 - **Dividers:** Insert a horizontal rule (\`---\`) or a page break.
 
@@ -49,12 +50,135 @@ Use the toolbar or shortcuts for common actions:
 
 - **Insert Image (🖼️):** Drag and drop an image or use the button. Adjust its width with the dropdown (25% - 100%).
 - **Insert Figure (🖼️+):** For professional figures, this option opens a dialog to add a **caption**, precise width, and alignment.
+- **Captions in Markdown:** An image on its own line with alt text or a title becomes a numbered figure: \`![Caption](chart.png "Optional longer caption"){placement=top}\`. Placement can be \`here\` (default), \`top\`, \`bottom\` or \`auto\`.
+- **Size and Position:** Add attributes after an image on its own line: \`![](photo.jpg){width=50% align=right}\`. Options are \`width\` and \`height\` (\`50%\`, \`6cm\`, \`200px\`, ...), \`fit=cover|contain|stretch\` (how the picture fills a box when you give both), \`align=left|center|right\`, and \`wrap=left|right\` to set the image beside the paragraph that follows it. Images without a width or alignment use the defaults from **🎨 Design → Images**.
+- **Galleries:** To show several images side by side as one figure, put them in a \`gallery\` block. Each image gets a letter (a, b, ...) and its own caption, and the text after the images is the caption of the whole figure:
+
+  \`\`\`markdown
+  ::: {.gallery #fig:login columns=2}
+  ![Before](login-old.png)
+  ![After](login-new.png)
+
+  The login screen before and after the redesign.
+  :::
+  \`\`\`
+
+  \`columns\` sets how many images share a row (all of them by default). Quarto's \`layout-ncol=2\` works too.
+- **Table Captions:** Put \`Table: Caption\` on its own line right before or after a table to number it.
+- **Lists of Figures/Tables:** Turn them on in **🎨 Design → Structure**; they are printed after the table of contents.
 
 #### **Tables & Math**
 
 - **Tables (▦):** Insert a basic Markdown table template.
+- **Grid & Multiline Tables:** When a cell needs a list, several paragraphs or code, draw the table Pandoc-style. Grid tables use \`+---+\` borders and \`|\` edges; put \`+===+\` under the header row, colons for alignment (\`+:---+---:+\`), and leave out a border to let a cell span several columns or rows. Multiline tables are framed by lines of dashes, with a dashed rule under the header and a blank line between rows. Column widths follow the widths you draw, and a \`Table: ...\` line next to either kind adds a caption.
+- **Tables from Data:** An empty code block like \`\`\`\` \`\`\`{table src="data/results.csv" caption="Results" #tbl:results} \`\`\`\` turns a CSV, TSV or JSON file into a table every time you render. Options: \`columns="name,score"\` (pick and order columns), \`headers="Name,Score"\`, \`align="lr"\` (one letter per column), \`format="score:.2;amount:$,.0"\` (decimals, thousands separators, \`%\`), and \`header-repeat=false\`.
+- **Charts:** A code block opened with \`\`\`\` \`\`\`{chart caption="Revenue" #fig:revenue} \`\`\`\` draws a chart from data when you render. Inside, write \`type:\` (\`bar\`, \`line\`, \`scatter\` or \`pie\`), \`src:\` (a CSV, TSV or JSON file) or inline \`data:\` rows, the \`x:\` column and one or more \`y:\` columns (\`y: [north, south]\`). Optional: \`title:\`, \`x-label:\`, \`y-label:\`, \`aspect:\` (width ÷ height) and \`colors:\`. Charts use your accent colour, are drawn on your computer without any online service, and can be referenced like figures.
 - **Math:** Write LaTeX math expressions. Use \`$x$\` for inline math ($E=mc^2$) and \`$$\` for block math, which is centered on its own line.
 
+#### **Variables**
+
+Write \`{{ name }}\` anywhere in your text and Tideflow fills in the value when rendering. Values come from (first match wins):
+- A YAML front matter block at the top of the document (\`---\` … \`---\`).
+- A \`_variables.yml\` or \`variables.yml\` next to the document or, in a git repository, in a parent folder up to the repository root, handy for product names and version numbers shared across many documents.
+- Your design settings, as \`{{ prefs.cover_title }}\`, \`{{ prefs.papersize }}\` and so on.
+- Built-ins: \`{{ date }}\`, \`{{ year }}\`, \`{{ filename }}\` and \`{{ git.rev }}\` (the current commit, when the document is in a git repository).
+
+Nested values use dots: \`{{ product.version }}\`. Variables are not replaced inside code; write \`\\{{ name }}\` to print the braces literally. Unknown names are reported as warnings.
+
+#### **Quarto Shortcodes**
+
+Documents written for Quarto (\`.qmd\`) keep working. These shortcodes are understood:
+- \`{{< pagebreak >}}\` starts a new page.
+- \`{{< meta title >}}\` prints a front matter value; \`{{< var product.version >}}\` prints one from the variables file.
+- \`{{< env USER >}}\` prints an environment variable (\`{{< env USER "someone" >}}\` gives a fallback).
+- \`{{< video https://example.com/intro.mp4 title="Intro" >}}\` becomes a link, since a PDF cannot play videos.
+- \`{{< kbd Ctrl-S >}}\` shows a key combination and \`{{< lipsum 2 >}}\` fills in placeholder paragraphs.
+
+Other shortcodes are removed and reported as warnings. Write \`{{{< name >}}}\` to print a shortcode literally.
+
+#### **Code From Files**
+
+Keep examples in sync with real source files by leaving a code block empty and pointing it at a file:
+- \`\`\`\` \`\`\`rust {include="src/lib.rs" lines="10-42"} \`\`\`\` takes lines 10 to 42 (paths are relative to the document).
+- \`region="setup"\` takes the lines between \`// region: setup\` and \`// endregion\` markers instead.
+- \`numbers=true\` adds line numbers and \`highlight="12,20-22"\` marks lines, both counted as in the file.
+
+If the file, range or region no longer exists you get a warning.
+
+#### **Cross-References**
+
+Label things with an attribute block, then refer to them by label and Tideflow fills in the number:
+- Figures: \`![Architecture](arch.png){#fig:arch}\`
+- Tables: a \`Table: Results {#tbl:results}\` line right before or after the table
+- Equations: \`$$ E = m c^2 $$ {#eq:energy}\` (Typst math syntax)
+- Sections: \`## Introduction {#sec:intro}\`
+
+Write \`@fig:arch\`, \`@tbl:results\`, \`@eq:energy\` or \`@sec:intro\` in your text, including figure and table captions. A reference to a label that doesn't exist shows up as a red \`??\` in the PDF and as a warning in the app.
+
+#### **Splitting Long Documents**
+
+Keep chapters in separate files and pull them in with an include line:
+- \`{{< include chapters/intro.md >}}\` inserts the file, resolved relative to the current document.
+- \`{{< include appendix.md shift-heading-level-by=1 >}}\` demotes its headings by one level (\`#\` becomes \`##\`).
+
+Images inside included files are resolved relative to those files. Missing files and include cycles are reported as warnings.
+
+#### **Callouts, Columns & Styled Spans**
+
+Wrap blocks in \`:::\` fences with a class to style them (the same syntax Pandoc and Quarto use, so \`.qmd\` callouts work too):
+- \`::: {.callout-note title="Heads up"}\` … \`:::\` draws a callout box; \`callout-tip\`, \`callout-warning\`, \`callout-important\` and \`callout-caution\` change its colour.
+- \`:::: columns\` holding several \`::: {.column width="40%"}\` blocks puts them side by side.
+- \`::: landscape\` puts its content on landscape pages; \`::: pagebreak\` starts a new page; \`::: center\`, \`::: small\` and \`::: hidden\` center, shrink or hide their content.
+- For a few words, use a span: \`[draft]{.smallcaps}\`, \`[key term]{.mark}\`, \`[text]{.underline}\`, \`[aside]{.small}\`.
+
+Use more colons on the outer fence when nesting (\`::::\` around \`:::\`). Some themes add their own classes: in the Notebook theme, try \`::: sticky\` and \`[text]{.ink}\`.
+
+#### **Page Breaks & Landscape Pages**
+
+Put any of these on a line of its own:
+- \`\\newpage\` (or \`\\pagebreak\`, \`<!-- pagebreak -->\`) starts a new page.
+- \`\\columnbreak\` (or \`<!-- columnbreak -->\`) moves on to the next column in a two-column layout.
+- \`<!-- landscape -->\` turns the following pages sideways, handy for wide tables, until \`<!-- portrait -->\` (or the end of the document). LaTeX's \`\\begin{landscape}\` and \`\\end{landscape}\` work too.
+
+#### **Document Classes**
+
+Set \`documentclass:\` in the front matter to change how the document is put together (themes only change how it looks):
+- \`article\` (the default): one continuous document.
+- \`report\`: every chapter (top-level heading) starts on a new page, and the \`abstract:\` and \`executive-summary:\` of the front matter get pages of their own before the table of contents.
+- \`book\`: chapters start on right-hand (odd) pages. Mark the parts of the book with \`\\frontmatter\` (pages numbered i, ii, iii and headings unnumbered), \`\\mainmatter\` (numbering starts again at 1) and \`\\backmatter\` (unnumbered again), each on a line of its own. \`# Basics {.part}\` makes a part page ("Part I", "Part II", ...).
+- \`letter\`: \`from:\` and \`to:\` addresses, the date and a bold \`subject:\` above the text, and \`closing:\` ("Sincerely," by default) and \`signature:\` (the author by default) below it.
+- \`memo\`: a "Memorandum" heading with \`to:\`, \`from:\`, \`cc:\`, \`date:\` and \`subject:\` lines.
+
+Letters and memos have no cover page, table of contents or section numbers. The app's templates folder has a sample of each (\`memo.qmd\`, \`letter.qmd\`, \`report-cover.qmd\`, \`book.qmd\`).
+
+#### **Language & Direction**
+
+Set the document language in the Design panel (**Document**) or in the front matter:
+
+\`\`\`yaml
+---
+lang: de-CH
+---
+\`\`\`
+
+The language (and optional region) sets hyphenation and quotation marks, and translates the text Tideflow adds itself — callout titles, "Figure" and "Table" in captions and references, the abstract and list headings, part pages, memo labels, the letter closing and spelled-out dates. English, German (\`de\`), French (\`fr\`), Japanese (\`ja\`) and Korean (\`ko\`) are translated so far; other languages get their hyphenation and quotes, with English for the rest. Write \`auto\` as the TOC title for a translated "Contents" heading.
+
+Arabic and Hebrew documents are set right to left: paragraphs start on the right, headers and footers, tables and lists are mirrored (the *Left* header slot goes to the right), and the borders of block quotes move to the right side. Tideflow switches automatically when most of the text is Arabic or Hebrew, or when the language is one written right to left (\`ar\`, \`he\`, \`fa\`, \`ur\`, ...); \`dir: rtl\` or \`dir: ltr\` in the front matter decides explicitly. Latin words, numbers and code inside a right-to-left paragraph keep their own direction.
+
+#### **Raw Typst**
+
+When Markdown is not enough, write Typst directly:
+- A fenced block marked \`{=typst}\` is passed to Typst as is:
+  \`\`\`\`
+  \`\`\`{=typst}
+  #set text(fill: blue)
+  #lorem(30)
+  \`\`\`
+  \`\`\`\`
+- Inline, add \`{=typst}\` after a code span: \`\` \`#pagebreak()\`{=typst} \`\`.
+
+Blocks and spans marked for other formats (\`{=html}\`, \`{=latex}\`) are left out of the PDF. Raw Typst is saved with your document like any other text.
+
 #### **Advanced Elements**
 
 The toolbar also gives you quick access to:
@@ -76,19 +200,48 @@ The right side of your screen is a live preview of your final PDF.
 Click the **🎨 Design** button to control your document's entire look and feel.
 
 - **Themes:** Instantly apply a professional design. Choose from 12 presets like *Academic*, *Minimal*, or *Elegant*. You can see visual thumbnails for each.
-- **Document:** Set the paper size (A4, Letter), orientation, and toggle a two-column layout for the entire document.
-- **Typography:** Change the main font, font size, and line height.
+- **Document:** Set the paper size (A4, Letter), orientation, language, and toggle a two-column layout for the entire document.
+- **Typography:** Change the main font, font size, and line height. **Fallback Fonts** take over, in order, for characters a font lacks: add a Chinese, Japanese or Korean font (e.g. *Noto Sans CJK JP*) and an emoji font once and every role — body text, code and headings — falls back to them; headings and math can also get fonts of their own. Fonts that are not installed are reported and skipped. Paragraphs can be justified or ragged right, hyphenated, and indented on the first line; widow and orphan control keeps single lines from being stranded at the top or bottom of a page. Ligatures and old-style figures switch the font's OpenType features. Headings can be kept with the paragraph after them, set in small caps or letter-spaced, and each level can get its own size (\`20pt\`, or \`1.6em\` of the body text) and weight. Anything left on *Theme default* keeps the theme's style.
 - **Spacing:** Adjust page margins and paragraph spacing.
-- **Structure:** Automatically add a **Cover Page** or a **Table of Contents (TOC)**.
+- **Header & Footer:** Put text on the left, center or right of every page's header and footer. Placeholders are filled in per page: \`{page}\`, \`{total}\` (page count), \`{title}\`, \`{chapter}\` (the current top-level heading), \`{date}\` and \`{author}\` — title, author and date come from the front matter, or from the cover page. You can also leave the first page bare, mirror left and right on even pages, add a logo and draw separator lines. **Page Numbering** picks the style (1, i, I, a, A), the number of the first content page, the text of the quick-setup page number ("Page 1 of 10"), and whether the table of contents and lists are numbered i, ii, iii.
+- **Structure:** Automatically add a **Table of Contents (TOC)**. The TOC can go after the cover, after the abstract (the \`abstract:\` field of the front matter) or at the end, list one to all heading levels, with or without dot leaders and unnumbered headings. **Chapter Contents** lists each chapter's sections below its heading. With **Number Sections** on, choose a numbering style — \`1.1\`, \`1.a\`, \`I.1\`, or a labelled one like \`Chapter 1\` (the label only goes on top-level headings) — and how many heading levels get numbers.
+- **Cover:** Add a cover page and pick its layout — centered, left-aligned, or a banner in the accent color across the top. Besides the title it can show a subtitle, several authors with their affiliations, an organization, a version number, a date (\`today\` or \`2025-03-14\`, in the format you choose), a logo and a background image that fills the whole page. The front matter fills in the same fields, and wins over the panel:
+
+  \`\`\`yaml
+  ---
+  title: Annual Report
+  subtitle: Fiscal Year 2025
+  author:
+    - name: Ada Lovelace
+      affiliation: Analytical Engines Ltd.
+    - Charles Babbage
+  organization: Tideflow Inc.
+  version: "1.2"
+  date: today
+  date-format: long
+  ---
+  \`\`\`
 - **Presets:** Save your custom style settings to reuse in other documents.
 
 Changes are applied live. You can drag-to-scroll through the theme gallery and other options for quick navigation.
 
+#### **Safe Mode**
+
+Rendering a document runs whatever raw Typst it contains. Before opening Markdown from someone you don't know, turn on **Safe Mode** in the Design panel's Advanced tab (or put \`safe-mode: true\` in a document's front matter). In safe mode:
+- raw Typst (\`<!--raw-typst ... -->\` comments and \`{=typst}\` blocks) is removed,
+- includes, code and data files, charts and images must be in the document's folder (or the assets folder),
+- equations containing \`#\` are shown as text, and \`{{< env >}}\` is unavailable,
+- variables are not filled into included code, charts or grid table captions,
+- the abstract, the executive summary and other front matter text are cleaned the same way.
+
+Everything removed is listed in the warnings.
+
 ### 3. Saving & Exporting (The Top Toolbar)
 
 - **Save (💾 / \`Ctrl+S\`):** Save your \`.md\` file.
 - **Export PDF (📄 / \`Ctrl+E\`):** This is the main goal. It saves the beautifully styled PDF you see in the preview.
 - **Export Clean MD:** Found in the save dropdown menu, this option saves a version of your Markdown file with all special formatting codes removed, making it perfect for platforms like GitHub.
+- **Mail Merge:** Also in the save dropdown. Pick a CSV, TSV or JSON file and Tideflow renders the saved document once per row into its own PDF. Every column is available as a variable (\`Dear {{ first_name }},\`), and the file name pattern can use them too (\`{{ last_name }}-certificate.pdf\`; \`{{ row }}\` is the row number). Files already in the output folder are kept; a new one gets a \`-2\`, \`-3\`, ... suffix instead.
 
 ---
 
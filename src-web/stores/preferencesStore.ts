@@ -1,9 +1,33 @@
 import { create } from 'zustand';
-import type { Preferences } from '../types';
+import type { HeaderFooter, Preferences, Typography } from '../types';
 import { logger } from '../utils/logger';
 
 const prefsLogger = logger.createScoped('PreferencesStore');
 
+export const defaultHeaderFooter: HeaderFooter = {
+  header: { left: '', center: '', right: '' },
+  footer: { left: '', center: '', right: '' },
+  first_page: 'same',
+  mirror_even_pages: false,
+  logo: '',
+  logo_position: 'left',
+  logo_height: '0.8cm',
+  rules: false,
+};
+
+export const defaultTypography: Typography = {
+  justify: null,
+  hyphenate: null,
+  first_line_indent: '',
+  widow_orphan_control: true,
+  keep_with_next: true,
+  ligatures: true,
+  oldstyle_figures: false,
+  small_caps_headings: false,
+  heading_tracking: '',
+  heading_levels: [],
+};
+
 // Initial preferences
 export const defaultPreferences: Preferences = {
   theme_id: 'default',
@@ -15,13 +39,35 @@ export const defaultPreferences: Preferences = {
   toc: false,
   toc_title: '',
   toc_two_column: false,
+  toc_depth: 3,
+  toc_placement: 'after_cover',
+  toc_leaders: true,
+  toc_unnumbered: true,
+  toc_mini: false,
+  list_of_figures: false,
+  list_of_tables: false,
   two_column_layout: false,
   cover_page: false,
   cover_title: '',
   cover_writer: '',
   cover_image: '',
   cover_image_width: '60%',
+  cover_layout: 'centered',
+  cover_subtitle: '',
+  cover_authors: [],
+  cover_organization: '',
+  cover_version: '',
+  cover_date: '',
+  cover_date_format: 'long',
+  cover_logo: '',
+  cover_background: '',
   number_sections: true,
+  heading_numbering: '1.1',
+  heading_numbering_depth: 6,
+  page_numbering: '1',
+  page_number_format: '{page}',
+  roman_front_matter: false,
+  first_page_number: 1,
   default_image_width: '80%',
   default_image_alignment: 'center',
   fonts: {
@@ -35,13 +81,18 @@ export const defaultPreferences: Preferences = {
   accent_color: '#1e40af',
   line_height: 1.5,
   paragraph_spacing: '0.65em',
+  language: 'en',
+  region: '',
+  typography: defaultTypography,
   page_numbers: false,
   header_title: false,
   header_text: '',
+  header_footer: defaultHeaderFooter,
   render_debounce_ms: 400,
   focused_preview_enabled: false,
   preserve_scroll_position: true,
   confirm_exit_on_unsaved: true,
+  safe_mode: false,
 };
 
 // Preferences-specific store state
@@ -13,6 +13,55 @@ export interface Margins {
 export interface Fonts {
   main: string;
   mono: string;
+  // Ordered fallback families, tried for characters the ones before lack
+  main_fallback?: string[];
+  mono_fallback?: string[];
+  headings?: string[]; // empty => body font
+  cjk?: string[]; // after every role's own families
+  emoji?: string[]; // last in every role
+  math?: string[]; // empty => theme's math font
+}
+
+// Left/center/right text of a header or footer; may contain {page}, {total},
+// {title}, {chapter}, {date} and {author}
+export interface PageSlots {
+  left: string;
+  center: string;
+  right: string;
+}
+
+export interface CoverAuthor {
+  name: string;
+  affiliation: string;
+}
+
+export interface HeaderFooter {
+  header: PageSlots;
+  footer: PageSlots;
+  first_page: 'same' | 'footer' | 'none';
+  mirror_even_pages: boolean; // swap left/right on even pages
+  logo: string;
+  logo_position: 'left' | 'right';
+  logo_height: string;
+  rules: boolean; // thin rule under the header and above the footer
+}
+
+export interface HeadingStyle {
+  size: string; // e.g. "20pt"; empty keeps the theme's
+  weight: string; // "bold", "600", ...; empty keeps the theme's
+}
+
+export interface Typography {
+  justify: boolean | null; // null => theme default
+  hyphenate: boolean | null; // null => automatic
+  first_line_indent: string; // e.g. "1.5em"; empty => none
+  widow_orphan_control: boolean;
+  keep_with_next: boolean; // keep headings with the next paragraph
+  ligatures: boolean;
+  oldstyle_figures: boolean;
+  small_caps_headings: boolean;
+  heading_tracking: string; // letter spacing of headings, e.g. "0.05em"
+  heading_levels: HeadingStyle[]; // levels 1, 2, ...
 }
 
 export interface Preferences {
@@ -22,13 +71,35 @@ export interface Preferences {
   toc: boolean;
   toc_title: string; // empty string => no heading
   toc_two_column?: boolean; // Enable two-column TOC layout
+  toc_depth?: number; // deepest heading level listed (1-6)
+  toc_placement?: 'after_cover' | 'after_abstract' | 'end';
+  toc_leaders?: boolean; // dot leaders before page numbers
+  toc_unnumbered?: boolean; // list unnumbered headings
+  toc_mini?: boolean; // section list below each chapter heading
+  list_of_figures?: boolean; // List of Figures after the TOC
+  list_of_tables?: boolean; // List of Tables after the TOC
   two_column_layout?: boolean; // Enable two-column layout for main content
   cover_page: boolean;
   cover_title: string;
   cover_writer: string;
   cover_image: string;
   cover_image_width: string;
+  cover_layout?: 'centered' | 'left' | 'banner';
+  cover_subtitle?: string;
+  cover_authors?: CoverAuthor[]; // cover_writer is used when empty
+  cover_organization?: string;
+  cover_version?: string;
+  cover_date?: string; // "today", YYYY-MM-DD or any text
+  cover_date_format?: string; // "long", "day-month-year", "numeric", "iso"
+  cover_logo?: string;
+  cover_background?: string; // full-bleed cover background image
   number_sections: boolean;
+  heading_numbering?: string; // Typst pattern: "1.1", "1.a", "I.1", "Chapter 1"
+  heading_numbering_depth?: number; // deepest numbered heading level (1-6)
+  page_numbering?: string; // "1", "i", "I", "a", "A"
+  page_number_format?: string; // e.g. "Page {page} of {total}"
+  roman_front_matter?: boolean; // number TOC/lists i, ii, ...
+  first_page_number?: number; // number of the first main content page
   default_image_width: string;
   default_image_alignment: string;
   fonts: Fonts;
@@ -39,14 +110,19 @@ export interface Preferences {
   accent_color: string;
   line_height: number;
   paragraph_spacing: string;
+  language?: string; // ISO 639 code of the document language, e.g. "de"
+  region?: string; // optional ISO 3166 region, e.g. "CH"
+  typography?: Typography;
   page_numbers: boolean;
   header_title: boolean;
   header_text: string;
+  header_footer?: HeaderFooter; // replaces page_numbers/header_text when any slot is filled
   // Preview optimization settings
   render_debounce_ms: number;
   focused_preview_enabled?: boolean; // kept optional (removed in UI) for backend compatibility
   preserve_scroll_position: boolean;
   confirm_exit_on_unsaved: boolean;
+  safe_mode?: boolean; // Reject raw Typst and files outside the document's folder
   // Optional explicit path to Typst binary (used as a final fallback)
   typst_path?: string;
 }
@@ -86,6 +162,8 @@ export interface PdfAnchorPosition {
 export interface SourceAnchor {
   id: string;
   editor: EditorLocation;
+  // Set when the anchor comes from an included file rather than the open one
+  file?: string;
   pdf?: PdfAnchorPosition;
 }
 
@@ -93,14 +171,39 @@ export interface SourceMap {
   anchors: SourceAnchor[];
 }
 
+// Non-fatal problem reported by the markdown preprocessor (e.g. dangling
+// cross-reference). `line` is zero-based; `file` is set when the problem is
+// in an included file.
+export interface PreprocessWarning {
+  message: string;
+  line?: number;
+  file?: string;
+}
+
+// Result of a mail-merge export (one PDF per data row)
+export interface MailMergeResult {
+  output_dir: string;
+  files: string[];
+  failures: { row: number; error: string }[];
+  warnings: string[];
+}
+
+export interface MailMergeProgress {
+  current: number;
+  total: number;
+  file: string | null;
+}
+
 export interface BackendRenderedDocument {
   pdf_path: string;
   source_map: SourceMap;
+  warnings?: PreprocessWarning[];
 }
 
 export interface RenderedDocument {
   pdfPath: string;
   sourceMap: SourceMap;
+  warnings: PreprocessWarning[];
 }
 
 export type ToastType = 'success' | 'error' | 'warning' | 'info';
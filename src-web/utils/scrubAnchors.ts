@@ -1,25 +1,29 @@
-// Utility to remove raw-typst anchor comments inserted for preview/sync
-// These markers look like: <!--raw-typst #label("...") --> and
-// <!--raw-typst #text(size: 0.001pt)[TFANCHOR:...] --> and similar.
+// Utility to remove the scroll-sync anchors inserted for preview/sync.
+// These markers look exactly like: <!--tf-anchor tf-... --> and, from older
+// versions, <!--raw-typst #text(size: 0.001pt)[TFANCHOR:...] -->.
 // We strip them before saving/exporting so the user-visible markdown remains clean
 // and copying text from the saved file doesn't include invisible tokens.
+//
+// Any other <!--raw-typst ... --> comment is raw Typst the user wrote (or the
+// toolbar inserted) and is kept. Mirrors `strip_anchor_markup` in the
+// preprocessor.
+
+const ANCHOR_MARKUP = /<!--tf-anchor [\w-]+ -->\n?|<!--raw-typst #text\(size: 0\.001pt\)\[TFANCHOR:[^\]]*\] -->\n?/g;
 
 export function scrubRawTypstAnchors(markdown: string): string {
   if (!markdown) return markdown;
+  return markdown.replace(ANCHOR_MARKUP, '');
+}
 
-  // Remove any <!--raw-typst ... --> comments in a robust way.
-  // We allow arbitrary whitespace and attributes inside the marker.
-  // Match sequences like:
-  // <!--raw-typst #label("tf-1") -->
-  // <!--raw-typst #text(size: 0.001pt)[TFANCHOR:tf-1] -->
-  // We remove them entirely. Keep surrounding newlines tidy by collapsing
-  // multiple consecutive newlines into a single newline after removal.
+// For "Export Clean MD": also drop every raw Typst comment and {=typst} block
+// or span, leaving plain Markdown for other tools (GitHub, Pandoc, ...).
+export function stripRawTypst(markdown: string): string {
+  if (!markdown) return markdown;
 
-  // Remove inline raw-typst comments
   const withoutComments = markdown.replace(/<!--\s*raw-typst[\s\S]*?-->/gi, '');
+  const withoutBlocks = withoutComments.replace(/^[ \t]*(`{3,}|~{3,})[ \t]*\{=typst\}[ \t]*\n[\s\S]*?^[ \t]*\1[ \t]*$\n?/gim, '');
+  const withoutSpans = withoutBlocks.replace(/(`+)[^`]+?\1\{=typst\}/gi, '');
 
   // Collapse more than 2 consecutive newlines into just 2 (preserve paragraph spacing)
-  const collapsed = withoutComments.replace(/\n{3,}/g, '\n\n');
-
-  return collapsed;
+  return withoutSpans.replace(/\n{3,}/g, '\n\n');
 }